@@ -0,0 +1,73 @@
+// Derive macros for nvcore
+//
+// The generated code refers to the library as `::nvcore`, enable its
+// `derive` feature rather than depending on this crate directly.
+
+mod nvsql;
+mod table;
+mod validate;
+
+use proc_macro::TokenStream;
+use syn::meta::ParseNestedMeta;
+use syn::{parenthesized, parse_macro_input, DeriveInput, Expr, Result, Token};
+
+// Field keys of `#[nv(...)]` read by each derive, a derive skips the keys of
+// the other so both can be used on the same struct
+pub(crate) const TABLE_KEYS: &[&str] = &["column", "skip"];
+pub(crate) const VALIDATE_KEYS: &[&str] = &[
+    "rename",
+    "required",
+    "each",
+    "nested",
+    "not_empty",
+    "email",
+    "length",
+    "range",
+    "regex",
+    "custom",
+    "message",
+];
+
+// Implements nvcore::validator::NvValidate from `#[nv(...)]` field
+// attributes, see validate.rs for the attributes
+#[proc_macro_derive(NvValidate, attributes(nv))]
+pub fn derive_nv_validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    validate::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Implements nvcore::sqlbuilder::Table and NvTable for an entity struct, see
+// table.rs for the attributes
+#[proc_macro_derive(NvTable, attributes(nv))]
+pub fn derive_nv_table(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    table::expand(&input)
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Builds an NvSelect from SQL with #{expr} parameters, see nvsql.rs
+#[proc_macro]
+pub fn nvsql(input: TokenStream) -> TokenStream {
+    nvsql::expand(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+// Consumes the meta when its key is one of `keys`, with its value or its
+// parenthesized arguments
+pub(crate) fn skip_keys(meta: &ParseNestedMeta, keys: &[&str]) -> Result<bool> {
+    if !keys.iter().any(|key| meta.path.is_ident(key)) {
+        return Ok(false);
+    }
+    if meta.input.peek(Token![=]) {
+        meta.value()?.parse::<Expr>()?;
+    } else if meta.input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in meta.input);
+        content.parse::<proc_macro2::TokenStream>()?;
+    }
+    Ok(true)
+}
@@ -0,0 +1,493 @@
+// nvsql!
+//
+// Builds an NvSelect from SQL written in the macro, parsed when the crate
+// compiles so a syntax error is a compile error pointing at the token:
+//
+//     let select = nvsql!(DatabaseDialect::PostgreSQL,
+//         SELECT u.id, u.name AS user_name, COUNT(*) AS total
+//         FROM users u
+//         WHERE u.age >= #{min_age} AND (u.name LIKE #{pattern} OR u.id IN #{ids})
+//         ORDER BY u.id DESC
+//         LIMIT 20 OFFSET #{offset}
+//     );
+//
+// `#{expr}` values and number or boolean literals in WHERE are bound
+// parameters, converted with Into into the parameter type of the select.
+// `IN #{expr}` binds every item of an iterable. LIMIT and OFFSET take an
+// integer literal or a u64 expression, NvSelect writes them inline.
+//
+// Select items are columns, `*`, `t.*` and COUNT, SUM, AVG, MIN, MAX,
+// COUNT(DISTINCT ..), UPPER and LOWER of a column. Conditions compare a
+// column with =, <>, !=, <, <=, >, >=, LIKE, ILIKE or REGEXP, or use
+// BETWEEN and IN, joined by AND, OR and parentheses. Keywords are matched
+// ignoring case. What NvSelect cannot build, such as JOIN, GROUP BY, NOT or
+// IS NULL, is rejected. Text values must be interpolated since Rust has no
+// single-quoted strings.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::ext::IdentExt;
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parenthesized, Expr, Ident, Lit, LitInt, Result, Token};
+
+const RESERVED: &[&str] = &[
+    "SELECT", "FROM", "WHERE", "AND", "OR", "NOT", "ORDER", "BY", "ASC", "DESC", "LIMIT", "OFFSET",
+    "AS", "IN", "BETWEEN", "LIKE", "ILIKE", "REGEXP", "IS", "NULL", "DISTINCT", "JOIN", "INNER",
+    "LEFT", "RIGHT", "FULL", "CROSS", "ON", "GROUP", "HAVING", "UNION",
+];
+
+pub fn expand(input: TokenStream) -> Result<TokenStream> {
+    syn::parse2::<Query>(input).map(|query| query.tokens)
+}
+
+struct Query {
+    tokens: TokenStream,
+}
+
+impl Parse for Query {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let dialect: Expr = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        keyword(input, "SELECT")?;
+        let mut calls = vec![parse_item(input)?];
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            calls.push(parse_item(input)?);
+        }
+
+        keyword(input, "FROM")?;
+        let mut tables = vec![parse_table(input)?];
+        while input.peek(Token![,]) {
+            input.parse::<Token![,]>()?;
+            tables.push(parse_table(input)?);
+        }
+        calls.push(quote!(.from() #(#tables)* .end_from_table_block()));
+
+        if eat_keyword(input, "WHERE")? {
+            let conditions = parse_conditions(input)?;
+            calls.push(quote!(.where_clause() #conditions .end_where_block()));
+        }
+
+        let ordered = peek_keyword(input, "ORDER");
+        if eat_keyword(input, "ORDER")? {
+            keyword(input, "BY")?;
+            let mut sorts = vec![parse_sort(input)?];
+            while input.peek(Token![,]) {
+                input.parse::<Token![,]>()?;
+                sorts.push(parse_sort(input)?);
+            }
+            calls.push(quote!(.order_by() #(#sorts)* .end_order_by_block()));
+        }
+
+        if eat_keyword(input, "LIMIT")? {
+            let limit = parse_paging(input)?;
+            calls.push(quote!(.limit(#limit)));
+        }
+        if peek_keyword(input, "OFFSET") {
+            // The dialect is only known at run time, requiring ORDER BY here
+            // means the offset call below cannot fail
+            if !ordered {
+                return Err(input.error("OFFSET requires ORDER BY"));
+            }
+            input.call(Ident::parse_any)?;
+            let offset = parse_paging(input)?;
+            calls.push(quote!(.offset(#offset).expect("ORDER BY is set before OFFSET")));
+        }
+
+        if !input.is_empty() {
+            return Err(unexpected(input));
+        }
+        Ok(Query {
+            tokens: quote!(::nvcore::sqlbuilder::NvSelect::new(#dialect) #(#calls)*),
+        })
+    }
+}
+
+fn parse_item(input: ParseStream) -> Result<TokenStream> {
+    if input.peek(Token![*]) {
+        input.parse::<Token![*]>()?;
+        return Ok(field(None, "*".to_string(), None, quote!(None)));
+    }
+
+    if input.peek(Ident::peek_any) && input.peek2(syn::token::Paren) {
+        let function = input.call(Ident::parse_any)?;
+        let content;
+        parenthesized!(content in input);
+        let distinct = eat_keyword(&content, "DISTINCT")?;
+        let aggregate = match (function.to_string().to_uppercase().as_str(), distinct) {
+            ("COUNT", false) => quote!(Count),
+            ("COUNT", true) => quote!(CountDistinct),
+            ("SUM", false) => quote!(Sum),
+            ("AVG", false) => quote!(Avg),
+            ("MIN", false) => quote!(Min),
+            ("MAX", false) => quote!(Max),
+            ("UPPER", false) => quote!(ToUpper),
+            ("LOWER", false) => quote!(ToLower),
+            _ => {
+                return Err(syn::Error::new(
+                    function.span(),
+                    format!("function {} is not supported by nvsql!", function),
+                ))
+            }
+        };
+        let (table, column) = if content.peek(Token![*]) && !distinct {
+            content.parse::<Token![*]>()?;
+            (None, "*".to_string())
+        } else {
+            parse_column(&content)?
+        };
+        if !content.is_empty() {
+            return Err(content.error("expected `)`"));
+        }
+        let alias = parse_alias(input)?;
+        return Ok(field(table, column, alias, aggregate));
+    }
+
+    let (table, column) = parse_column(input)?;
+    let alias = parse_alias(input)?;
+    Ok(field(table, column, alias, quote!(None)))
+}
+
+fn field(
+    table: Option<String>,
+    column: String,
+    alias: Option<String>,
+    aggregate: TokenStream,
+) -> TokenStream {
+    let table = optional_string(table);
+    let alias = optional_string(alias);
+    quote! {
+        .f(
+            ::std::string::ToString::to_string(#column),
+            #table,
+            #alias,
+            ::nvcore::sqlbuilder::SqlAggregateFunction::#aggregate,
+            false,
+        )
+    }
+}
+
+fn parse_table(input: ParseStream) -> Result<TokenStream> {
+    let mut table = name(input)?;
+    if input.peek(Token![.]) {
+        input.parse::<Token![.]>()?;
+        table = format!("{}.{}", table, name(input)?);
+    }
+    let alias = optional_string(parse_alias(input)?);
+    Ok(quote!(.add_table_with_alias(#table, &#alias)))
+}
+
+// Conditions joined by AND and OR, kept in the order they are written as
+// WhereStatement renders them
+fn parse_conditions(input: ParseStream) -> Result<TokenStream> {
+    let mut calls = vec![parse_condition(input)?];
+    loop {
+        if eat_keyword(input, "AND")? {
+            calls.push(quote!(.and()));
+        } else if eat_keyword(input, "OR")? {
+            calls.push(quote!(.or()));
+        } else {
+            break;
+        }
+        calls.push(parse_condition(input)?);
+    }
+    Ok(quote!(#(#calls)*))
+}
+
+fn parse_condition(input: ParseStream) -> Result<TokenStream> {
+    if input.peek(syn::token::Paren) {
+        let content;
+        parenthesized!(content in input);
+        let conditions = parse_conditions(&content)?;
+        if !content.is_empty() {
+            return Err(unexpected(&content));
+        }
+        return Ok(quote!(.start_group() #conditions .end_group()));
+    }
+
+    let (table, column) = parse_column(input)?;
+    let column = match table {
+        Some(table) => format!("{}.{}", table, column),
+        None => column,
+    };
+
+    if eat_keyword(input, "BETWEEN")? {
+        let low = parse_value(input)?;
+        keyword(input, "AND")?;
+        let high = parse_value(input)?;
+        return Ok(quote!(.add_condition_between(#column, #low, #high)));
+    }
+
+    if eat_keyword(input, "IN")? {
+        if input.peek(Token![#]) {
+            let expr = parse_interpolation(input)?;
+            return Ok(quote! {
+                .add_condition_in(
+                    #column,
+                    &::core::iter::Iterator::collect::<::std::vec::Vec<_>>(
+                        ::core::iter::Iterator::map(
+                            ::core::iter::IntoIterator::into_iter(#expr),
+                            ::core::convert::Into::into,
+                        ),
+                    ),
+                )
+            });
+        }
+        let content;
+        parenthesized!(content in input);
+        let values = content.parse_terminated(parse_value, Token![,])?;
+        if values.is_empty() {
+            return Err(content.error("IN needs at least one value"));
+        }
+        let values = values.iter();
+        return Ok(quote!(.add_condition_in(#column, &[#(#values),*])));
+    }
+
+    // parse_operator yields single value comparisons only, so the
+    // condition cannot be rejected
+    let operator = parse_operator(input)?;
+    let value = parse_value(input)?;
+    Ok(quote! {
+        .add_condition(#column, &::nvcore::sqlbuilder::SqlOperator::#operator, #value)
+        .expect("single value comparison")
+    })
+}
+
+fn parse_operator(input: ParseStream) -> Result<TokenStream> {
+    let operator = if input.peek(Token![<=]) {
+        input.parse::<Token![<=]>()?;
+        quote!(LessOrEqual)
+    } else if input.peek(Token![>=]) {
+        input.parse::<Token![>=]>()?;
+        quote!(GreaterOrEqual)
+    } else if input.peek(Token![!=]) {
+        input.parse::<Token![!=]>()?;
+        quote!(NotEqual)
+    } else if input.peek(Token![<]) && input.peek2(Token![>]) {
+        input.parse::<Token![<]>()?;
+        input.parse::<Token![>]>()?;
+        quote!(NotEqual)
+    } else if input.peek(Token![<]) {
+        input.parse::<Token![<]>()?;
+        quote!(Less)
+    } else if input.peek(Token![>]) {
+        input.parse::<Token![>]>()?;
+        quote!(Greater)
+    } else if input.peek(Token![=]) {
+        input.parse::<Token![=]>()?;
+        quote!(Equal)
+    } else if eat_keyword(input, "LIKE")? {
+        quote!(Like)
+    } else if eat_keyword(input, "ILIKE")? {
+        quote!(ILike)
+    } else if eat_keyword(input, "REGEXP")? {
+        quote!(Regexp)
+    } else if peek_keyword(input, "IS") || peek_keyword(input, "NOT") {
+        return Err(unexpected(input));
+    } else {
+        return Err(input.error("expected a comparison operator"));
+    };
+    Ok(operator)
+}
+
+// A bound parameter
+fn parse_value(input: ParseStream) -> Result<TokenStream> {
+    if input.peek(Token![#]) {
+        let expr = parse_interpolation(input)?;
+        return Ok(quote!(::core::convert::Into::into(#expr)));
+    }
+    if eat_keyword(input, "TRUE")? {
+        return Ok(quote!(::core::convert::Into::into(true)));
+    }
+    if eat_keyword(input, "FALSE")? {
+        return Ok(quote!(::core::convert::Into::into(false)));
+    }
+
+    let minus = if input.peek(Token![-]) {
+        Some(input.parse::<Token![-]>()?)
+    } else {
+        None
+    };
+    match input.parse::<Lit>()? {
+        Lit::Int(value) => Ok(quote!(::core::convert::Into::into(#minus #value))),
+        Lit::Float(value) => Ok(quote!(::core::convert::Into::into(#minus #value))),
+        Lit::Str(value) => Err(syn::Error::new(
+            value.span(),
+            "text values must be interpolated with #{..}",
+        )),
+        value => Err(syn::Error::new(value.span(), "unsupported literal")),
+    }
+}
+
+fn parse_interpolation(input: ParseStream) -> Result<Expr> {
+    input.parse::<Token![#]>()?;
+    let content;
+    braced!(content in input);
+    content.parse()
+}
+
+fn parse_sort(input: ParseStream) -> Result<TokenStream> {
+    let (table, column) = parse_column(input)?;
+    let table = optional_string(table);
+    let (sort, explicit) = if eat_keyword(input, "ASC")? {
+        (quote!(Ascending), true)
+    } else if eat_keyword(input, "DESC")? {
+        (quote!(Descending), true)
+    } else {
+        (quote!(Ascending), false)
+    };
+    Ok(quote! {
+        .by(
+            ::std::string::ToString::to_string(#column),
+            #table,
+            ::nvcore::sqlbuilder::SortType::#sort,
+            #explicit,
+        )
+    })
+}
+
+fn parse_paging(input: ParseStream) -> Result<TokenStream> {
+    if input.peek(Token![#]) {
+        let expr = parse_interpolation(input)?;
+        return Ok(quote!(#expr));
+    }
+    let value: LitInt = input.parse()?;
+    value.base10_parse::<u64>()?;
+    Ok(quote!(#value))
+}
+
+// `column` or `table.column`, `table.*` as well
+fn parse_column(input: ParseStream) -> Result<(Option<String>, String)> {
+    let first = name(input)?;
+    if !input.peek(Token![.]) {
+        return Ok((None, first));
+    }
+    input.parse::<Token![.]>()?;
+    if input.peek(Token![*]) {
+        input.parse::<Token![*]>()?;
+        return Ok((Some(first), "*".to_string()));
+    }
+    Ok((Some(first), name(input)?))
+}
+
+fn parse_alias(input: ParseStream) -> Result<Option<String>> {
+    if eat_keyword(input, "AS")? || (input.peek(Ident::peek_any) && !peek_reserved(input)) {
+        return name(input).map(Some);
+    }
+    Ok(None)
+}
+
+fn name(input: ParseStream) -> Result<String> {
+    let ident = input.call(Ident::parse_any)?;
+    if is_reserved(&ident) {
+        return Err(syn::Error::new(
+            ident.span(),
+            format!("expected a name, found {}", ident),
+        ));
+    }
+    Ok(ident.unraw().to_string())
+}
+
+fn optional_string(value: Option<String>) -> TokenStream {
+    match value {
+        Some(value) => {
+            quote!(::core::option::Option::Some(::std::string::ToString::to_string(#value)))
+        }
+        None => quote!(::core::option::Option::None),
+    }
+}
+
+fn is_reserved(ident: &Ident) -> bool {
+    let ident = ident.to_string();
+    RESERVED
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(&ident))
+}
+
+fn peek_reserved(input: ParseStream) -> bool {
+    input
+        .fork()
+        .call(Ident::parse_any)
+        .is_ok_and(|ident| is_reserved(&ident))
+}
+
+fn peek_keyword(input: ParseStream, keyword: &str) -> bool {
+    input
+        .fork()
+        .call(Ident::parse_any)
+        .is_ok_and(|ident| ident.to_string().eq_ignore_ascii_case(keyword))
+}
+
+fn eat_keyword(input: ParseStream, keyword: &str) -> Result<bool> {
+    if !peek_keyword(input, keyword) {
+        return Ok(false);
+    }
+    input.call(Ident::parse_any)?;
+    Ok(true)
+}
+
+fn keyword(input: ParseStream, keyword: &str) -> Result<()> {
+    if !eat_keyword(input, keyword)? {
+        return Err(input.error(format!("expected {}", keyword)));
+    }
+    Ok(())
+}
+
+// Names the keyword NvSelect has no builder for
+fn unexpected(input: ParseStream) -> syn::Error {
+    match input.fork().call(Ident::parse_any) {
+        Ok(ident) if is_reserved(&ident) => syn::Error::new(
+            ident.span(),
+            format!(
+                "{} is not supported by nvsql!",
+                ident.to_string().to_uppercase()
+            ),
+        ),
+        _ => input.error("unexpected token"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: TokenStream) -> String {
+        expand(input).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn test_syntax_errors() {
+        assert_eq!(
+            error(quote!(dialect, SELECT id users)),
+            "unexpected end of input, expected FROM"
+        );
+        assert_eq!(
+            error(quote!(dialect, SELECT id FROM users u JOIN teams t ON t.id = u.team_id)),
+            "JOIN is not supported by nvsql!"
+        );
+        assert_eq!(
+            error(quote!(dialect, SELECT id FROM users WHERE name = "ada")),
+            "text values must be interpolated with #{..}"
+        );
+        assert_eq!(
+            error(quote!(dialect, SELECT id FROM users WHERE deleted is null)),
+            "IS is not supported by nvsql!"
+        );
+        assert_eq!(
+            error(quote!(dialect, SELECT id FROM users WHERE id #{id})),
+            "expected a comparison operator"
+        );
+        assert_eq!(
+            error(quote!(dialect, SELECT md5(id) FROM users)),
+            "function md5 is not supported by nvsql!"
+        );
+        assert!(expand(quote!(
+            dialect,
+            select u.id, count(*) as total from users u
+            where u.id in (1, 2) and u.name like #{pattern}
+            order by u.id desc limit #{limit}
+        ))
+        .is_ok());
+    }
+}
@@ -0,0 +1,214 @@
+// #[derive(NvTable)]
+//
+// Maps a struct with named fields to a table, every field being a column:
+//
+//     #[derive(NvTable)]
+//     #[nv(table = "users")]
+//     struct User {
+//         id: i64,
+//         #[nv(column = "email_address")]
+//         email: String,
+//         nickname: Option<String>,
+//         #[nv(skip)]
+//         posts: Vec<Post>,
+//     }
+//
+// implements Table, NvTable and FromRow and adds a Column constant per column named
+// after the field in upper case, User::EMAIL is Column<String> for
+// users.email_address. Option fields get a column of the inner type. A
+// field called `name` gives a NAME column, the table name is then
+// <User as Table>::NAME.
+//
+// The table defaults to the struct name in snake case and a column to the
+// field name. Columns are selected under the name of their field, which is
+// how rows are mapped back, skipped fields are filled with Default. The
+// keys of NvValidate are skipped.
+
+use crate::validate::Shape;
+use proc_macro2::TokenStream;
+use quote::{format_ident, quote};
+use syn::{Data, DeriveInput, Fields, LitStr, Result};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "NvTable supports structs with named fields only",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "NvTable supports structs only",
+            ))
+        }
+    };
+
+    let name = &input.ident;
+    let mut table = snake_case(&name.to_string());
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("nv")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("table") {
+                table = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("expected `table = \"..\"`"))
+            }
+        })?;
+    }
+
+    let mut constants = Vec::new();
+    let mut columns = Vec::new();
+    let mut mapped = Vec::new();
+    let mut inits = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let field_name = ident.to_string().trim_start_matches("r#").to_string();
+        let mut column = field_name.clone();
+        let mut skip = false;
+        for attr in field.attrs.iter().filter(|a| a.path().is_ident("nv")) {
+            attr.parse_nested_meta(|meta| {
+                if crate::skip_keys(&meta, crate::VALIDATE_KEYS)? {
+                    return Ok(());
+                }
+                if meta.path.is_ident("column") {
+                    column = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("skip") {
+                    skip = true;
+                } else {
+                    let key = meta.path.get_ident().map(|i| i.to_string());
+                    return Err(meta.error(format!(
+                        "unknown nv attribute `{}`",
+                        key.unwrap_or_default()
+                    )));
+                }
+                Ok(())
+            })?;
+        }
+
+        if skip {
+            inits.push(quote!(#ident: ::core::default::Default::default()));
+            continue;
+        }
+
+        let vis = &field.vis;
+        let constant = format_ident!("{}", field_name.to_uppercase());
+        let ty = match Shape::of(&field.ty) {
+            Shape::Option(inner) => inner,
+            _ => &field.ty,
+        };
+        constants.push(quote! {
+            #vis const #constant: ::nvcore::sqlbuilder::Column<#ty> =
+                ::nvcore::sqlbuilder::Column::new(#table, #column);
+        });
+        inits.push(quote!(#ident: ::nvcore::row::Row::get(row, #field_name)?));
+        columns.push(column.clone());
+        mapped.push(quote!((#column, #field_name)));
+    }
+
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #type_generics #where_clause {
+            #(#constants)*
+        }
+
+        impl #impl_generics ::nvcore::sqlbuilder::Table for #name #type_generics #where_clause {
+            const NAME: &'static str = #table;
+
+            fn columns() -> &'static [&'static str] {
+                &[#(#columns),*]
+            }
+        }
+
+        impl #impl_generics ::nvcore::sqlbuilder::NvTable for #name #type_generics #where_clause {
+            fn fields() -> &'static [(&'static str, &'static str)] {
+                &[#(#mapped),*]
+            }
+        }
+
+        impl #impl_generics ::nvcore::row::FromRow for #name #type_generics #where_clause {
+            fn from_row<R: ::nvcore::row::Row>(
+                row: &R,
+                _columns: &[::std::string::String],
+            ) -> ::core::result::Result<Self, ::nvcore::row::RowError> {
+                ::core::result::Result::Ok(Self { #(#inits),* })
+            }
+        }
+    })
+}
+
+// UserProfile becomes user_profile
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() {
+            if i > 0 {
+                snake.push('_');
+            }
+            snake.extend(c.to_lowercase());
+        } else {
+            snake.push(c);
+        }
+    }
+    snake
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: TokenStream) -> String {
+        expand(&syn::parse2(input).unwrap())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_table_attributes() {
+        assert_eq!(snake_case("UserProfile"), "user_profile");
+        assert_eq!(
+            error(quote!(
+                enum E {
+                    A,
+                }
+            )),
+            "NvTable supports structs only"
+        );
+        assert_eq!(
+            error(quote!(
+                #[nv(name = "users")]
+                struct User {
+                    id: i64,
+                }
+            )),
+            "expected `table = \"..\"`"
+        );
+        assert_eq!(
+            error(quote!(
+                struct User {
+                    #[nv(primary_key)]
+                    id: i64,
+                }
+            )),
+            "unknown nv attribute `primary_key`"
+        );
+
+        // Validation keys are left to NvValidate
+        let tokens = expand(
+            &syn::parse2(quote!(
+                struct User {
+                    #[nv(length(min = 1, max = 50), column = "user_name")]
+                    name: String,
+                }
+            ))
+            .unwrap(),
+        )
+        .unwrap()
+        .to_string();
+        assert!(tokens.contains("\"user_name\" , \"name\""));
+    }
+}
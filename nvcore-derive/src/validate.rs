@@ -0,0 +1,443 @@
+// #[derive(NvValidate)]
+//
+// Every field with an `#[nv(...)]` attribute becomes a rule of the generated
+// validator, in field order:
+//
+//     #[derive(NvValidate)]
+//     struct User {
+//         #[nv(length(min = 1, max = 50))]
+//         name: String,
+//         #[nv(required, email)]
+//         email: Option<String>,
+//         #[nv(range(0..=120))]
+//         age: u8,
+//         #[nv(length(max = 5), each(not_empty, regex = "^[a-z]+$"))]
+//         tags: Vec<String>,
+//         #[nv(nested)]
+//         address: Address,
+//     }
+//
+// Checks: not_empty, email, length(min = .., max = ..), range(a..=b),
+// range(min = .., max = ..), regex = "..", custom = path and nested.
+// `message = ".."` replaces the message of the check before it, `rename`
+// sets the name used in error paths.
+//
+// Option<X> fields are checked only when Some unless `required` is given.
+// On Vec<X> fields the checks apply to the collection, `each(...)` holds the
+// checks of the elements and `nested` validates every element. Fields are
+// recognised by the last segment of their type path, aliases of Option or
+// Vec are treated as plain values. The keys of NvTable are skipped.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::meta::ParseNestedMeta;
+use syn::spanned::Spanned;
+use syn::{
+    parenthesized, Data, DeriveInput, Expr, ExprLit, ExprRange, Fields, GenericArgument, Ident,
+    Lit, LitStr, Path, PathArguments, RangeLimits, Result, Token, Type,
+};
+
+pub fn expand(input: &DeriveInput) -> Result<TokenStream> {
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "NvValidate supports structs with named fields only",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "NvValidate supports structs only",
+            ))
+        }
+    };
+
+    let mut rules = Vec::new();
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        if let Some(spec) = FieldSpec::parse(ident, &field.ty, &field.attrs)? {
+            rules.extend(spec.rules(ident));
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+    // The validator is built once per type, a static cannot name generic
+    // parameters so generic structs build it on every call
+    let cached = input.generics.params.is_empty().then(|| {
+        quote! {
+            fn run_validator<R>(
+                run: impl FnOnce(&::nvcore::validator::Validator<Self>) -> R,
+            ) -> R {
+                static VALIDATOR: ::std::sync::OnceLock<::nvcore::validator::Validator<#name>> =
+                    ::std::sync::OnceLock::new();
+                run(VALIDATOR.get_or_init(<#name as ::nvcore::validator::NvValidate>::validator))
+            }
+        }
+    });
+    Ok(quote! {
+        impl #impl_generics ::nvcore::validator::NvValidate for #name #type_generics #where_clause {
+            fn validator() -> ::nvcore::validator::Validator<Self> {
+                let validator = ::nvcore::validator::Validator::<Self>::new();
+                #(let validator = validator #rules .build();)*
+                validator
+            }
+
+            #cached
+        }
+    })
+}
+
+pub(crate) enum Shape<'a> {
+    Plain(&'a Type),
+    Option(&'a Type),
+    Vec(&'a Type),
+}
+
+impl<'a> Shape<'a> {
+    pub(crate) fn of(ty: &'a Type) -> Self {
+        if let Type::Path(path) = ty {
+            if let Some(segment) = path.path.segments.last() {
+                if let PathArguments::AngleBracketed(args) = &segment.arguments {
+                    if let (1, Some(GenericArgument::Type(inner))) =
+                        (args.args.len(), args.args.first())
+                    {
+                        if segment.ident == "Option" {
+                            return Shape::Option(inner);
+                        }
+                        if segment.ident == "Vec" {
+                            return Shape::Vec(inner);
+                        }
+                    }
+                }
+            }
+        }
+        Shape::Plain(ty)
+    }
+}
+
+// Builder calls on one value, `nested` is kept apart so it runs after the
+// checks of the same value
+#[derive(Default)]
+struct Checks {
+    calls: Vec<TokenStream>,
+    nested: bool,
+}
+
+struct FieldSpec<'a> {
+    name: String,
+    shape: Shape<'a>,
+    required: bool,
+    checks: Checks,
+    each: Checks,
+}
+
+impl<'a> FieldSpec<'a> {
+    fn parse(ident: &Ident, ty: &'a Type, attrs: &[syn::Attribute]) -> Result<Option<Self>> {
+        let attrs: Vec<_> = attrs.iter().filter(|a| a.path().is_ident("nv")).collect();
+        if attrs.is_empty() {
+            return Ok(None);
+        }
+
+        let mut spec = FieldSpec {
+            name: ident.to_string().trim_start_matches("r#").to_string(),
+            shape: Shape::of(ty),
+            required: false,
+            checks: Checks::default(),
+            each: Checks::default(),
+        };
+        for attr in attrs {
+            attr.parse_nested_meta(|meta| {
+                if crate::skip_keys(&meta, crate::TABLE_KEYS)? {
+                    return Ok(());
+                }
+                if meta.path.is_ident("rename") {
+                    spec.name = meta.value()?.parse::<LitStr>()?.value();
+                } else if meta.path.is_ident("required") {
+                    if !matches!(spec.shape, Shape::Option(_)) {
+                        return Err(meta.error("required applies to Option fields"));
+                    }
+                    spec.required = true;
+                } else if meta.path.is_ident("each") {
+                    if !matches!(spec.shape, Shape::Vec(_)) {
+                        return Err(meta.error("each(...) applies to Vec fields"));
+                    }
+                    meta.parse_nested_meta(|meta| parse_check(&meta, &mut spec.each))?;
+                } else if meta.path.is_ident("nested") && matches!(spec.shape, Shape::Vec(_)) {
+                    spec.each.nested = true;
+                } else {
+                    parse_check(&meta, &mut spec.checks)?;
+                }
+                Ok(())
+            })?;
+        }
+        let empty = !spec.required && spec.checks.is_empty() && spec.each.is_empty();
+        Ok((!empty).then_some(spec))
+    }
+
+    // Builder chains, each one starting a rule on the validator
+    fn rules(&self, ident: &Ident) -> Vec<TokenStream> {
+        let name = &self.name;
+        let mut rules = Vec::new();
+        match self.shape {
+            Shape::Plain(ty) => {
+                let calls = self.checks.chain(ty);
+                rules.push(quote!(.rule_for(#name, |value| &value.#ident) #calls));
+            }
+            Shape::Option(inner) => {
+                let required = self.required.then(|| quote!(.required()));
+                let calls = self.checks.chain(inner);
+                rules.push(quote!(.rule_for_option(#name, |value| &value.#ident) #required #calls));
+            }
+            Shape::Vec(inner) => {
+                if !self.checks.calls.is_empty() {
+                    let calls = &self.checks.calls;
+                    rules.push(quote!(.rule_for(#name, |value| &value.#ident) #(#calls)*));
+                }
+                if !self.each.calls.is_empty() || self.each.nested {
+                    let calls = self.each.chain(inner);
+                    rules.push(
+                        quote!(.rule_for_each(#name, |value| value.#ident.as_slice()) #calls),
+                    );
+                }
+            }
+        }
+        rules
+    }
+}
+
+impl Checks {
+    fn is_empty(&self) -> bool {
+        self.calls.is_empty() && !self.nested
+    }
+
+    fn chain(&self, ty: &Type) -> TokenStream {
+        let calls = &self.calls;
+        let nested = self
+            .nested
+            .then(|| quote!(.set_validator(<#ty as ::nvcore::validator::NvValidate>::validator())));
+        quote!(#(#calls)* #nested)
+    }
+}
+
+fn parse_check(meta: &ParseNestedMeta, checks: &mut Checks) -> Result<()> {
+    let path = &meta.path;
+    let call = if path.is_ident("not_empty") {
+        quote!(.not_empty())
+    } else if path.is_ident("email") {
+        quote!(.email())
+    } else if path.is_ident("nested") {
+        checks.nested = true;
+        return Ok(());
+    } else if path.is_ident("length") {
+        parse_length(meta)?
+    } else if path.is_ident("range") {
+        parse_range(meta)?
+    } else if path.is_ident("regex") {
+        let pattern: LitStr = meta.value()?.parse()?;
+        if let Err(e) = regex::Regex::new(&pattern.value()) {
+            return Err(syn::Error::new(
+                pattern.span(),
+                format!("invalid regex: {}", e),
+            ));
+        }
+        quote!(.matches(#pattern))
+    } else if path.is_ident("custom") {
+        // The code of the check is the name of the function, called through a
+        // closure so that &String reaches a function taking &str
+        let function: Path = meta.value()?.parse()?;
+        let code = function
+            .segments
+            .last()
+            .map(|s| s.ident.to_string())
+            .unwrap_or_default();
+        quote!(.must(#code, |value| #function(value)))
+    } else if path.is_ident("message") {
+        let message: LitStr = meta.value()?.parse()?;
+        if checks.calls.is_empty() {
+            return Err(meta.error("message must follow a check"));
+        }
+        quote!(.with_message(#message))
+    } else {
+        let name = quote!(#path).to_string().replace(' ', "");
+        return Err(meta.error(format!("unknown nv attribute `{}`", name)));
+    };
+    checks.calls.push(call);
+    Ok(())
+}
+
+// length(min = a, max = b), either bound may be left out
+fn parse_length(meta: &ParseNestedMeta) -> Result<TokenStream> {
+    let (min, max) = parse_bounds(meta)?;
+    if let (Some(min), Some(max)) = (&min, &max) {
+        if let (Some(a), Some(b)) = (int_value(min), int_value(max)) {
+            if a > b {
+                return Err(meta.error("length has min greater than max"));
+            }
+        }
+    }
+    match (min, max) {
+        (Some(min), Some(max)) => Ok(quote!(.length(#min, #max))),
+        (Some(min), None) => Ok(quote!(.min_len(#min))),
+        (None, Some(max)) => Ok(quote!(.max_len(#max))),
+        (None, None) => Err(meta.error("length needs min, max or both")),
+    }
+}
+
+// range(a..=b), range(a..b), range(a..), range(..=b) or
+// range(min = a, max = b) where the bounds are inclusive
+fn parse_range(meta: &ParseNestedMeta) -> Result<TokenStream> {
+    let content;
+    parenthesized!(content in meta.input);
+    let (min, max, exclusive_max) = if content.peek(Ident) && content.peek2(Token![=]) {
+        let (min, max) = bounds(meta, &content)?;
+        (min, max, false)
+    } else {
+        match content.parse::<Expr>()? {
+            Expr::Range(ExprRange {
+                start, limits, end, ..
+            }) => (
+                start.map(|e| *e),
+                end.map(|e| *e),
+                matches!(limits, RangeLimits::HalfOpen(_)),
+            ),
+            other => {
+                return Err(syn::Error::new(
+                    other.span(),
+                    "expected a range such as 0..=120",
+                ))
+            }
+        }
+    };
+
+    match (min, max, exclusive_max) {
+        (Some(min), Some(max), false) => Ok(quote!(.range(#min..=#max))),
+        (Some(min), Some(max), true) => Ok(quote!(.min(#min).less_than(#max))),
+        (Some(min), None, _) => Ok(quote!(.min(#min))),
+        (None, Some(max), false) => Ok(quote!(.max(#max))),
+        (None, Some(max), true) => Ok(quote!(.less_than(#max))),
+        (None, None, _) => Err(meta.error("range needs a lower bound, an upper bound or both")),
+    }
+}
+
+fn parse_bounds(meta: &ParseNestedMeta) -> Result<(Option<Expr>, Option<Expr>)> {
+    let content;
+    parenthesized!(content in meta.input);
+    bounds(meta, &content)
+}
+
+fn bounds(
+    meta: &ParseNestedMeta,
+    content: &syn::parse::ParseBuffer,
+) -> Result<(Option<Expr>, Option<Expr>)> {
+    let (mut min, mut max) = (None, None);
+    while !content.is_empty() {
+        let key: Ident = content.parse()?;
+        content.parse::<Token![=]>()?;
+        let value: Expr = content.parse()?;
+        match key.to_string().as_str() {
+            "min" => min = Some(value),
+            "max" => max = Some(value),
+            _ => {
+                return Err(syn::Error::new(
+                    key.span(),
+                    format!("unknown bound `{}`, expected min or max", key),
+                ))
+            }
+        }
+        if !content.is_empty() {
+            content.parse::<Token![,]>()?;
+        }
+    }
+    if min.is_none() && max.is_none() {
+        return Err(meta.error("expected min, max or both"));
+    }
+    Ok((min, max))
+}
+
+fn int_value(expr: &Expr) -> Option<u128> {
+    match expr {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(value),
+            ..
+        }) => value.base10_parse().ok(),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(input: TokenStream) -> String {
+        expand(&syn::parse2(input).unwrap())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_attribute_errors() {
+        assert_eq!(
+            error(quote!(
+                struct Id(u32);
+            )),
+            "NvValidate supports structs with named fields only"
+        );
+        assert_eq!(
+            error(quote!(
+                struct S {
+                    #[nv(required)]
+                    name: String,
+                }
+            )),
+            "required applies to Option fields"
+        );
+        assert_eq!(
+            error(quote!(
+                struct S {
+                    #[nv(length(min = 5, max = 1))]
+                    name: String,
+                }
+            )),
+            "length has min greater than max"
+        );
+        assert!(error(quote!(
+            struct S {
+                #[nv(regex = "(")]
+                name: String,
+            }
+        ))
+        .starts_with("invalid regex"));
+        assert_eq!(
+            error(quote!(
+                struct S {
+                    #[nv(message = "x", email)]
+                    email: String,
+                }
+            )),
+            "message must follow a check"
+        );
+        assert_eq!(
+            error(quote!(
+                struct S {
+                    #[nv(each(email))]
+                    email: String,
+                }
+            )),
+            "each(...) applies to Vec fields"
+        );
+        assert_eq!(
+            error(quote!(
+                struct S {
+                    #[nv(uuid)]
+                    id: String,
+                }
+            )),
+            "unknown nv attribute `uuid`"
+        );
+    }
+}
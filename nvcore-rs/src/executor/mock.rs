@@ -0,0 +1,201 @@
+use crate::executor::{ExecuteError, Executor, IterStream, Query};
+use crate::row::{FromRow, ValueRow};
+use futures_core::Stream;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::future::{ready, Future};
+
+enum MockResult {
+    Rows(Vec<ValueRow>),
+    Affected(u64),
+    Error(String),
+}
+
+// Executor for unit tests, each query takes the next result queued with
+// returning, affecting or failing and is recorded with its values. A query
+// without a queued result gets no row and affects none.
+pub struct MockExecutor<T> {
+    results: RefCell<VecDeque<MockResult>>,
+    queries: RefCell<Vec<Query<T>>>,
+}
+
+impl<T> Default for MockExecutor<T> {
+    fn default() -> Self {
+        Self {
+            results: RefCell::new(VecDeque::new()),
+            queries: RefCell::new(Vec::new()),
+        }
+    }
+}
+
+impl<T: Clone> MockExecutor<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn returning(self, rows: Vec<ValueRow>) -> Self {
+        self.push(MockResult::Rows(rows))
+    }
+
+    pub fn affecting(self, count: u64) -> Self {
+        self.push(MockResult::Affected(count))
+    }
+
+    pub fn failing(self, message: &str) -> Self {
+        self.push(MockResult::Error(message.to_string()))
+    }
+
+    // Every query run so far, in order
+    pub fn queries(&self) -> Vec<Query<T>> {
+        self.queries.borrow().clone()
+    }
+
+    fn push(self, result: MockResult) -> Self {
+        self.results.borrow_mut().push_back(result);
+        self
+    }
+
+    fn next(&self, query: Query<T>) -> (Query<T>, Option<MockResult>) {
+        self.queries.borrow_mut().push(query.clone());
+        (query, self.results.borrow_mut().pop_front())
+    }
+
+    fn rows<F: FromRow>(&self, query: Query<T>) -> Result<Vec<F>, ExecuteError<String>> {
+        match self.next(query) {
+            (query, Some(MockResult::Rows(rows))) => rows
+                .iter()
+                .map(|row| F::from_row(row, &query.columns).map_err(ExecuteError::Row))
+                .collect(),
+            (_, Some(MockResult::Error(message))) => Err(ExecuteError::Driver(message)),
+            _ => Ok(Vec::new()),
+        }
+    }
+}
+
+impl<T: Clone> Executor<T> for MockExecutor<T> {
+    type Error = String;
+
+    fn fetch_all<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Vec<F>, ExecuteError<String>>> + Send {
+        ready(self.rows(query.into()))
+    }
+
+    fn fetch_optional<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Option<F>, ExecuteError<String>>> + Send {
+        ready(self.rows(query.into()).map(|rows| rows.into_iter().next()))
+    }
+
+    fn execute(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<u64, ExecuteError<String>>> + Send {
+        ready(match self.next(query.into()) {
+            (_, Some(MockResult::Affected(count))) => Ok(count),
+            (_, Some(MockResult::Rows(rows))) => Ok(rows.len() as u64),
+            (_, Some(MockResult::Error(message))) => Err(ExecuteError::Driver(message)),
+            (_, None) => Ok(0),
+        })
+    }
+
+    fn stream<F: FromRow>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Stream<Item = Result<F, ExecuteError<String>>> {
+        let rows = match self.rows(query.into()) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        };
+        IterStream::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, NvInsert, NvSelect, SqlAggregateFunction,
+        SqlOperator,
+    };
+    use std::pin::pin;
+    use std::task::{Context, Poll, Waker};
+
+    type Param = DefaultPostgresParamType;
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_mock_executor_records_queries() {
+        let select = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "email".to_string(),
+                None,
+                Some("contact".to_string()),
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition("active", &SqlOperator::Equal, Param::Bool(true))
+            .unwrap()
+            .end_where_block();
+        let insert = NvInsert::new(DatabaseDialect::PostgreSQL)
+            .into_table("users")
+            .value("email", Param::String("ada@example.com".to_string()));
+
+        let executor = MockExecutor::new()
+            .returning(vec![
+                ValueRow::new()
+                    .with("id", 1)
+                    .with("contact", "ada@example.com"),
+                ValueRow::new()
+                    .with("id", 2)
+                    .with("contact", "bob@example.com"),
+            ])
+            .affecting(1)
+            .failing("connection reset");
+
+        let users: Vec<(i64, String)> = block_on(executor.fetch_all(&select)).unwrap();
+        assert_eq!(users[1], (2, "bob@example.com".to_string()));
+        assert_eq!(block_on(executor.execute(&insert)).unwrap(), 1);
+        assert_eq!(
+            block_on(executor.fetch_one::<(i64, String)>(&select))
+                .unwrap_err()
+                .to_string(),
+            "connection reset"
+        );
+        assert!(matches!(
+            block_on(executor.fetch_one::<(i64, String)>(&select)),
+            Err(ExecuteError::NotFound)
+        ));
+
+        let queries = executor.queries();
+        assert_eq!(queries.len(), 4);
+        assert_eq!(
+            queries[0].sql,
+            "SELECT id, email AS contact FROM users WHERE active = $1"
+        );
+        assert_eq!(queries[0].values, vec![Param::Bool(true)]);
+        assert_eq!(queries[1].sql, "INSERT INTO users (email) VALUES ($1)");
+    }
+}
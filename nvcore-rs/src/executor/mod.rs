@@ -0,0 +1,205 @@
+// Runs built statements against a database
+//
+// A Query is the SQL of a built statement with its values() and the names of
+// its select list, taken from an NvSelect, Select or NvInsert. Rows are
+// mapped with FromRow:
+//
+//     let users: Vec<User> = executor.fetch_all(&select).await?;
+//     let (total,): (i64,) = executor.fetch_one(&count).await?;
+//     let inserted = executor.execute(&insert).await?;
+//
+// Executor is implemented for SqliteExecutor (rusqlite feature),
+// tokio_postgres::Client and Transaction (tokio-postgres feature) and
+// sqlx::PgPool (sqlx feature). MockExecutor answers with rows queued by the
+// test and records every query.
+
+mod mock;
+#[cfg(feature = "tokio-postgres")]
+mod postgres;
+#[cfg(feature = "rusqlite")]
+mod sqlite;
+#[cfg(feature = "sqlx")]
+mod sqlx_postgres;
+
+pub use mock::*;
+#[cfg(feature = "rusqlite")]
+pub use sqlite::*;
+
+use crate::row::{FromRow, RowError};
+use crate::sqlbuilder::{DatabaseDialect, NvInsert, NvSelect, Select};
+use futures_core::Stream;
+use std::fmt;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<T> {
+    pub sql: String,
+    pub values: Vec<T>,
+    // Names of the select list, or of RETURNING for inserts
+    pub columns: Vec<String>,
+    // Dialect the SQL was generated for, executors refuse other dialects
+    pub dialect: Option<DatabaseDialect>,
+}
+
+impl<T> Query<T> {
+    pub fn new(sql: &str, values: Vec<T>) -> Self {
+        Self {
+            sql: sql.to_string(),
+            values,
+            columns: Vec::new(),
+            dialect: None,
+        }
+    }
+
+    pub fn with_columns(mut self, columns: &[&str]) -> Self {
+        self.columns = columns.iter().map(|c| c.to_string()).collect();
+        self
+    }
+
+    // Fails when the query was built for another dialect than the executor
+    // runs, queries without a dialect run anywhere
+    pub fn ensure_dialect<E>(&self, dialect: DatabaseDialect) -> Result<(), ExecuteError<E>> {
+        match self.dialect {
+            Some(found) if found != dialect => Err(ExecuteError::Dialect {
+                expected: dialect,
+                found,
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl<T: Clone> From<&NvSelect<T>> for Query<T> {
+    fn from(select: &NvSelect<T>) -> Self {
+        Self {
+            sql: select.generate_query(false),
+            values: select.values().into_iter().cloned().collect(),
+            columns: select.column_names(),
+            dialect: Some(select.dialect()),
+        }
+    }
+}
+
+impl<T: Clone> From<&Arc<NvSelect<T>>> for Query<T> {
+    fn from(select: &Arc<NvSelect<T>>) -> Self {
+        Self::from(select.as_ref())
+    }
+}
+
+impl<T: Clone> From<&Select<T>> for Query<T> {
+    fn from(select: &Select<T>) -> Self {
+        Self {
+            sql: select.generate_query(false),
+            values: select.values().into_iter().cloned().collect(),
+            columns: select.column_names(),
+            dialect: Some(select.dialect()),
+        }
+    }
+}
+
+impl<T: Clone> From<&NvInsert<T>> for Query<T> {
+    fn from(insert: &NvInsert<T>) -> Self {
+        Self {
+            sql: insert.generate_query(false),
+            values: insert.values().clone(),
+            columns: insert.returning_columns().clone(),
+            dialect: Some(insert.dialect()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ExecuteError<E> {
+    Driver(E),
+    Row(RowError),
+    // fetch_one got no row
+    NotFound,
+    // The query was built for `found`, the executor runs `expected`
+    Dialect {
+        expected: DatabaseDialect,
+        found: DatabaseDialect,
+    },
+}
+
+impl<E> From<RowError> for ExecuteError<E> {
+    fn from(error: RowError) -> Self {
+        ExecuteError::Row(error)
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for ExecuteError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecuteError::Driver(error) => write!(f, "{}", error),
+            ExecuteError::Row(error) => write!(f, "{}", error),
+            ExecuteError::NotFound => write!(f, "the query returned no row"),
+            ExecuteError::Dialect { expected, found } => write!(
+                f,
+                "the query was built for {}, the executor runs {}",
+                found, expected
+            ),
+        }
+    }
+}
+
+impl<E: fmt::Debug + fmt::Display> std::error::Error for ExecuteError<E> {}
+
+// The futures are Send so queries can run on a multi-threaded runtime
+pub trait Executor<T> {
+    type Error;
+
+    fn fetch_all<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Vec<F>, ExecuteError<Self::Error>>> + Send;
+
+    // The first row, if any
+    fn fetch_optional<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Option<F>, ExecuteError<Self::Error>>> + Send;
+
+    // The first row, NotFound without one
+    fn fetch_one<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<F, ExecuteError<Self::Error>>> + Send {
+        let row = self.fetch_optional(query);
+        async move { row.await?.ok_or(ExecuteError::NotFound) }
+    }
+
+    // Runs a statement and returns the number of affected rows
+    fn execute(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<u64, ExecuteError<Self::Error>>> + Send;
+
+    // Rows mapped one at a time as the driver yields them
+    fn stream<F: FromRow>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Stream<Item = Result<F, ExecuteError<Self::Error>>>;
+}
+
+// Stream over rows already read, for executors without a row stream
+pub(crate) struct IterStream<I>(I);
+
+impl<I> IterStream<I> {
+    pub(crate) fn new(rows: impl IntoIterator<IntoIter = I>) -> Self {
+        Self(rows.into_iter())
+    }
+}
+
+// The iterator is never pinned, rows that are not Unpin are only moved out
+impl<I> Unpin for IterStream<I> {}
+
+impl<I: Iterator> Stream for IterStream<I> {
+    type Item = I::Item;
+
+    fn poll_next(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<Option<I::Item>> {
+        Poll::Ready(self.get_mut().0.next())
+    }
+}
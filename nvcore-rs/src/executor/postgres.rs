@@ -0,0 +1,165 @@
+use crate::executor::{ExecuteError, Executor, Query};
+use crate::row::FromRow;
+use crate::sqlbuilder::{DatabaseDialect, DefaultPostgresParamType};
+use bytes::BytesMut;
+use futures_core::Stream;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use std::error::Error;
+use std::future::Future;
+use tokio_postgres::types::{to_sql_checked, IsNull, ToSql, Type};
+use tokio_postgres::{Client, Transaction};
+
+// Integers and floats are written as the type Postgres infers for the
+// placeholder, so an Int compares with a BIGINT column
+impl ToSql for DefaultPostgresParamType {
+    fn to_sql(
+        &self,
+        ty: &Type,
+        out: &mut BytesMut,
+    ) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+        match self {
+            DefaultPostgresParamType::SmallInt(value) => integer_to_sql((*value).into(), ty, out),
+            DefaultPostgresParamType::Int(value) => integer_to_sql((*value).into(), ty, out),
+            DefaultPostgresParamType::BigInt(value) => integer_to_sql(*value, ty, out),
+            DefaultPostgresParamType::Float(value) => float_to_sql((*value).into(), ty, out),
+            DefaultPostgresParamType::Double(value) => float_to_sql(*value, ty, out),
+            DefaultPostgresParamType::String(value) => value.to_sql_checked(ty, out),
+            DefaultPostgresParamType::Bool(value) => value.to_sql_checked(ty, out),
+            DefaultPostgresParamType::TimePoint(value) => value.to_sql_checked(ty, out),
+        }
+    }
+
+    fn accepts(ty: &Type) -> bool {
+        <i64 as ToSql>::accepts(ty)
+            || <i32 as ToSql>::accepts(ty)
+            || <i16 as ToSql>::accepts(ty)
+            || <f64 as ToSql>::accepts(ty)
+            || <f32 as ToSql>::accepts(ty)
+            || <String as ToSql>::accepts(ty)
+            || <bool as ToSql>::accepts(ty)
+            || <std::time::SystemTime as ToSql>::accepts(ty)
+    }
+
+    to_sql_checked!();
+}
+
+fn integer_to_sql(
+    value: i64,
+    ty: &Type,
+    out: &mut BytesMut,
+) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+    if *ty == Type::INT2 {
+        i16::try_from(value)?.to_sql_checked(ty, out)
+    } else if *ty == Type::INT4 {
+        i32::try_from(value)?.to_sql_checked(ty, out)
+    } else if *ty == Type::FLOAT4 || *ty == Type::FLOAT8 {
+        float_to_sql(value as f64, ty, out)
+    } else {
+        value.to_sql_checked(ty, out)
+    }
+}
+
+fn float_to_sql(
+    value: f64,
+    ty: &Type,
+    out: &mut BytesMut,
+) -> Result<IsNull, Box<dyn Error + Sync + Send>> {
+    if *ty == Type::FLOAT4 {
+        (value as f32).to_sql_checked(ty, out)
+    } else {
+        value.to_sql_checked(ty, out)
+    }
+}
+
+fn parameters(query: &Query<DefaultPostgresParamType>) -> Vec<&(dyn ToSql + Sync)> {
+    query
+        .values
+        .iter()
+        .map(|value| value as &(dyn ToSql + Sync))
+        .collect()
+}
+
+// Client and Transaction have the same query methods but no shared trait
+// that returns futures usable here
+macro_rules! postgres_executor {
+    ($($client:ty),*) => {
+        $(
+            impl Executor<DefaultPostgresParamType> for $client {
+                type Error = tokio_postgres::Error;
+
+                fn fetch_all<F: FromRow + Send>(
+                    &self,
+                    query: impl Into<Query<DefaultPostgresParamType>>,
+                ) -> impl Future<Output = Result<Vec<F>, ExecuteError<tokio_postgres::Error>>> + Send {
+                    let query = ready_query(query);
+                    async move {
+                        let query = query?;
+                        let rows = self
+                            .query(&query.sql, &parameters(&query))
+                            .await
+                            .map_err(ExecuteError::Driver)?;
+                        rows.iter()
+                            .map(|row| F::from_row(row, &query.columns).map_err(ExecuteError::Row))
+                            .collect()
+                    }
+                }
+
+                fn fetch_optional<F: FromRow + Send>(
+                    &self,
+                    query: impl Into<Query<DefaultPostgresParamType>>,
+                ) -> impl Future<Output = Result<Option<F>, ExecuteError<tokio_postgres::Error>>> + Send {
+                    let rows = self.stream(query);
+                    async move {
+                        let mut rows = std::pin::pin!(rows);
+                        rows.next().await.transpose()
+                    }
+                }
+
+                fn execute(
+                    &self,
+                    query: impl Into<Query<DefaultPostgresParamType>>,
+                ) -> impl Future<Output = Result<u64, ExecuteError<tokio_postgres::Error>>> + Send {
+                    let query = ready_query(query);
+                    async move {
+                        let query = query?;
+                        self.execute(&query.sql, &parameters(&query))
+                            .await
+                            .map_err(ExecuteError::Driver)
+                    }
+                }
+
+                fn stream<F: FromRow>(
+                    &self,
+                    query: impl Into<Query<DefaultPostgresParamType>>,
+                ) -> impl Stream<Item = Result<F, ExecuteError<tokio_postgres::Error>>> {
+                    let query = ready_query(query);
+                    let columns = query
+                        .as_ref()
+                        .map(|query| query.columns.clone())
+                        .unwrap_or_default();
+                    stream::once(async move {
+                        let query = query?;
+                        self.query_raw(&query.sql, query.values.iter())
+                            .await
+                            .map(|rows| rows.map_err(ExecuteError::Driver))
+                            .map_err(ExecuteError::Driver)
+                    })
+                    .try_flatten()
+                    .map(move |row| {
+                        row.and_then(|row| F::from_row(&row, &columns).map_err(ExecuteError::Row))
+                    })
+                }
+            }
+        )*
+    };
+}
+
+postgres_executor!(Client, Transaction<'_>);
+
+fn ready_query(
+    query: impl Into<Query<DefaultPostgresParamType>>,
+) -> Result<Query<DefaultPostgresParamType>, ExecuteError<tokio_postgres::Error>> {
+    let query = query.into();
+    query.ensure_dialect(DatabaseDialect::PostgreSQL)?;
+    Ok(query)
+}
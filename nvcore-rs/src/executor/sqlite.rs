@@ -0,0 +1,284 @@
+use crate::executor::{ExecuteError, Executor, IterStream, Query};
+use crate::row::FromRow;
+use crate::sqlbuilder::{
+    sqlite_time_point_to_string, DatabaseDialect, DefaultSqliteParamType, NvInsert, NvSelect,
+};
+use futures_core::Stream;
+use rusqlite::types::{ToSqlOutput, Value, ValueRef};
+use rusqlite::{ffi, params_from_iter, Connection, ToSql};
+use std::future::{ready, Future};
+
+impl ToSql for DefaultSqliteParamType {
+    fn to_sql(&self) -> rusqlite::Result<ToSqlOutput<'_>> {
+        Ok(match self {
+            DefaultSqliteParamType::Null => ToSqlOutput::Owned(Value::Null),
+            DefaultSqliteParamType::Int(value) => ToSqlOutput::Owned(Value::Integer(*value as i64)),
+            DefaultSqliteParamType::BigInt(value) => ToSqlOutput::Owned(Value::Integer(*value)),
+            DefaultSqliteParamType::Double(value) => ToSqlOutput::Owned(Value::Real(*value)),
+            DefaultSqliteParamType::String(value) => {
+                ToSqlOutput::Borrowed(ValueRef::Text(value.as_bytes()))
+            }
+            DefaultSqliteParamType::Bool(value) => {
+                ToSqlOutput::Owned(Value::Integer(*value as i64))
+            }
+            DefaultSqliteParamType::TimePoint(value) => {
+                ToSqlOutput::Owned(Value::Text(sqlite_time_point_to_string(value)))
+            }
+            DefaultSqliteParamType::Blob(value) => ToSqlOutput::Borrowed(ValueRef::Blob(value)),
+        })
+    }
+}
+
+// In-process execution backend for queries built with DatabaseDialect::SQLite
+pub struct SqliteExecutor {
+    connection: Connection,
+}
+
+impl SqliteExecutor {
+    pub fn new(connection: Connection) -> Self {
+        Self { connection }
+    }
+
+    pub fn open_in_memory() -> rusqlite::Result<Self> {
+        Ok(Self::new(Connection::open_in_memory()?))
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.connection
+    }
+
+    pub fn execute_batch(&self, sql: &str) -> rusqlite::Result<()> {
+        self.connection.execute_batch(sql)
+    }
+
+    // Run the select and return every row as a list of column values
+    pub fn query<T: ToSql + Clone>(
+        &self,
+        select: &NvSelect<T>,
+    ) -> rusqlite::Result<Vec<Vec<Value>>> {
+        Self::ensure_dialect(select.dialect())?;
+
+        let mut statement = self.connection.prepare(&select.generate_query(false))?;
+        let column_count = statement.column_count();
+        let rows = statement.query_map(params_from_iter(select.values()), |row| {
+            (0..column_count)
+                .map(|i| row.get::<_, Value>(i))
+                .collect::<rusqlite::Result<Vec<_>>>()
+        })?;
+
+        rows.collect()
+    }
+
+    // Run the insert and return the number of affected rows
+    pub fn insert<T: ToSql>(&self, insert: &NvInsert<T>) -> rusqlite::Result<usize> {
+        Self::ensure_dialect(insert.dialect())?;
+
+        self.connection.execute(
+            &insert.generate_query(false),
+            params_from_iter(insert.values().iter()),
+        )
+    }
+
+    // Queries of another dialect fail as SQLITE_MISUSE before reaching SQLite
+    fn ensure_dialect(dialect: DatabaseDialect) -> rusqlite::Result<()> {
+        if dialect != DatabaseDialect::SQLite {
+            return Err(rusqlite::Error::SqliteFailure(
+                ffi::Error::new(ffi::SQLITE_MISUSE),
+                Some(format!(
+                    "SqliteExecutor only runs queries built for SQLite, got {}",
+                    dialect
+                )),
+            ));
+        }
+        Ok(())
+    }
+}
+
+// rusqlite is synchronous, the futures are ready when returned and stream
+// reads every row before yielding the first
+impl<T: ToSql> Executor<T> for SqliteExecutor {
+    type Error = rusqlite::Error;
+
+    fn fetch_all<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Vec<F>, ExecuteError<rusqlite::Error>>> + Send {
+        ready(self.read(&query.into(), None))
+    }
+
+    fn fetch_optional<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<Option<F>, ExecuteError<rusqlite::Error>>> + Send {
+        ready(
+            self.read(&query.into(), Some(1))
+                .map(|rows| rows.into_iter().next()),
+        )
+    }
+
+    fn execute(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Future<Output = Result<u64, ExecuteError<rusqlite::Error>>> + Send {
+        let query = query.into();
+        ready(
+            query
+                .ensure_dialect(DatabaseDialect::SQLite)
+                .and_then(|()| {
+                    self.connection
+                        .execute(&query.sql, params_from_iter(query.values.iter()))
+                        .map(|count| count as u64)
+                        .map_err(ExecuteError::Driver)
+                }),
+        )
+    }
+
+    fn stream<F: FromRow>(
+        &self,
+        query: impl Into<Query<T>>,
+    ) -> impl Stream<Item = Result<F, ExecuteError<rusqlite::Error>>> {
+        let rows = match self.read(&query.into(), None) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        };
+        IterStream::new(rows)
+    }
+}
+
+impl SqliteExecutor {
+    // Maps at most `limit` rows of the query
+    fn read<T: ToSql, F: FromRow>(
+        &self,
+        query: &Query<T>,
+        limit: Option<usize>,
+    ) -> Result<Vec<F>, ExecuteError<rusqlite::Error>> {
+        query.ensure_dialect(DatabaseDialect::SQLite)?;
+
+        let mut statement = self
+            .connection
+            .prepare(&query.sql)
+            .map_err(ExecuteError::Driver)?;
+        let mut rows = statement
+            .query(params_from_iter(query.values.iter()))
+            .map_err(ExecuteError::Driver)?;
+        let mut mapped = Vec::new();
+        while let Some(row) = rows.next().map_err(ExecuteError::Driver)? {
+            mapped.push(F::from_row(row, &query.columns)?);
+            if limit.is_some_and(|limit| mapped.len() >= limit) {
+                break;
+            }
+        }
+        Ok(mapped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{SqlAggregateFunction, SqlOperator};
+
+    #[test]
+    fn test_select_round_trip() {
+        let executor = SqliteExecutor::open_in_memory().unwrap();
+        executor
+            .execute_batch("CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, created TEXT);")
+            .unwrap();
+
+        for (id, name) in [(1, "Alice"), (2, "bob"), (3, "Carol")] {
+            let insert = NvInsert::new(DatabaseDialect::SQLite)
+                .into_table("users")
+                .value("id", DefaultSqliteParamType::BigInt(id))
+                .value("name", DefaultSqliteParamType::String(name.to_string()))
+                .value(
+                    "created",
+                    DefaultSqliteParamType::String("2024-05-01 10:20:30".to_string()),
+                );
+            assert_eq!(executor.insert(&insert).unwrap(), 1);
+        }
+
+        let upsert = NvInsert::new(DatabaseDialect::SQLite)
+            .into_table("users")
+            .value("id", DefaultSqliteParamType::BigInt(2))
+            .value("name", DefaultSqliteParamType::String("Bob".to_string()))
+            .on_conflict_update(&["id"], &["name"])
+            .unwrap();
+        executor.insert(&upsert).unwrap();
+
+        let select = NvSelect::<DefaultSqliteParamType>::new(DatabaseDialect::SQLite)
+            .f(
+                "name".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "created".to_string(),
+                Some("u".to_string()),
+                Some("created_date".to_string()),
+                SqlAggregateFunction::ToIso8601Date,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "u.name",
+                &SqlOperator::ILike,
+                DefaultSqliteParamType::String("b%".to_string()),
+            )
+            .unwrap()
+            .or()
+            .add_condition(
+                "u.id",
+                &SqlOperator::GreaterOrEqual,
+                DefaultSqliteParamType::Int(3),
+            )
+            .unwrap()
+            .end_where_block()
+            .limit_offset(10, 0)
+            .unwrap();
+
+        let rows = executor.query(&select).unwrap();
+        assert_eq!(
+            rows,
+            vec![
+                vec![
+                    Value::Text("Bob".to_string()),
+                    Value::Text("2024-05-01".to_string())
+                ],
+                vec![
+                    Value::Text("Carol".to_string()),
+                    Value::Text("2024-05-01".to_string())
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn test_rejects_other_dialects() {
+        let executor = SqliteExecutor::open_in_memory().unwrap();
+        let insert = NvInsert::new(DatabaseDialect::MySQL)
+            .into_table("users")
+            .value("id", DefaultSqliteParamType::BigInt(1));
+
+        let error = executor.insert(&insert).unwrap_err();
+        assert_eq!(
+            error.sqlite_error_code(),
+            Some(rusqlite::ErrorCode::ApiMisuse)
+        );
+        assert!(error.to_string().contains("got MySQL"));
+
+        // Through Executor the dialects are reported as they are
+        let execute = std::pin::pin!(Executor::execute(&executor, &insert));
+        let mut cx = std::task::Context::from_waker(std::task::Waker::noop());
+        assert!(matches!(
+            execute.poll(&mut cx),
+            std::task::Poll::Ready(Err(ExecuteError::Dialect {
+                expected: DatabaseDialect::SQLite,
+                found: DatabaseDialect::MySQL,
+            }))
+        ));
+    }
+}
@@ -0,0 +1,98 @@
+use crate::executor::{ExecuteError, Executor, Query};
+use crate::row::FromRow;
+use crate::sqlbuilder::{DatabaseDialect, DefaultPostgresParamType};
+use chrono::{DateTime, Utc};
+use futures_core::Stream;
+use futures_util::{stream, StreamExt, TryStreamExt};
+use sqlx::postgres::{PgArguments, PgPool};
+use sqlx::{AssertSqlSafe, Postgres};
+use std::future::{ready, Future};
+
+type PgQuery = sqlx::query::Query<'static, Postgres, PgArguments>;
+
+// Values are bound with their own type, TimePoint as TIMESTAMPTZ
+fn bind(query: Query<DefaultPostgresParamType>) -> Result<PgQuery, ExecuteError<sqlx::Error>> {
+    query.ensure_dialect(DatabaseDialect::PostgreSQL)?;
+    Ok(query
+        .values
+        .into_iter()
+        .fold(
+            sqlx::query(AssertSqlSafe(query.sql)),
+            |bound, value| match value {
+                DefaultPostgresParamType::SmallInt(value) => bound.bind(value),
+                DefaultPostgresParamType::Int(value) => bound.bind(value),
+                DefaultPostgresParamType::BigInt(value) => bound.bind(value),
+                DefaultPostgresParamType::Float(value) => bound.bind(value),
+                DefaultPostgresParamType::Double(value) => bound.bind(value),
+                DefaultPostgresParamType::String(value) => bound.bind(value),
+                DefaultPostgresParamType::Bool(value) => bound.bind(value),
+                DefaultPostgresParamType::TimePoint(value) => {
+                    bound.bind(DateTime::<Utc>::from(value))
+                }
+            },
+        ))
+}
+
+impl Executor<DefaultPostgresParamType> for PgPool {
+    type Error = sqlx::Error;
+
+    fn fetch_all<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<DefaultPostgresParamType>>,
+    ) -> impl Future<Output = Result<Vec<F>, ExecuteError<sqlx::Error>>> + Send {
+        let query = query.into();
+        let columns = query.columns.clone();
+        let bound = bind(query);
+        async move {
+            let rows = bound?.fetch_all(self).await.map_err(ExecuteError::Driver)?;
+            rows.iter()
+                .map(|row| F::from_row(row, &columns).map_err(ExecuteError::Row))
+                .collect()
+        }
+    }
+
+    fn fetch_optional<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<DefaultPostgresParamType>>,
+    ) -> impl Future<Output = Result<Option<F>, ExecuteError<sqlx::Error>>> + Send {
+        let query = query.into();
+        let columns = query.columns.clone();
+        let bound = bind(query);
+        async move {
+            let row = bound?
+                .fetch_optional(self)
+                .await
+                .map_err(ExecuteError::Driver)?;
+            row.map(|row| F::from_row(&row, &columns).map_err(ExecuteError::Row))
+                .transpose()
+        }
+    }
+
+    fn execute(
+        &self,
+        query: impl Into<Query<DefaultPostgresParamType>>,
+    ) -> impl Future<Output = Result<u64, ExecuteError<sqlx::Error>>> + Send {
+        let bound = bind(query.into());
+        async move {
+            bound?
+                .execute(self)
+                .await
+                .map(|result| result.rows_affected())
+                .map_err(ExecuteError::Driver)
+        }
+    }
+
+    fn stream<F: FromRow>(
+        &self,
+        query: impl Into<Query<DefaultPostgresParamType>>,
+    ) -> impl Stream<Item = Result<F, ExecuteError<sqlx::Error>>> {
+        let query = query.into();
+        let columns = query.columns.clone();
+        stream::once(ready(bind(query)))
+            .map_ok(move |bound| bound.fetch(self).map_err(ExecuteError::Driver))
+            .try_flatten()
+            .map(move |row| {
+                row.and_then(|row| F::from_row(&row, &columns).map_err(ExecuteError::Row))
+            })
+    }
+}
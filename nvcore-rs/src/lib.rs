@@ -1,2 +1,8 @@
+// Lets code generated by nvcore-derive refer to ::nvcore from inside the crate
+extern crate self as nvcore;
+
+pub mod executor;
+pub mod row;
 pub mod sqlbuilder;
-pub mod utils;
\ No newline at end of file
+pub mod utils;
+pub mod validator;
@@ -0,0 +1,311 @@
+// Result rows read by column name or position
+//
+// Drivers implement Row by converting their column values to SqlValue, for
+// rusqlite, tokio-postgres and sqlx behind their features. Structs deriving
+// NvTable read each field from the column selected under its name, tuples
+// read the columns by position, so duplicate names such as `u.id, t.id` and
+// unnamed expressions map too:
+//
+//     let select = NvSelect::new(DatabaseDialect::SQLite)
+//         .select_all::<User>()
+//         .from()
+//         .add_table_with_alias(User::NAME, &None)
+//         .end_from_table_block();
+//     ...
+//     let user: User = select.map_row(row)?;
+//     let (id, email): (i64, String) = select.map_row(row)?;
+
+#[cfg(feature = "tokio-postgres")]
+mod postgres;
+#[cfg(feature = "rusqlite")]
+mod sqlite;
+#[cfg(feature = "sqlx")]
+mod sqlx_postgres;
+mod value;
+
+pub use value::*;
+
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum RowError {
+    MissingColumn(String),
+    MissingIndex(usize),
+    Value { column: String, error: ValueError },
+    ColumnCount { expected: usize, found: usize },
+    Driver(String),
+}
+
+impl fmt::Display for RowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RowError::MissingColumn(column) => write!(f, "no column named {}", column),
+            RowError::MissingIndex(index) => write!(f, "no column at index {}", index),
+            RowError::Value { column, error } => write!(f, "column {}: {}", column, error),
+            RowError::ColumnCount { expected, found } => {
+                write!(f, "expected {} columns, the row has {}", expected, found)
+            }
+            RowError::Driver(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for RowError {}
+
+pub trait Row {
+    // Value of the column selected as `name`
+    fn value(&self, name: &str) -> Result<SqlValue, RowError>;
+
+    // Value of the column at `index`, counted from 0 in select-list order
+    fn value_at(&self, index: usize) -> Result<SqlValue, RowError>;
+
+    fn column_count(&self) -> usize;
+
+    fn get<V: FromSqlValue>(&self, name: &str) -> Result<V, RowError>
+    where
+        Self: Sized,
+    {
+        V::from_sql_value(self.value(name)?).map_err(|error| RowError::Value {
+            column: name.to_string(),
+            error,
+        })
+    }
+}
+
+// Types a result row maps into, `columns` are the names of the select list
+// in order, see NvSelect::column_names
+pub trait FromRow: Sized {
+    fn from_row<R: Row>(row: &R, columns: &[String]) -> Result<Self, RowError>;
+}
+
+// Errors name the column by its select-list name when it has one
+fn tuple_field<V: FromSqlValue, R: Row>(
+    row: &R,
+    columns: &[String],
+    index: usize,
+) -> Result<V, RowError> {
+    V::from_sql_value(row.value_at(index)?).map_err(|error| RowError::Value {
+        column: match columns.get(index) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => index.to_string(),
+        },
+        error,
+    })
+}
+
+macro_rules! tuple_from_row {
+    ($($count:literal => ($($ty:ident $index:tt),+)),* $(,)?) => {
+        $(
+            impl<$($ty: FromSqlValue),+> FromRow for ($($ty,)+) {
+                fn from_row<R: Row>(row: &R, columns: &[String]) -> Result<Self, RowError> {
+                    if row.column_count() != $count {
+                        return Err(RowError::ColumnCount {
+                            expected: $count,
+                            found: row.column_count(),
+                        });
+                    }
+                    Ok(($(tuple_field::<$ty, R>(row, columns, $index)?,)+))
+                }
+            }
+        )*
+    };
+}
+
+tuple_from_row!(
+    1 => (A 0),
+    2 => (A 0, B 1),
+    3 => (A 0, B 1, C 2),
+    4 => (A 0, B 1, C 2, D 3),
+    5 => (A 0, B 1, C 2, D 3, E 4),
+    6 => (A 0, B 1, C 2, D 3, E 4, F 5),
+    7 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6),
+    8 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7),
+    9 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8),
+    10 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9),
+    11 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10),
+    12 => (A 0, B 1, C 2, D 3, E 4, F 5, G 6, H 7, I 8, J 9, K 10, L 11),
+);
+
+// A row held in memory, for tests and rows built by hand. Names are matched
+// ignoring ASCII case as databases fold unquoted aliases.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ValueRow {
+    columns: Vec<(String, SqlValue)>,
+}
+
+impl ValueRow {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, name: &str, value: impl Into<SqlValue>) -> Self {
+        self.columns.push((name.to_string(), value.into()));
+        self
+    }
+
+    pub fn columns(&self) -> &[(String, SqlValue)] {
+        &self.columns
+    }
+}
+
+impl Row for ValueRow {
+    fn value(&self, name: &str) -> Result<SqlValue, RowError> {
+        self.columns
+            .iter()
+            .find(|(column, _)| column.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.clone())
+            .ok_or_else(|| RowError::MissingColumn(name.to_string()))
+    }
+
+    fn value_at(&self, index: usize) -> Result<SqlValue, RowError> {
+        self.columns
+            .get(index)
+            .map(|(_, value)| value.clone())
+            .ok_or(RowError::MissingIndex(index))
+    }
+
+    fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, NvSelect, SqlAggregateFunction,
+    };
+
+    type Param = DefaultPostgresParamType;
+
+    #[test]
+    fn test_tuples_map_by_position() {
+        let select = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "id".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "email".to_string(),
+                Some("u".to_string()),
+                Some("contact".to_string()),
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "id".to_string(),
+                None,
+                Some("orders".to_string()),
+                SqlAggregateFunction::Count,
+                false,
+            );
+        assert_eq!(select.column_names(), vec!["id", "contact", "orders"]);
+
+        let row = ValueRow::new()
+            .with("id", 7)
+            .with("contact", None::<String>)
+            .with("orders", 3);
+        let mapped: (i64, Option<String>, u32) = select.map_row(&row).unwrap();
+        assert_eq!(mapped, (7, None, 3));
+
+        // `u.id, t.id` and `id, COUNT(id)` come back under repeated or
+        // database-chosen names
+        let joined = ValueRow::new().with("id", 7).with("id", 9).with("count", 2);
+        let columns = crate::sqlbuilder::Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("u"), None, SqlAggregateFunction::None, false)
+            .f("id", Some("t"), None, SqlAggregateFunction::None, false)
+            .f("id", Some("t"), None, SqlAggregateFunction::Count, false)
+            .column_names();
+        assert_eq!(columns, vec!["id", "id", ""]);
+        let mapped = <(i64, i64, u32)>::from_row(&joined, &columns).unwrap();
+        assert_eq!(mapped, (7, 9, 2));
+
+        assert_eq!(
+            select.map_row::<(i64, String), _>(&row),
+            Err(RowError::ColumnCount {
+                expected: 2,
+                found: 3
+            })
+        );
+        assert_eq!(
+            select
+                .map_row::<(i64, String, u32), _>(&row)
+                .unwrap_err()
+                .to_string(),
+            "column contact: unexpected null"
+        );
+    }
+
+    #[cfg(feature = "derive")]
+    #[derive(Debug, PartialEq, crate::sqlbuilder::NvTable, crate::validator::NvValidate)]
+    #[nv(table = "users")]
+    struct User {
+        id: i64,
+        #[nv(column = "email_address", email)]
+        email: String,
+        nickname: Option<String>,
+        #[nv(skip)]
+        roles: Vec<String>,
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derived_table_maps_rows() {
+        use crate::sqlbuilder::{Select, SqlOperator, Table};
+        use crate::validator::NvValidate;
+
+        assert_eq!(User::NAME, "users");
+        assert_eq!(User::columns(), &["id", "email_address", "nickname"]);
+        assert_eq!(User::EMAIL.name(), "email_address");
+
+        let select = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .select_all::<User>()
+            .from()
+            .add_table_with_alias(User::NAME, &None)
+            .end_from_table_block();
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT id AS id, email_address AS email, nickname AS nickname FROM users"
+        );
+
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .select_all::<User>()
+            .from_table(User::NAME, Some("u"))
+            .where_clause(|w| w.condition(User::ID, SqlOperator::Equal, 7).unwrap());
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT u.id AS id, u.email_address AS email, u.nickname AS nickname \
+             FROM users AS u WHERE u.id = $1"
+        );
+
+        let row = ValueRow::new()
+            .with("id", 7)
+            .with("EMAIL", "ada@example.com")
+            .with("nickname", None::<String>);
+        let user = User::from_row(&row, &[]).unwrap();
+        assert_eq!(
+            user,
+            User {
+                id: 7,
+                email: "ada@example.com".to_string(),
+                nickname: None,
+                roles: Vec::new(),
+            }
+        );
+        assert!(user.validate().is_valid());
+
+        let row = ValueRow::new().with("id", "7");
+        assert_eq!(
+            User::from_row(&row, &[]).unwrap_err().to_string(),
+            "column id: expected i64, found text"
+        );
+        let row = ValueRow::new().with("id", 7);
+        assert_eq!(
+            User::from_row(&row, &[]),
+            Err(RowError::MissingColumn("email".to_string()))
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use crate::row::{Row, RowError, SqlValue};
+use std::time::SystemTime;
+use tokio_postgres::types::{FromSql, Type};
+
+// Columns are looked up ignoring ASCII case and read by their type, types
+// SqlValue has no variant for, such as NUMERIC or JSON, are reported as
+// errors rather than converted
+impl Row for tokio_postgres::Row {
+    fn value(&self, name: &str) -> Result<SqlValue, RowError> {
+        let index = self
+            .columns()
+            .iter()
+            .position(|column| column.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| RowError::MissingColumn(name.to_string()))?;
+        self.value_at(index)
+    }
+
+    fn value_at(&self, index: usize) -> Result<SqlValue, RowError> {
+        let column = self
+            .columns()
+            .get(index)
+            .ok_or(RowError::MissingIndex(index))?;
+        let ty = column.type_();
+
+        if *ty == Type::BOOL {
+            read(self, index, SqlValue::Bool)
+        } else if *ty == Type::INT2 {
+            read(self, index, |value: i16| SqlValue::Int(value.into()))
+        } else if *ty == Type::INT4 {
+            read(self, index, |value: i32| SqlValue::Int(value.into()))
+        } else if *ty == Type::INT8 {
+            read(self, index, SqlValue::Int)
+        } else if *ty == Type::OID {
+            read(self, index, |value: u32| SqlValue::Int(value.into()))
+        } else if *ty == Type::FLOAT4 {
+            read(self, index, |value: f32| SqlValue::Double(value.into()))
+        } else if *ty == Type::FLOAT8 {
+            read(self, index, SqlValue::Double)
+        } else if [
+            Type::TEXT,
+            Type::VARCHAR,
+            Type::BPCHAR,
+            Type::NAME,
+            Type::UNKNOWN,
+        ]
+        .contains(ty)
+        {
+            read(self, index, SqlValue::Text)
+        } else if *ty == Type::BYTEA {
+            read(self, index, SqlValue::Blob)
+        } else if *ty == Type::TIMESTAMP || *ty == Type::TIMESTAMPTZ {
+            read(self, index, |value: SystemTime| SqlValue::TimePoint(value))
+        } else {
+            Err(RowError::Driver(format!(
+                "column {} has unsupported type {}",
+                column.name(),
+                ty
+            )))
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        self.columns().len()
+    }
+}
+
+fn read<'a, V: FromSql<'a>>(
+    row: &'a tokio_postgres::Row,
+    index: usize,
+    into: impl FnOnce(V) -> SqlValue,
+) -> Result<SqlValue, RowError> {
+    row.try_get::<_, Option<V>>(index)
+        .map(|value| value.map_or(SqlValue::Null, into))
+        .map_err(|error| RowError::Driver(error.to_string()))
+}
@@ -0,0 +1,99 @@
+use crate::row::{Row, RowError, SqlValue};
+use rusqlite::types::ValueRef;
+
+// rusqlite looks columns up ignoring ASCII case
+impl Row for rusqlite::Row<'_> {
+    fn value(&self, name: &str) -> Result<SqlValue, RowError> {
+        let index = self
+            .as_ref()
+            .column_index(name)
+            .map_err(|_| RowError::MissingColumn(name.to_string()))?;
+        self.value_at(index)
+    }
+
+    fn value_at(&self, index: usize) -> Result<SqlValue, RowError> {
+        if index >= self.column_count() {
+            return Err(RowError::MissingIndex(index));
+        }
+        let value = self
+            .get_ref(index)
+            .map_err(|error| RowError::Driver(error.to_string()))?;
+
+        Ok(match value {
+            ValueRef::Null => SqlValue::Null,
+            ValueRef::Integer(value) => SqlValue::Int(value),
+            ValueRef::Real(value) => SqlValue::Double(value),
+            ValueRef::Text(value) => SqlValue::Text(String::from_utf8_lossy(value).into_owned()),
+            ValueRef::Blob(value) => SqlValue::Blob(value.to_vec()),
+        })
+    }
+
+    fn column_count(&self) -> usize {
+        self.as_ref().column_count()
+    }
+}
+
+#[cfg(all(test, feature = "derive"))]
+mod tests {
+    use crate::executor::SqliteExecutor;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultSqliteParamType, NvSelect, NvTable, SqlOperator, Table,
+    };
+    use rusqlite::params_from_iter;
+    use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+    #[derive(Debug, PartialEq, NvTable)]
+    #[nv(table = "users")]
+    struct User {
+        id: i64,
+        name: String,
+        active: bool,
+        created: SystemTime,
+    }
+
+    #[test]
+    fn test_sqlite_rows_map_to_entities() {
+        let executor = SqliteExecutor::open_in_memory().unwrap();
+        executor
+            .execute_batch(
+                "CREATE TABLE users (id INTEGER PRIMARY KEY, name TEXT, active INTEGER, created TEXT);
+                 INSERT INTO users VALUES (1, 'Ada', 1, '2024-05-01 10:20:30');
+                 INSERT INTO users VALUES (2, 'Bob', 0, '2024-05-02 10:20:30');",
+            )
+            .unwrap();
+
+        let select = NvSelect::new(DatabaseDialect::SQLite)
+            .select_all::<User>()
+            .from()
+            .add_table_with_alias(<User as Table>::NAME, &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "active",
+                &SqlOperator::Equal,
+                DefaultSqliteParamType::Bool(true),
+            )
+            .unwrap()
+            .end_where_block();
+
+        let mut statement = executor
+            .connection()
+            .prepare(&select.generate_query(false))
+            .unwrap();
+        let mut rows = statement.query(params_from_iter(select.values())).unwrap();
+        let mut users = Vec::new();
+        while let Some(row) = rows.next().unwrap() {
+            users.push(select.map_row::<User, _>(row).unwrap());
+        }
+
+        assert_eq!(
+            users,
+            vec![User {
+                id: 1,
+                name: "Ada".to_string(),
+                active: true,
+                created: UNIX_EPOCH + Duration::from_secs(1_714_558_830),
+            }]
+        );
+    }
+}
@@ -0,0 +1,58 @@
+use crate::row::{Row, RowError, SqlValue};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{Column, Decode, Postgres, Type, TypeInfo};
+
+// Same lookup as the tokio-postgres row, columns are read by the name of
+// their Postgres type
+impl Row for PgRow {
+    fn value(&self, name: &str) -> Result<SqlValue, RowError> {
+        let column = sqlx::Row::columns(self)
+            .iter()
+            .find(|column| column.name().eq_ignore_ascii_case(name))
+            .ok_or_else(|| RowError::MissingColumn(name.to_string()))?;
+        self.value_at(column.ordinal())
+    }
+
+    fn value_at(&self, index: usize) -> Result<SqlValue, RowError> {
+        let column = sqlx::Row::columns(self)
+            .get(index)
+            .ok_or(RowError::MissingIndex(index))?;
+
+        match column.type_info().name() {
+            "BOOL" => read(self, index, SqlValue::Bool),
+            "INT2" => read(self, index, |value: i16| SqlValue::Int(value.into())),
+            "INT4" => read(self, index, |value: i32| SqlValue::Int(value.into())),
+            "INT8" => read(self, index, SqlValue::Int),
+            "FLOAT4" => read(self, index, |value: f32| SqlValue::Double(value.into())),
+            "FLOAT8" => read(self, index, SqlValue::Double),
+            "TEXT" | "VARCHAR" | "CHAR" | "BPCHAR" | "NAME" => read(self, index, SqlValue::Text),
+            "BYTEA" => read(self, index, SqlValue::Blob),
+            "TIMESTAMP" => read(self, index, |value: NaiveDateTime| {
+                SqlValue::TimePoint(value.and_utc().into())
+            }),
+            "TIMESTAMPTZ" => read(self, index, |value: DateTime<Utc>| {
+                SqlValue::TimePoint(value.into())
+            }),
+            other => Err(RowError::Driver(format!(
+                "column {} has unsupported type {}",
+                column.name(),
+                other
+            ))),
+        }
+    }
+
+    fn column_count(&self) -> usize {
+        sqlx::Row::columns(self).len()
+    }
+}
+
+fn read<'r, V: Decode<'r, Postgres> + Type<Postgres>>(
+    row: &'r PgRow,
+    index: usize,
+    into: impl FnOnce(V) -> SqlValue,
+) -> Result<SqlValue, RowError> {
+    sqlx::Row::try_get::<Option<V>, _>(row, index)
+        .map(|value| value.map_or(SqlValue::Null, into))
+        .map_err(|error| RowError::Driver(error.to_string()))
+}
@@ -0,0 +1,202 @@
+use std::fmt;
+use std::time::SystemTime;
+
+// A column value of a result row, whatever driver it was read with
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlValue {
+    Null,
+    Int(i64),
+    Double(f64),
+    Text(String),
+    Bool(bool),
+    Blob(Vec<u8>),
+    TimePoint(SystemTime),
+}
+
+impl SqlValue {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            SqlValue::Null => "null",
+            SqlValue::Int(_) => "integer",
+            SqlValue::Double(_) => "double",
+            SqlValue::Text(_) => "text",
+            SqlValue::Bool(_) => "bool",
+            SqlValue::Blob(_) => "blob",
+            SqlValue::TimePoint(_) => "time point",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValueError {
+    UnexpectedNull,
+    WrongType {
+        expected: &'static str,
+        found: &'static str,
+    },
+    OutOfRange {
+        expected: &'static str,
+    },
+}
+
+impl fmt::Display for ValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValueError::UnexpectedNull => write!(f, "unexpected null"),
+            ValueError::WrongType { expected, found } => {
+                write!(f, "expected {}, found {}", expected, found)
+            }
+            ValueError::OutOfRange { expected } => write!(f, "value out of range for {}", expected),
+        }
+    }
+}
+
+impl std::error::Error for ValueError {}
+
+// Rust types a column value converts to
+pub trait FromSqlValue: Sized {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError>;
+}
+
+fn wrong_type<T>(expected: &'static str, value: &SqlValue) -> Result<T, ValueError> {
+    match value {
+        SqlValue::Null => Err(ValueError::UnexpectedNull),
+        value => Err(ValueError::WrongType {
+            expected,
+            found: value.type_name(),
+        }),
+    }
+}
+
+impl FromSqlValue for SqlValue {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        Ok(value)
+    }
+}
+
+impl<V: FromSqlValue> FromSqlValue for Option<V> {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::Null => Ok(None),
+            value => V::from_sql_value(value).map(Some),
+        }
+    }
+}
+
+macro_rules! integer_from_sql_value {
+    ($($ty:ty),*) => {
+        $(
+            impl FromSqlValue for $ty {
+                fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+                    match value {
+                        SqlValue::Int(value) => <$ty>::try_from(value).map_err(|_| {
+                            ValueError::OutOfRange {
+                                expected: stringify!($ty),
+                            }
+                        }),
+                        value => wrong_type(stringify!($ty), &value),
+                    }
+                }
+            }
+        )*
+    };
+}
+
+integer_from_sql_value!(i8, i16, i32, i64, u8, u16, u32, u64, isize, usize);
+
+impl FromSqlValue for f64 {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::Double(value) => Ok(value),
+            SqlValue::Int(value) => Ok(value as f64),
+            value => wrong_type("f64", &value),
+        }
+    }
+}
+
+impl FromSqlValue for f32 {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        f64::from_sql_value(value).map(|value| value as f32)
+    }
+}
+
+// SQLite stores booleans as 0 and 1
+impl FromSqlValue for bool {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::Bool(value) => Ok(value),
+            SqlValue::Int(0) => Ok(false),
+            SqlValue::Int(1) => Ok(true),
+            SqlValue::Int(_) => Err(ValueError::OutOfRange { expected: "bool" }),
+            value => wrong_type("bool", &value),
+        }
+    }
+}
+
+impl FromSqlValue for String {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::Text(value) => Ok(value),
+            value => wrong_type("String", &value),
+        }
+    }
+}
+
+impl FromSqlValue for Vec<u8> {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::Blob(value) => Ok(value),
+            value => wrong_type("Vec<u8>", &value),
+        }
+    }
+}
+
+// Also reads the ISO-8601 text time points are stored as in SQLite
+impl FromSqlValue for SystemTime {
+    fn from_sql_value(value: SqlValue) -> Result<Self, ValueError> {
+        match value {
+            SqlValue::TimePoint(value) => Ok(value),
+            SqlValue::Text(text) => {
+                chrono::NaiveDateTime::parse_from_str(&text, "%Y-%m-%d %H:%M:%S")
+                    .map(|datetime| datetime.and_utc().into())
+                    .or_else(|_| {
+                        chrono::DateTime::parse_from_rfc3339(&text).map(|datetime| datetime.into())
+                    })
+                    .map_err(|_| ValueError::WrongType {
+                        expected: "SystemTime",
+                        found: "text",
+                    })
+            }
+            value => wrong_type("SystemTime", &value),
+        }
+    }
+}
+
+macro_rules! sql_value_from {
+    ($($ty:ty => $variant:ident),* $(,)?) => {
+        $(
+            impl From<$ty> for SqlValue {
+                fn from(value: $ty) -> Self {
+                    SqlValue::$variant(value.into())
+                }
+            }
+        )*
+    };
+}
+
+sql_value_from!(
+    i32 => Int,
+    i64 => Int,
+    f64 => Double,
+    bool => Bool,
+    String => Text,
+    &str => Text,
+    Vec<u8> => Blob,
+    SystemTime => TimePoint,
+);
+
+impl<V: Into<SqlValue>> From<Option<V>> for SqlValue {
+    fn from(value: Option<V>) -> Self {
+        value.map_or(SqlValue::Null, Into::into)
+    }
+}
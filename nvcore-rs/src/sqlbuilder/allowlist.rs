@@ -0,0 +1,524 @@
+// Allowlist of the identifiers a query may use
+//
+// APIs that let clients pick sort columns or filter fields declare, per
+// endpoint, the tables and columns those clients may reach. Checking a query
+// walks every table, column, function and raw fragment in it, subqueries
+// included, and reports each identifier that is not declared, so client input
+// never reaches the SQL text as an identifier:
+//
+//     let allowlist = Allowlist::new()
+//         .table("users", &["id", "name", "email"])
+//         .table("teams", &["id", "name"]);
+//     let sql = select.generate_query_checked(&allowlist, false)?;
+//
+// Names are compared case-insensitively unless the column is quoted. Column
+// references resolve through the aliases of the FROM and JOIN tables of their
+// SELECT and of the enclosing SELECTs, ORDER BY may also name a select-list
+// alias.
+
+use crate::sqlbuilder::{
+    walk_expr, walk_order_by_item, walk_select, walk_select_item, Expr, OrderByItem, SelectItem,
+    SelectStmt, Table, TableRef, Visitor,
+};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Violation {
+    UnknownTable {
+        table: String,
+    },
+    UnknownColumn {
+        table: Option<String>,
+        column: String,
+    },
+    // Qualifier that is not the alias of a table of the query
+    UnknownAlias {
+        alias: String,
+        column: String,
+    },
+    UnknownFunction {
+        name: String,
+    },
+    RawFragment {
+        sql: String,
+    },
+    // Select-list or table alias that is not a plain identifier
+    InvalidAlias {
+        alias: String,
+    },
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Violation::UnknownTable { table } => write!(f, "table {} is not allowed", table),
+            Violation::UnknownColumn {
+                table: Some(table),
+                column,
+            } => write!(f, "column {}.{} is not allowed", table, column),
+            Violation::UnknownColumn {
+                table: None,
+                column,
+            } => {
+                write!(f, "column {} is not allowed", column)
+            }
+            Violation::UnknownAlias { alias, column } => write!(
+                f,
+                "{} in {}.{} is not a table of the query",
+                alias, alias, column
+            ),
+            Violation::UnknownFunction { name } => write!(f, "function {} is not allowed", name),
+            Violation::RawFragment { sql } => write!(f, "raw SQL {} is not allowed", sql),
+            Violation::InvalidAlias { alias } => write!(f, "alias {} is not an identifier", alias),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AllowlistError {
+    pub violations: Vec<Violation>,
+}
+
+impl fmt::Display for AllowlistError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, violation) in self.violations.iter().enumerate() {
+            if i > 0 {
+                write!(f, "; ")?;
+            }
+            write!(f, "{}", violation)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for AllowlistError {}
+
+#[derive(Debug, Clone, Default)]
+pub struct Allowlist {
+    // Lowercase table name to its columns as declared
+    tables: HashMap<String, Vec<String>>,
+    functions: HashSet<String>,
+    raw: HashSet<String>,
+}
+
+impl Allowlist {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn table(mut self, name: &str, columns: &[&str]) -> Self {
+        self.tables
+            .entry(name.to_lowercase())
+            .or_default()
+            .extend(columns.iter().map(|c| c.to_string()));
+        self
+    }
+
+    // Table declared with nv_table!, with all of its columns
+    pub fn schema<Tb: Table>(self) -> Self {
+        self.table(Tb::NAME, Tb::columns())
+    }
+
+    pub fn function(mut self, name: &str) -> Self {
+        self.functions.insert(name.to_lowercase());
+        self
+    }
+
+    // Raw fragment allowed verbatim, `*` and numbers always are
+    pub fn raw(mut self, sql: &str) -> Self {
+        self.raw.insert(sql.to_string());
+        self
+    }
+
+    pub fn check<T>(&self, select: &SelectStmt<T>) -> Result<(), AllowlistError> {
+        let mut checker = Checker {
+            allowlist: self,
+            scopes: Vec::new(),
+            violations: Vec::new(),
+        };
+        select.accept(&mut checker);
+        checker.into_result()
+    }
+
+    // Table and plain column names, as used by inserts
+    pub(crate) fn check_columns<'a>(
+        &self,
+        table: &str,
+        columns: impl IntoIterator<Item = &'a String>,
+    ) -> Result<(), AllowlistError> {
+        let mut violations = Vec::new();
+        match self.tables.get(&table.to_lowercase()) {
+            Some(allowed) => {
+                for column in columns {
+                    if !has_column(allowed, column, false) {
+                        violations.push(Violation::UnknownColumn {
+                            table: Some(table.to_string()),
+                            column: column.clone(),
+                        });
+                    }
+                }
+            }
+            None => violations.push(Violation::UnknownTable {
+                table: table.to_string(),
+            }),
+        }
+        into_result(violations)
+    }
+}
+
+fn has_column(columns: &[String], column: &str, quoted: bool) -> bool {
+    columns.iter().any(|c| {
+        if quoted {
+            c == column
+        } else {
+            c.eq_ignore_ascii_case(column)
+        }
+    })
+}
+
+pub(crate) fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    matches!(chars.next(), Some(c) if c.is_alphabetic() || c == '_')
+        && chars.all(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn into_result(violations: Vec<Violation>) -> Result<(), AllowlistError> {
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(AllowlistError { violations })
+    }
+}
+
+enum Source<'a> {
+    Table(&'a [String]),
+    // Output names of a FROM subquery, unnamed items cannot be referenced
+    Derived(Vec<String>),
+}
+
+// Tables of one SELECT by lowercase alias, or name when not aliased
+struct Scope<'a> {
+    sources: HashMap<String, Source<'a>>,
+    item_aliases: Vec<String>,
+    in_order_by: bool,
+}
+
+struct Checker<'a> {
+    allowlist: &'a Allowlist,
+    scopes: Vec<Scope<'a>>,
+    violations: Vec<Violation>,
+}
+
+impl<'a> Checker<'a> {
+    fn into_result(self) -> Result<(), AllowlistError> {
+        into_result(self.violations)
+    }
+
+    fn scope<T>(&mut self, select: &SelectStmt<T>) -> Scope<'a> {
+        let mut sources = HashMap::new();
+        let tables = select
+            .from
+            .iter()
+            .chain(select.joins.iter().map(|join| &join.table));
+        for table in tables {
+            match table {
+                TableRef::Table { name, alias, .. } => {
+                    let Some(columns) = self.allowlist.tables.get(&name.to_lowercase()) else {
+                        self.violations.push(Violation::UnknownTable {
+                            table: name.clone(),
+                        });
+                        continue;
+                    };
+                    if let Some(alias) = alias {
+                        self.check_alias(alias);
+                    }
+                    let key = alias.as_ref().unwrap_or(name).to_lowercase();
+                    sources.insert(key, Source::Table(columns));
+                }
+                TableRef::Subquery { query, alias } => {
+                    self.check_alias(alias);
+                    sources.insert(alias.to_lowercase(), Source::Derived(output_names(query)));
+                }
+            }
+        }
+
+        Scope {
+            sources,
+            item_aliases: select
+                .items
+                .iter()
+                .filter_map(|item| item.alias.clone())
+                .collect(),
+            in_order_by: false,
+        }
+    }
+
+    fn check_alias(&mut self, alias: &str) {
+        if !is_identifier(alias) {
+            self.violations.push(Violation::InvalidAlias {
+                alias: alias.to_string(),
+            });
+        }
+    }
+
+    fn check_column(&mut self, table_alias: Option<&str>, name: &str, quoted: bool) {
+        // Never matched against the declared columns, so a fragment passed
+        // as a column name cannot reach the SQL text
+        if !is_identifier(name) {
+            self.violations.push(Violation::UnknownColumn {
+                table: table_alias.map(str::to_string),
+                column: name.to_string(),
+            });
+            return;
+        }
+
+        let known = |source: &Source| match source {
+            Source::Table(columns) => has_column(columns, name, quoted),
+            Source::Derived(columns) => has_column(columns, name, quoted),
+        };
+
+        let found = match table_alias {
+            Some(alias) => {
+                let source = self
+                    .scopes
+                    .iter()
+                    .rev()
+                    .find_map(|scope| scope.sources.get(&alias.to_lowercase()));
+                match source {
+                    Some(source) => known(source),
+                    None => {
+                        self.violations.push(Violation::UnknownAlias {
+                            alias: alias.to_string(),
+                            column: name.to_string(),
+                        });
+                        return;
+                    }
+                }
+            }
+            None => self.scopes.iter().rev().enumerate().any(|(depth, scope)| {
+                (depth == 0 && scope.in_order_by && has_column(&scope.item_aliases, name, quoted))
+                    || scope.sources.values().any(known)
+            }),
+        };
+
+        if !found {
+            self.violations.push(Violation::UnknownColumn {
+                table: table_alias.map(str::to_string),
+                column: name.to_string(),
+            });
+        }
+    }
+}
+
+impl<T> Visitor<T> for Checker<'_> {
+    fn visit_select(&mut self, select: &SelectStmt<T>) {
+        let scope = self.scope(select);
+        self.scopes.push(scope);
+        walk_select(self, select);
+        self.scopes.pop();
+    }
+
+    fn visit_select_item(&mut self, item: &SelectItem<T>) {
+        if let Some(alias) = &item.alias {
+            self.check_alias(alias);
+        }
+        walk_select_item(self, item);
+    }
+
+    fn visit_order_by_item(&mut self, item: &OrderByItem<T>) {
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.in_order_by = true;
+        }
+        walk_order_by_item(self, item);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<T>) {
+        match expr {
+            Expr::Column {
+                table_alias,
+                name,
+                quoted,
+            } => self.check_column(table_alias.as_deref(), name, *quoted),
+            Expr::Raw(sql) | Expr::Literal(sql)
+                if sql != "*"
+                    && sql.parse::<f64>().is_err()
+                    && !self.allowlist.raw.contains(sql) =>
+            {
+                self.violations
+                    .push(Violation::RawFragment { sql: sql.clone() });
+            }
+            Expr::Function { name, .. }
+                if !self.allowlist.functions.contains(&name.to_lowercase()) =>
+            {
+                self.violations
+                    .push(Violation::UnknownFunction { name: name.clone() });
+            }
+            _ => {}
+        }
+        walk_expr(self, expr);
+    }
+}
+
+fn output_names<T>(select: &SelectStmt<T>) -> Vec<String> {
+    select
+        .items
+        .iter()
+        .filter_map(|item| match (&item.alias, &item.expr) {
+            (Some(alias), _) => Some(alias.clone()),
+            (None, Expr::Column { name, .. }) => Some(name.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, NvSelect, Select, SortType,
+        SqlAggregateFunction, SqlJoinType, SqlOperator,
+    };
+
+    type Param = DefaultPostgresParamType;
+
+    fn allowlist() -> Allowlist {
+        Allowlist::new()
+            .table("users", &["id", "name", "team_id", "created_at"])
+            .table("teams", &["id", "name"])
+            .function("LOWER")
+    }
+
+    #[test]
+    fn test_allowlist_reports_every_violation() {
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "name",
+                Some("u"),
+                Some("user_name"),
+                SqlAggregateFunction::None,
+                false,
+            )
+            .fn_static("LOWER", &["u.name"], Some("lower_name"))
+            .from(|f| {
+                f.add_table_with_alias("users", Some("u")).add_subquery(
+                    Select::new(DatabaseDialect::PostgreSQL)
+                        .f("id", None, Some("team"), SqlAggregateFunction::None, false)
+                        .from_table("teams", None),
+                    "t",
+                )
+            })
+            .where_clause(|w| {
+                w.add_condition("t.team", SqlOperator::Equal, Param::Int(1))
+                    .unwrap()
+                    .add_condition("created_at", SqlOperator::Greater, Param::Int(2))
+                    .unwrap()
+            })
+            .order_by("user_name", None, SortType::Descending);
+        assert_eq!(allowlist().raw("u.name").check(select.statement()), Ok(()));
+
+        // Client supplied sort column, filter field and alias
+        let select = select
+            .order_by("password; DROP TABLE users", None, SortType::Ascending)
+            .order_by("password", Some("u"), SortType::Ascending)
+            .where_clause(|w| {
+                w.add_condition("x.id", SqlOperator::Equal, Param::Int(3))
+                    .unwrap()
+            })
+            .join(SqlJoinType::InnerJoin, "secrets", Some("s"), |on| {
+                on.add_column_condition("s.user_id", SqlOperator::Equal, "u.id")
+            })
+            .f(
+                "id",
+                Some("u"),
+                Some("id\" FROM x --"),
+                SqlAggregateFunction::None,
+                false,
+            );
+        let error = allowlist().check(select.statement()).unwrap_err();
+        assert_eq!(
+            error.violations,
+            vec![
+                Violation::UnknownTable {
+                    table: "secrets".to_string()
+                },
+                Violation::RawFragment {
+                    sql: "u.name".to_string()
+                },
+                Violation::InvalidAlias {
+                    alias: "id\" FROM x --".to_string()
+                },
+                Violation::UnknownAlias {
+                    alias: "s".to_string(),
+                    column: "user_id".to_string()
+                },
+                Violation::UnknownAlias {
+                    alias: "x".to_string(),
+                    column: "id".to_string()
+                },
+                Violation::UnknownColumn {
+                    table: None,
+                    column: "password; DROP TABLE users".to_string()
+                },
+                Violation::UnknownColumn {
+                    table: Some("u".to_string()),
+                    column: "password".to_string()
+                },
+            ]
+        );
+
+        // A derived table without names for its output hides no columns
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("t"), None, SqlAggregateFunction::None, false)
+            .from(|f| {
+                f.add_subquery(
+                    Select::new(DatabaseDialect::PostgreSQL)
+                        .f("id", None, Some("id"), SqlAggregateFunction::None, false)
+                        .f("id", None, None, SqlAggregateFunction::Count, false)
+                        .from_table("users", None),
+                    "t",
+                )
+            })
+            .order_by(
+                "(SELECT password FROM secrets LIMIT 1)",
+                None,
+                SortType::Ascending,
+            )
+            .order_by("count", None, SortType::Ascending);
+        assert_eq!(
+            allowlist()
+                .check(select.statement())
+                .unwrap_err()
+                .violations,
+            vec![
+                Violation::UnknownColumn {
+                    table: None,
+                    column: "(SELECT password FROM secrets LIMIT 1)".to_string()
+                },
+                Violation::UnknownColumn {
+                    table: None,
+                    column: "count".to_string()
+                },
+            ]
+        );
+
+        let select = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "email".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &None)
+            .end_from_table_block();
+        assert_eq!(
+            select
+                .generate_query_checked(&allowlist(), false)
+                .unwrap_err()
+                .to_string(),
+            "column email is not allowed"
+        );
+    }
+}
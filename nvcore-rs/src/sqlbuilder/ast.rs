@@ -0,0 +1,253 @@
+// Typed SQL syntax tree
+//
+// The fluent builders produce these nodes and `Renderer` turns them into SQL
+// text plus the ordered parameter list. Nodes carry no dialect and no
+// placeholder indexes, so a tree can be inspected, rewritten and rendered for
+// any dialect after it has been built.
+
+use crate::sqlbuilder::{
+    AggregateOptions, SortType, SqlAggregateFunction, SqlJoinType, SqlOperator,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Expr<T> {
+    Column {
+        table_alias: Option<String>,
+        name: String,
+        quoted: bool,
+    },
+    // Bound value, rendered as the dialect placeholder
+    Param(T),
+    // Parameter bound by name when the query is executed
+    Named(String),
+    // SQL fragment emitted as is
+    Raw(String),
+    // Number or DATE / TIMESTAMP literal as written in the parsed SQL, see
+    // parse_select_keeping_literals
+    Literal(String),
+    // `*` or `alias.*`, in a select list or as the COUNT(*) argument
+    Wildcard {
+        table_alias: Option<String>,
+    },
+    Function {
+        name: String,
+        args: Vec<Expr<T>>,
+    },
+    // Any SqlAggregateFunction call, including the scalar ToUpper/ToIso8601*
+    Aggregate {
+        function: SqlAggregateFunction,
+        arg: Box<Expr<T>>,
+        options: AggregateOptions,
+    },
+    Subquery(Box<SelectStmt<T>>),
+}
+
+impl<T> Expr<T> {
+    pub fn column(table_alias: Option<&str>, name: &str) -> Self {
+        Expr::Column {
+            table_alias: table_alias.map(str::to_string),
+            name: name.to_string(),
+            quoted: false,
+        }
+    }
+
+    // Named parameter, a leading ':' is accepted and dropped
+    pub fn named(name: &str) -> Self {
+        Expr::Named(name.trim_start_matches(':').to_string())
+    }
+
+    // Column from a free-form reference such as "u.id", anything that is not a
+    // plain (alias.)identifier is kept as a raw fragment
+    pub fn column_ref(reference: &str) -> Self {
+        let is_identifier = |text: &str| {
+            !text.is_empty()
+                && text
+                    .chars()
+                    .all(|c| c.is_alphanumeric() || c == '_' || c == '.' || c == '$')
+        };
+        if reference == "*" {
+            return Expr::Wildcard { table_alias: None };
+        }
+        if let Some(alias) = reference
+            .strip_suffix(".*")
+            .filter(|alias| is_identifier(alias))
+        {
+            return Expr::Wildcard {
+                table_alias: Some(alias.to_string()),
+            };
+        }
+        if !is_identifier(reference) {
+            return Expr::Raw(reference.to_string());
+        }
+
+        match reference.rsplit_once('.') {
+            Some((alias, name)) => Expr::column(Some(alias), name),
+            None => Expr::column(None, reference),
+        }
+    }
+
+    // Interleaves raw and bound arguments following a parameter list format
+    // such as "svv", 's' takes the next static value and 'v' the next bound one
+    pub fn function_arguments(
+        parameter_format: &str,
+        values: impl IntoIterator<Item = T>,
+        static_values: impl IntoIterator<Item = String>,
+    ) -> Vec<Self> {
+        let mut values = values.into_iter();
+        let mut static_values = static_values.into_iter();
+        let mut arguments = Vec::new();
+
+        for ch in parameter_format.chars() {
+            match ch {
+                's' => arguments.extend(static_values.next().map(Expr::Raw)),
+                'v' => arguments.extend(values.next().map(Expr::Param)),
+                _ => {}
+            }
+        }
+
+        arguments
+    }
+
+    // Wraps the expression in an aggregate call, SqlAggregateFunction::None
+    // leaves it untouched
+    pub fn aggregate(self, function: SqlAggregateFunction, options: AggregateOptions) -> Self {
+        if function == SqlAggregateFunction::None && options.filter.is_none() {
+            return self;
+        }
+        Expr::Aggregate {
+            function,
+            arg: Box::new(self),
+            options,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectItem<T> {
+    pub expr: Expr<T>,
+    pub alias: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum TableRef<T> {
+    // A quoted name is quoted part by part with the dialect quote when
+    // rendered, `schema.table` stays dotted
+    Table {
+        name: String,
+        alias: Option<String>,
+        #[cfg_attr(feature = "serde", serde(default))]
+        quoted: bool,
+    },
+    Subquery {
+        query: Box<SelectStmt<T>>,
+        alias: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Join<T> {
+    pub join_type: SqlJoinType,
+    pub table: TableRef<T>,
+    pub on: Option<Predicate<T>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Predicate<T> {
+    // BETWEEN takes two right operands, IN one or more
+    Compare {
+        left: Expr<T>,
+        op: SqlOperator,
+        right: Vec<Expr<T>>,
+    },
+    And(Vec<Predicate<T>>),
+    Or(Vec<Predicate<T>>),
+    // Parentheses written by the caller, precedence parentheses are added by
+    // the renderer
+    Group(Box<Predicate<T>>),
+}
+
+impl<T> Predicate<T> {
+    pub fn and(self, other: Predicate<T>) -> Self {
+        match self {
+            Predicate::And(mut predicates) => {
+                predicates.push(other);
+                Predicate::And(predicates)
+            }
+            predicate => Predicate::And(vec![predicate, other]),
+        }
+    }
+
+    pub fn or(self, other: Predicate<T>) -> Self {
+        match self {
+            Predicate::Or(mut predicates) => {
+                predicates.push(other);
+                Predicate::Or(predicates)
+            }
+            predicate => Predicate::Or(vec![predicate, other]),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct OrderByItem<T> {
+    pub expr: Expr<T>,
+    pub sort: Option<SortType>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SelectStmt<T> {
+    pub items: Vec<SelectItem<T>>,
+    pub from: Vec<TableRef<T>>,
+    pub joins: Vec<Join<T>>,
+    pub selection: Option<Predicate<T>>,
+    pub order_by: Vec<OrderByItem<T>>,
+    pub limit: Option<u64>,
+    pub offset: Option<u64>,
+}
+
+impl<T> Default for SelectStmt<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> SelectStmt<T> {
+    pub fn new() -> Self {
+        Self {
+            items: Vec::new(),
+            from: Vec::new(),
+            joins: Vec::new(),
+            selection: None,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+        }
+    }
+
+    // ANDs a predicate into the WHERE clause
+    pub fn add_selection(&mut self, predicate: Predicate<T>) {
+        self.selection = Some(match self.selection.take() {
+            Some(selection) => selection.and(predicate),
+            None => predicate,
+        });
+    }
+}
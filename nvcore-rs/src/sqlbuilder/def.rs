@@ -1,11 +1,20 @@
 #![allow(dead_code)]
 
 // use chrono::{TimeZone, Utc};
+use crate::sqlbuilder::{
+    InsertError, MySqlDialect, OracleDialect, PostgreSqlDialect, RenderError, SqlDialect,
+    SqlServerDialect, SqliteDialect,
+};
 use std::fmt;
 use std::time::SystemTime;
 
 // Define DefaultPostgresParamType to support comprehensive PostgreSQL data types
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum DefaultPostgresParamType {
     SmallInt(i16),         // Small Int
     Int(i32),              // Integer
@@ -27,17 +36,43 @@ impl fmt::Display for DefaultPostgresParamType {
             DefaultPostgresParamType::Double(value) => write!(f, "{}", value),
             DefaultPostgresParamType::String(value) => write!(f, "{}", value),
             DefaultPostgresParamType::Bool(value) => write!(f, "{}", value),
-            DefaultPostgresParamType::TimePoint(value) => {
-                let duration = value.duration_since(SystemTime::UNIX_EPOCH).unwrap();
-                write!(f, "{}", duration.as_secs())
-            },
+            DefaultPostgresParamType::TimePoint(value) => write!(f, "{}", unix_seconds(value)),
         }
     }
 }
 
 
+// Plain values into the default parameter types, used by typed columns
+macro_rules! param_from {
+    ($param:ident { $($variant:ident($ty:ty)),* $(,)? }) => {
+        $(
+            impl From<$ty> for $param {
+                fn from(value: $ty) -> Self {
+                    $param::$variant(value)
+                }
+            }
+        )*
+    };
+}
+
+param_from!(DefaultPostgresParamType {
+    SmallInt(i16),
+    Int(i32),
+    BigInt(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Bool(bool),
+    TimePoint(SystemTime),
+});
+
 // Define DefaultOracleParamType to support comprehensive Oracle data types
 #[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum DefaultOracleParamType {
     Int(i32),       // Oracle NUMBER, INTEGER
     LongLong(i64),  // Oracle NUMBER (large integer)
@@ -51,29 +86,344 @@ pub enum DefaultOracleParamType {
     RawBlob(Vec<u8>), // Oracle RAW, BLOB
 }
 
+// Define DefaultMySqlParamType to support comprehensive MySQL/MariaDB data types
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DefaultMySqlParamType {
+    TinyInt(i8),           // TINYINT
+    SmallInt(i16),         // SMALLINT
+    Int(i32),              // INT, MEDIUMINT
+    BigInt(i64),           // BIGINT
+    UnsignedBigInt(u64),   // BIGINT UNSIGNED
+    Float(f32),            // FLOAT
+    Double(f64),           // DOUBLE, DECIMAL
+    String(String),        // CHAR, VARCHAR, TEXT, ENUM, SET, JSON
+    Bool(bool),            // BOOL, TINYINT(1)
+    TimePoint(SystemTime), // DATETIME, TIMESTAMP
+    Blob(Vec<u8>),         // BINARY, VARBINARY, BLOB
+}
+
+param_from!(DefaultOracleParamType {
+    Int(i32),
+    LongLong(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Bool(bool),
+    TimePoint(SystemTime),
+    RawBlob(Vec<u8>),
+});
+
+param_from!(DefaultMySqlParamType {
+    TinyInt(i8),
+    SmallInt(i16),
+    Int(i32),
+    BigInt(i64),
+    UnsignedBigInt(u64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Bool(bool),
+    TimePoint(SystemTime),
+    Blob(Vec<u8>),
+});
+
+impl fmt::Display for DefaultMySqlParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultMySqlParamType::TinyInt(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::SmallInt(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::Int(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::BigInt(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::UnsignedBigInt(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::Float(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::Double(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::String(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::Bool(value) => write!(f, "{}", value),
+            DefaultMySqlParamType::TimePoint(value) => write!(f, "{}", unix_seconds(value)),
+            DefaultMySqlParamType::Blob(value) => write!(f, "<{} bytes>", value.len()),
+        }
+    }
+}
+
+
+// Define DefaultSqliteParamType to support SQLite storage classes
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DefaultSqliteParamType {
+    Null,                  // NULL
+    Int(i32),              // INTEGER
+    BigInt(i64),           // INTEGER
+    Double(f64),           // REAL
+    String(String),        // TEXT
+    Bool(bool),            // INTEGER 0 / 1
+    TimePoint(SystemTime), // TEXT as ISO-8601 'YYYY-MM-DD HH:MM:SS'
+    Blob(Vec<u8>),         // BLOB
+}
+
+param_from!(DefaultSqliteParamType {
+    Int(i32),
+    BigInt(i64),
+    Double(f64),
+    String(String),
+    Bool(bool),
+    TimePoint(SystemTime),
+    Blob(Vec<u8>),
+});
+
+impl fmt::Display for DefaultSqliteParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultSqliteParamType::Null => write!(f, "NULL"),
+            DefaultSqliteParamType::Int(value) => write!(f, "{}", value),
+            DefaultSqliteParamType::BigInt(value) => write!(f, "{}", value),
+            DefaultSqliteParamType::Double(value) => write!(f, "{}", value),
+            DefaultSqliteParamType::String(value) => write!(f, "{}", value),
+            DefaultSqliteParamType::Bool(value) => write!(f, "{}", value),
+            DefaultSqliteParamType::TimePoint(value) => {
+                write!(f, "{}", sqlite_time_point_to_string(value))
+            }
+            DefaultSqliteParamType::Blob(value) => write!(f, "<{} bytes>", value.len()),
+        }
+    }
+}
+
+// Define DefaultSqlServerParamType to support SQL Server data types
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum DefaultSqlServerParamType {
+    TinyInt(u8),           // TINYINT
+    SmallInt(i16),         // SMALLINT
+    Int(i32),              // INT
+    BigInt(i64),           // BIGINT
+    Float(f32),            // REAL
+    Double(f64),           // FLOAT, DECIMAL
+    String(String),        // CHAR, VARCHAR, NVARCHAR
+    Bool(bool),            // BIT
+    TimePoint(SystemTime), // DATETIME2
+    Blob(Vec<u8>),         // BINARY, VARBINARY
+}
+
+param_from!(DefaultSqlServerParamType {
+    TinyInt(u8),
+    SmallInt(i16),
+    Int(i32),
+    BigInt(i64),
+    Float(f32),
+    Double(f64),
+    String(String),
+    Bool(bool),
+    TimePoint(SystemTime),
+    Blob(Vec<u8>),
+});
+
+impl fmt::Display for DefaultSqlServerParamType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DefaultSqlServerParamType::TinyInt(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::SmallInt(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::Int(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::BigInt(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::Float(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::Double(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::String(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::Bool(value) => write!(f, "{}", value),
+            DefaultSqlServerParamType::TimePoint(value) => write!(f, "{}", unix_seconds(value)),
+            DefaultSqlServerParamType::Blob(value) => write!(f, "<{} bytes>", value.len()),
+        }
+    }
+}
+
+// Whole seconds since the Unix epoch, negative for time points before 1970
+fn unix_seconds(value: &SystemTime) -> i64 {
+    chrono::DateTime::<chrono::Utc>::from(*value).timestamp()
+}
+
+// SQLite has no date type, time points are stored as ISO-8601 text so the
+// built-in date and time functions understand them
+pub fn sqlite_time_point_to_string(value: &SystemTime) -> String {
+    let datetime: chrono::DateTime<chrono::Utc> = (*value).into();
+    datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+}
 
 // Define a trait for parameter types
 pub trait ParameterType {}
 
+// Renders a bound value as a literal of the dialect, used to inline values in
+// debug SQL
+pub trait ToSqlLiteral {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String;
+}
+
+impl ToSqlLiteral for DefaultPostgresParamType {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String {
+        match self {
+            DefaultPostgresParamType::SmallInt(value) => value.to_string(),
+            DefaultPostgresParamType::Int(value) => value.to_string(),
+            DefaultPostgresParamType::BigInt(value) => value.to_string(),
+            DefaultPostgresParamType::Float(value) => value.to_string(),
+            DefaultPostgresParamType::Double(value) => value.to_string(),
+            DefaultPostgresParamType::String(value) => generate_string_literal(dialect, value),
+            DefaultPostgresParamType::Bool(value) => generate_boolean_literal(dialect, *value),
+            DefaultPostgresParamType::TimePoint(value) => {
+                generate_time_point_literal(dialect, value)
+            }
+        }
+    }
+}
+
+impl ToSqlLiteral for DefaultOracleParamType {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String {
+        match self {
+            DefaultOracleParamType::Int(value) => value.to_string(),
+            DefaultOracleParamType::LongLong(value) => value.to_string(),
+            DefaultOracleParamType::Float(value) => value.to_string(),
+            DefaultOracleParamType::Double(value) => value.to_string(),
+            DefaultOracleParamType::String(value) => generate_string_literal(dialect, value),
+            DefaultOracleParamType::Bool(value) => generate_boolean_literal(dialect, *value),
+            DefaultOracleParamType::TimePoint(value) => generate_time_point_literal(dialect, value),
+            DefaultOracleParamType::RawBlob(value) => generate_blob_literal(dialect, value),
+        }
+    }
+}
+
+impl ToSqlLiteral for DefaultMySqlParamType {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String {
+        match self {
+            DefaultMySqlParamType::TinyInt(value) => value.to_string(),
+            DefaultMySqlParamType::SmallInt(value) => value.to_string(),
+            DefaultMySqlParamType::Int(value) => value.to_string(),
+            DefaultMySqlParamType::BigInt(value) => value.to_string(),
+            DefaultMySqlParamType::UnsignedBigInt(value) => value.to_string(),
+            DefaultMySqlParamType::Float(value) => value.to_string(),
+            DefaultMySqlParamType::Double(value) => value.to_string(),
+            DefaultMySqlParamType::String(value) => generate_string_literal(dialect, value),
+            DefaultMySqlParamType::Bool(value) => generate_boolean_literal(dialect, *value),
+            DefaultMySqlParamType::TimePoint(value) => generate_time_point_literal(dialect, value),
+            DefaultMySqlParamType::Blob(value) => generate_blob_literal(dialect, value),
+        }
+    }
+}
+
+impl ToSqlLiteral for DefaultSqliteParamType {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String {
+        match self {
+            DefaultSqliteParamType::Null => "NULL".to_string(),
+            DefaultSqliteParamType::Int(value) => value.to_string(),
+            DefaultSqliteParamType::BigInt(value) => value.to_string(),
+            DefaultSqliteParamType::Double(value) => value.to_string(),
+            DefaultSqliteParamType::String(value) => generate_string_literal(dialect, value),
+            DefaultSqliteParamType::Bool(value) => generate_boolean_literal(dialect, *value),
+            DefaultSqliteParamType::TimePoint(value) => generate_time_point_literal(dialect, value),
+            DefaultSqliteParamType::Blob(value) => generate_blob_literal(dialect, value),
+        }
+    }
+}
+
+impl ToSqlLiteral for DefaultSqlServerParamType {
+    fn to_sql_literal(&self, dialect: &DatabaseDialect) -> String {
+        match self {
+            DefaultSqlServerParamType::TinyInt(value) => value.to_string(),
+            DefaultSqlServerParamType::SmallInt(value) => value.to_string(),
+            DefaultSqlServerParamType::Int(value) => value.to_string(),
+            DefaultSqlServerParamType::BigInt(value) => value.to_string(),
+            DefaultSqlServerParamType::Float(value) => value.to_string(),
+            DefaultSqlServerParamType::Double(value) => value.to_string(),
+            DefaultSqlServerParamType::String(value) => generate_string_literal(dialect, value),
+            DefaultSqlServerParamType::Bool(value) => generate_boolean_literal(dialect, *value),
+            DefaultSqlServerParamType::TimePoint(value) => {
+                generate_time_point_literal(dialect, value)
+            }
+            DefaultSqlServerParamType::Blob(value) => generate_blob_literal(dialect, value),
+        }
+    }
+}
+
 impl ParameterType for DefaultPostgresParamType {}
 impl ParameterType for DefaultOracleParamType {}
+impl ParameterType for DefaultMySqlParamType {}
+impl ParameterType for DefaultSqliteParamType {}
+impl ParameterType for DefaultSqlServerParamType {}
 
-#[derive(Debug, PartialEq,Eq,Clone,Copy)]
+#[derive(Clone, Copy)]
 pub enum DatabaseDialect {
     PostgreSQL,
     Oracle,
+    MySQL,
+    SQLite,
+    SqlServer,
+    Custom(&'static dyn SqlDialect),
 }
 
-impl fmt::Display for DatabaseDialect {
+static POSTGRESQL_DIALECT: PostgreSqlDialect = PostgreSqlDialect;
+static ORACLE_DIALECT: OracleDialect = OracleDialect;
+static MYSQL_DIALECT: MySqlDialect = MySqlDialect;
+static SQLITE_DIALECT: SqliteDialect = SqliteDialect;
+static SQLSERVER_DIALECT: SqlServerDialect = SqlServerDialect;
+
+impl DatabaseDialect {
+    pub fn sql_dialect(&self) -> &'static dyn SqlDialect {
+        match self {
+            DatabaseDialect::PostgreSQL => &POSTGRESQL_DIALECT,
+            DatabaseDialect::Oracle => &ORACLE_DIALECT,
+            DatabaseDialect::MySQL => &MYSQL_DIALECT,
+            DatabaseDialect::SQLite => &SQLITE_DIALECT,
+            DatabaseDialect::SqlServer => &SQLSERVER_DIALECT,
+            DatabaseDialect::Custom(dialect) => *dialect,
+        }
+    }
+}
+
+// Custom dialects are compared by name
+impl PartialEq for DatabaseDialect {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (DatabaseDialect::Custom(a), DatabaseDialect::Custom(b)) => a.name() == b.name(),
+            (DatabaseDialect::Custom(_), _) | (_, DatabaseDialect::Custom(_)) => false,
+            _ => std::mem::discriminant(self) == std::mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for DatabaseDialect {}
+
+impl fmt::Debug for DatabaseDialect {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             DatabaseDialect::PostgreSQL => write!(f, "PostgreSQL"),
             DatabaseDialect::Oracle => write!(f, "Oracle"),
+            DatabaseDialect::MySQL => write!(f, "MySQL"),
+            DatabaseDialect::SQLite => write!(f, "SQLite"),
+            DatabaseDialect::SqlServer => write!(f, "SqlServer"),
+            DatabaseDialect::Custom(dialect) => write!(f, "Custom({})", dialect.name()),
         }
     }
 }
 
-#[derive(Debug)]
+impl fmt::Display for DatabaseDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.sql_dialect().name())
+    }
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SortType {
     Ascending,
     Descending,
@@ -89,6 +439,11 @@ impl fmt::Display for SortType {
 }
 
 #[derive(Debug,PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SqlOperator {
     Equal,
     NotEqual,
@@ -97,8 +452,10 @@ pub enum SqlOperator {
     Greater,
     GreaterOrEqual,
     Like,
+    ILike,
     Between,
     In,
+    Regexp,
 }
 
 impl fmt::Display for SqlOperator {
@@ -111,13 +468,20 @@ impl fmt::Display for SqlOperator {
             SqlOperator::Greater => write!(f, ">"),
             SqlOperator::GreaterOrEqual => write!(f, ">="),
             SqlOperator::Like => write!(f, "LIKE"),
+            SqlOperator::ILike => write!(f, "ILIKE"),
             SqlOperator::Between => write!(f, "BETWEEN"),
             SqlOperator::In => write!(f, "IN"),
+            SqlOperator::Regexp => write!(f, "REGEXP"),
         }
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum LogicOperator {
     And,
     Or,
@@ -133,12 +497,22 @@ impl fmt::Display for LogicOperator {
 }
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SqlAggregateFunction {
     None,
     Distinct,
     Count,
     Avg,
     Sum,
+    Min,
+    Max,
+    CountDistinct,
+    StringAgg,
+    ArrayAgg,
     ToUpper,
     ToLower,
     ToIso8601DateTime,
@@ -146,6 +520,23 @@ pub enum SqlAggregateFunction {
     ToIso8601Time,
 }
 
+impl SqlAggregateFunction {
+    // Whether the function folds many rows into one, FILTER only applies to these
+    pub fn is_aggregate(&self) -> bool {
+        matches!(
+            self,
+            SqlAggregateFunction::Count
+                | SqlAggregateFunction::Avg
+                | SqlAggregateFunction::Sum
+                | SqlAggregateFunction::Min
+                | SqlAggregateFunction::Max
+                | SqlAggregateFunction::CountDistinct
+                | SqlAggregateFunction::StringAgg
+                | SqlAggregateFunction::ArrayAgg
+        )
+    }
+}
+
 impl fmt::Display for SqlAggregateFunction {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -154,6 +545,11 @@ impl fmt::Display for SqlAggregateFunction {
             SqlAggregateFunction::Count => write!(f, "Count"),
             SqlAggregateFunction::Avg => write!(f, "Avg"),
             SqlAggregateFunction::Sum => write!(f, "Sum"),
+            SqlAggregateFunction::Min => write!(f, "Min"),
+            SqlAggregateFunction::Max => write!(f, "Max"),
+            SqlAggregateFunction::CountDistinct => write!(f, "CountDistinct"),
+            SqlAggregateFunction::StringAgg => write!(f, "StringAgg"),
+            SqlAggregateFunction::ArrayAgg => write!(f, "ArrayAgg"),
             SqlAggregateFunction::ToUpper => write!(f, "ToUpper"),
             SqlAggregateFunction::ToLower => write!(f, "ToLower"),
             SqlAggregateFunction::ToIso8601DateTime => write!(f, "ToIso8601DateTime"),
@@ -163,7 +559,12 @@ impl fmt::Display for SqlAggregateFunction {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Clone, Copy)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
 pub enum SqlJoinType {
     None,
     InnerJoin,
@@ -203,10 +604,144 @@ impl fmt::Display for JoinDefMode {
 
 // Function to determine the parameter format based on the dialect
 pub fn determine_parameter_format(dialect: &DatabaseDialect, parameter_index: u32) -> String {
-    match dialect {
-        DatabaseDialect::PostgreSQL => format!("${}", parameter_index),
-        DatabaseDialect::Oracle => format!(":{}", parameter_index),
+    dialect.sql_dialect().parameter_placeholder(parameter_index)
+}
+
+// Function to quote an identifier (table, column or alias) based on the dialect
+pub fn quote_identifier(dialect: &DatabaseDialect, identifier: &str) -> String {
+    dialect.sql_dialect().quote_identifier(identifier)
+}
+
+// Function to generate a comparison expression, `first_parameter_index` is the
+// index of the first bound value and `value_size` the number of bound values
+pub fn generate_comparison(
+    dialect: &DatabaseDialect,
+    field: &str,
+    op: SqlOperator,
+    first_parameter_index: u32,
+    value_size: u32,
+) -> Result<String, RenderError> {
+    dialect
+        .sql_dialect()
+        .comparison(field, op, first_parameter_index, value_size)
+}
+
+// Function to generate the row limiting clause based on the dialect
+pub fn generate_limit_offset(
+    dialect: &DatabaseDialect,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> String {
+    dialect.sql_dialect().limit_offset(limit, offset)
+}
+
+// Function to generate the row limiting clause placed right after SELECT
+pub fn generate_top_clause(
+    dialect: &DatabaseDialect,
+    limit: Option<u64>,
+    offset: Option<u64>,
+) -> String {
+    dialect.sql_dialect().top_clause(limit, offset)
+}
+
+// Function to check whether the row limiting clause can only be used after ORDER BY
+pub fn paging_requires_order_by(dialect: &DatabaseDialect, offset: Option<u64>) -> bool {
+    dialect.sql_dialect().paging_requires_order_by(offset)
+}
+
+// Function to generate the upsert clause appended after INSERT ... VALUES (...)
+pub fn generate_upsert_clause(
+    dialect: &DatabaseDialect,
+    conflict_keys: &[String],
+    update_columns: &[String],
+) -> Result<String, InsertError> {
+    if conflict_keys.is_empty() {
+        return Err(InsertError::EmptyConflictKeys);
     }
+    dialect
+        .sql_dialect()
+        .upsert_clause(conflict_keys, update_columns)
+        .ok_or(InsertError::UpsertIsMerge { dialect: *dialect })
+}
+
+// Function to wrap an expression with an aggregate or scalar function
+pub fn generate_aggregate_function(
+    dialect: &DatabaseDialect,
+    fn_type: SqlAggregateFunction,
+    expression: &str,
+) -> String {
+    dialect.sql_dialect().aggregate_function(fn_type, expression)
+}
+
+// Function to generate the alias suffix of a table or derived table
+pub fn generate_table_alias(dialect: &DatabaseDialect, alias: &str) -> String {
+    if dialect.sql_dialect().supports_table_alias_as() {
+        format!(" AS {}", alias)
+    } else {
+        format!(" {}", alias)
+    }
+}
+
+// Function to generate an aggregate call with an optional STRING_AGG separator
+// and FILTER (WHERE ...) predicate, dialects without FILTER get the predicate
+// folded into the argument as CASE WHEN predicate THEN expression END
+pub fn generate_aggregate_call(
+    dialect: &DatabaseDialect,
+    fn_type: SqlAggregateFunction,
+    expression: &str,
+    separator: Option<&str>,
+    filter: Option<&str>,
+) -> String {
+    let sql_dialect = dialect.sql_dialect();
+    let filter = filter.filter(|_| fn_type.is_aggregate());
+    let native_filter = sql_dialect.supports_aggregate_filter();
+
+    let argument = match filter {
+        Some(predicate) if !native_filter => {
+            let value = if expression == "*" || expression.ends_with(".*") {
+                "1"
+            } else {
+                expression
+            };
+            format!("CASE WHEN {} THEN {} END", predicate, value)
+        }
+        _ => expression.to_string(),
+    };
+
+    let mut call = match (fn_type, separator) {
+        (SqlAggregateFunction::StringAgg, Some(separator)) => {
+            sql_dialect.string_aggregate(&argument, separator)
+        }
+        _ => sql_dialect.aggregate_function(fn_type, &argument),
+    };
+
+    if let Some(predicate) = filter {
+        if native_filter {
+            call.push_str(&format!(" FILTER (WHERE {})", predicate));
+        }
+    }
+
+    call
+}
+
+// Function to generate a boolean literal based on the dialect
+pub fn generate_boolean_literal(dialect: &DatabaseDialect, value: bool) -> String {
+    dialect.sql_dialect().boolean_literal(value).to_string()
+}
+
+// Function to generate a quoted string literal based on the dialect
+pub fn generate_string_literal(dialect: &DatabaseDialect, value: &str) -> String {
+    dialect.sql_dialect().string_literal(value)
+}
+
+// Function to generate a time point literal based on the dialect
+pub fn generate_time_point_literal(dialect: &DatabaseDialect, value: &SystemTime) -> String {
+    dialect.sql_dialect().time_point_literal(value)
+}
+
+// Function to generate a binary literal based on the dialect
+pub fn generate_blob_literal(dialect: &DatabaseDialect, value: &[u8]) -> String {
+    dialect.sql_dialect().blob_literal(value)
 }
 
 // Function to convert SqlOperator to a string representation
@@ -219,12 +754,23 @@ pub fn sql_operator_to_string(op: SqlOperator) -> String {
         SqlOperator::Greater => ">".to_string(),
         SqlOperator::GreaterOrEqual => ">=".to_string(),
         SqlOperator::Like => "LIKE".to_string(),
+        SqlOperator::ILike => "ILIKE".to_string(),
         SqlOperator::Between => "BETWEEN".to_string(),
         SqlOperator::In => "IN".to_string(),
+        SqlOperator::Regexp => "REGEXP".to_string(),
     }
 }
 
 // Function to convert LogicOperator to a string representation
+pub fn join_type_to_string(join: SqlJoinType) -> &'static str {
+    match join {
+        SqlJoinType::None => "JOIN",
+        SqlJoinType::InnerJoin => "INNER JOIN",
+        SqlJoinType::LeftJoin => "LEFT JOIN",
+        SqlJoinType::RightJoin => "RIGHT JOIN",
+    }
+}
+
 pub fn logic_operator_to_string(logic: LogicOperator) -> String {
     match logic {
         LogicOperator::And => "AND".to_string(),
@@ -235,7 +781,7 @@ pub fn logic_operator_to_string(logic: LogicOperator) -> String {
 // Function to generate indentation for pretty printing
 pub fn generate_indentation(level: u32, indent_char: char, number_per_print: u32) -> String {
     let n = (number_per_print * level) as usize;
-    std::iter::repeat(indent_char).take(n).collect()
+    std::iter::repeat_n(indent_char, n).collect()
 }
 
 #[cfg(test)]
@@ -246,5 +792,116 @@ mod tests {
     fn test_database_dialect_display() {
         assert_eq!(DatabaseDialect::PostgreSQL.to_string(), "PostgreSQL");
         assert_eq!(DatabaseDialect::Oracle.to_string(), "Oracle");
+        assert_eq!(DatabaseDialect::MySQL.to_string(), "MySQL");
+        assert_eq!(DatabaseDialect::SQLite.to_string(), "SQLite");
+        assert_eq!(DatabaseDialect::SqlServer.to_string(), "SQL Server");
+    }
+
+    #[test]
+    fn test_sql_server_dialect() {
+        let dialect = DatabaseDialect::SqlServer;
+        assert_eq!(determine_parameter_format(&dialect, 2), "@p2");
+        assert_eq!(quote_identifier(&dialect, "odd]name"), "[odd]]name]");
+        assert_eq!(generate_top_clause(&dialect, Some(5), None), "TOP (5)");
+        assert_eq!(generate_limit_offset(&dialect, Some(5), None), "");
+        assert_eq!(
+            generate_limit_offset(&dialect, Some(5), Some(10)),
+            "OFFSET 10 ROWS FETCH NEXT 5 ROWS ONLY"
+        );
+        assert_eq!(
+            generate_aggregate_function(&dialect, SqlAggregateFunction::ToIso8601DateTime, "o.created"),
+            "CONVERT(VARCHAR(19), o.created, 126)"
+        );
+    }
+
+    #[test]
+    fn test_sqlite_dialect() {
+        let dialect = DatabaseDialect::SQLite;
+        assert_eq!(determine_parameter_format(&dialect, 3), "?3");
+        assert_eq!(
+            generate_comparison(&dialect, "name", SqlOperator::ILike, 1, 1).unwrap(),
+            "name LIKE ?1"
+        );
+        assert_eq!(generate_limit_offset(&dialect, None, Some(5)), "LIMIT -1 OFFSET 5");
+        assert_eq!(
+            generate_aggregate_function(&dialect, SqlAggregateFunction::ToIso8601Date, "t.created"),
+            "strftime('%Y-%m-%d', t.created)"
+        );
+    }
+
+    #[test]
+    fn test_mysql_parameter_and_quoting() {
+        let dialect = DatabaseDialect::MySQL;
+        assert_eq!(determine_parameter_format(&dialect, 1), "?");
+        assert_eq!(determine_parameter_format(&dialect, 7), "?");
+        assert_eq!(quote_identifier(&dialect, "order"), "`order`");
+        assert_eq!(quote_identifier(&dialect, "we`ird"), "`we``ird`");
+        assert_eq!(
+            quote_identifier(&DatabaseDialect::PostgreSQL, "order"),
+            "\"order\""
+        );
+    }
+
+    #[test]
+    fn test_time_point_display_before_epoch() {
+        let before = SystemTime::UNIX_EPOCH - std::time::Duration::from_millis(1_500);
+        assert_eq!(DefaultMySqlParamType::TimePoint(before).to_string(), "-2");
+        assert_eq!(DefaultPostgresParamType::TimePoint(before).to_string(), "-2");
+    }
+
+    #[test]
+    fn test_generate_comparison() {
+        let pg = DatabaseDialect::PostgreSQL;
+        let my = DatabaseDialect::MySQL;
+        assert_eq!(
+            generate_comparison(&pg, "a.id", SqlOperator::Between, 3, 2).unwrap(),
+            "a.id BETWEEN $3 AND $4"
+        );
+        assert_eq!(
+            generate_comparison(&pg, "a.id", SqlOperator::In, 1, 3).unwrap(),
+            "a.id IN ($1, $2, $3)"
+        );
+        assert_eq!(
+            generate_comparison(&pg, "a.id", SqlOperator::In, 1, 0).unwrap(),
+            "1 = 0"
+        );
+        assert_eq!(
+            generate_comparison(&my, "a.name", SqlOperator::Regexp, 1, 1).unwrap(),
+            "a.name REGEXP ?"
+        );
+        assert_eq!(
+            generate_comparison(&DatabaseDialect::Oracle, "a.name", SqlOperator::Regexp, 2, 1)
+                .unwrap(),
+            "REGEXP_LIKE(a.name, :2)"
+        );
+        assert_eq!(
+            generate_comparison(&pg, "a.id", SqlOperator::Between, 1, 1).unwrap_err(),
+            RenderError::OperandCount {
+                op: SqlOperator::Between,
+                expected: 2,
+                found: 1
+            }
+        );
+        assert_eq!(
+            generate_comparison(&pg, "a.id", SqlOperator::Equal, 1, 0)
+                .unwrap_err()
+                .to_string(),
+            "= takes 1 right operand, found 0"
+        );
+    }
+
+    #[test]
+    fn test_generate_limit_offset() {
+        let my = DatabaseDialect::MySQL;
+        assert_eq!(generate_limit_offset(&my, Some(10), Some(20)), "LIMIT 20, 10");
+        assert_eq!(generate_limit_offset(&my, Some(10), None), "LIMIT 10");
+        assert_eq!(
+            generate_limit_offset(&DatabaseDialect::PostgreSQL, Some(10), Some(20)),
+            "LIMIT 10 OFFSET 20"
+        );
+        assert_eq!(
+            generate_limit_offset(&DatabaseDialect::Oracle, Some(10), Some(20)),
+            "OFFSET 20 ROWS FETCH NEXT 10 ROWS ONLY"
+        );
     }
 }
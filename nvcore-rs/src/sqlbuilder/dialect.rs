@@ -0,0 +1,842 @@
+#![allow(dead_code)]
+
+use crate::sqlbuilder::{
+    sqlite_time_point_to_string, RenderError, SqlAggregateFunction, SqlOperator,
+};
+use std::time::SystemTime;
+
+// Right operands a comparison takes, BETWEEN two and IN any number (None)
+pub fn right_operand_count(op: SqlOperator) -> Option<usize> {
+    match op {
+        SqlOperator::Between => Some(2),
+        SqlOperator::In => None,
+        _ => Some(1),
+    }
+}
+
+pub fn check_operand_count(op: SqlOperator, found: usize) -> Result<(), RenderError> {
+    match right_operand_count(op) {
+        Some(expected) if expected != found => Err(RenderError::OperandCount {
+            op,
+            expected,
+            found,
+        }),
+        _ => Ok(()),
+    }
+}
+
+// How a dialect hands back the rows touched by INSERT
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ReturningStyle {
+    Unsupported,
+    Returning, // INSERT ... VALUES (...) RETURNING col
+    Output,    // INSERT ... OUTPUT INSERTED.col VALUES (...)
+}
+
+// Everything the builders need to know about a database. Implement it for a
+// unit struct and pass it as DatabaseDialect::Custom(&MY_DIALECT) to support a
+// database that is not built in, the default methods follow ANSI SQL.
+pub trait SqlDialect: Send + Sync {
+    fn name(&self) -> &str;
+
+    // Placeholder for the bound value at `index`, starting from 1
+    fn parameter_placeholder(&self, index: u32) -> String;
+
+    // Placeholder of a named parameter on dialects that bind by name, None
+    // binds the name positionally
+    fn named_parameter_placeholder(&self, _name: &str) -> Option<String> {
+        None
+    }
+
+    // Whether a positional placeholder may appear several times to reuse one
+    // bound value, like $1 on PostgreSQL
+    fn reuses_parameter_placeholders(&self) -> bool {
+        false
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("\"{}\"", identifier.replace('"', "\"\""))
+    }
+
+    // Row limiting clause appended at the end of SELECT
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => {
+                format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit)
+            }
+            (Some(limit), None) => format!("FETCH FIRST {} ROWS ONLY", limit),
+            (None, Some(offset)) => format!("OFFSET {} ROWS", offset),
+            (None, None) => String::new(),
+        }
+    }
+
+    // Row limiting clause placed right after SELECT
+    fn top_clause(&self, _limit: Option<u64>, _offset: Option<u64>) -> String {
+        String::new()
+    }
+
+    fn paging_requires_order_by(&self, _offset: Option<u64>) -> bool {
+        false
+    }
+
+    fn function_name(&self, fn_type: SqlAggregateFunction) -> &'static str {
+        match fn_type {
+            SqlAggregateFunction::None => "",
+            SqlAggregateFunction::Distinct => "DISTINCT",
+            SqlAggregateFunction::Count | SqlAggregateFunction::CountDistinct => "COUNT",
+            SqlAggregateFunction::Avg => "AVG",
+            SqlAggregateFunction::Sum => "SUM",
+            SqlAggregateFunction::Min => "MIN",
+            SqlAggregateFunction::Max => "MAX",
+            SqlAggregateFunction::StringAgg => "STRING_AGG",
+            SqlAggregateFunction::ArrayAgg => "ARRAY_AGG",
+            SqlAggregateFunction::ToUpper => "UPPER",
+            SqlAggregateFunction::ToLower => "LOWER",
+            SqlAggregateFunction::ToIso8601DateTime
+            | SqlAggregateFunction::ToIso8601Date
+            | SqlAggregateFunction::ToIso8601Time => "TO_CHAR",
+        }
+    }
+
+    fn aggregate_function(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        default_aggregate_function(self, fn_type, expression)
+    }
+
+    // ToIso8601DateTime, ToIso8601Date and ToIso8601Time as text
+    fn iso8601_format(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        let mask = match fn_type {
+            SqlAggregateFunction::ToIso8601Date => "YYYY-MM-DD",
+            SqlAggregateFunction::ToIso8601Time => "HH24:MI:SS",
+            _ => "YYYY-MM-DD\"T\"HH24:MI:SS",
+        };
+        format!(
+            "{}({}, '{}')",
+            self.function_name(fn_type),
+            expression,
+            mask
+        )
+    }
+
+    // Concatenation of the group values with a separator
+    fn string_aggregate(&self, expression: &str, separator: &str) -> String {
+        format!(
+            "{}({}, {})",
+            self.function_name(SqlAggregateFunction::StringAgg),
+            expression,
+            self.string_literal(separator)
+        )
+    }
+
+    fn supports_aggregate_filter(&self) -> bool {
+        false
+    }
+
+    fn string_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\'', "''"))
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value {
+            "TRUE"
+        } else {
+            "FALSE"
+        }
+    }
+
+    // Time point literal in UTC, only used to inline values in debug SQL
+    fn time_point_literal(&self, value: &SystemTime) -> String {
+        format!("TIMESTAMP '{}'", utc_time_point_text(value))
+    }
+
+    // Binary literal, only used to inline values in debug SQL
+    fn blob_literal(&self, value: &[u8]) -> String {
+        format!("X'{}'", hex_text(value))
+    }
+
+    // Case-insensitive LIKE, plain LIKE suits databases with case-insensitive
+    // default collations
+    fn case_insensitive_like(&self, field: &str, pattern: &str) -> String {
+        format!("{} LIKE {}", field, pattern)
+    }
+
+    fn regexp_match(&self, field: &str, pattern: &str) -> String {
+        format!("REGEXP_LIKE({}, {})", field, pattern)
+    }
+
+    // Comparison expression, `first_parameter_index` is the index of the first
+    // bound value and `value_size` the number of bound values
+    fn comparison(
+        &self,
+        field: &str,
+        op: SqlOperator,
+        first_parameter_index: u32,
+        value_size: u32,
+    ) -> Result<String, RenderError> {
+        let params = (0..value_size)
+            .map(|i| self.parameter_placeholder(first_parameter_index + i))
+            .collect::<Vec<_>>();
+        self.compare(field, op, &params)
+    }
+
+    // Comparison between already rendered operands, see right_operand_count.
+    // `IN ()` is invalid SQL, an empty list matches no row
+    fn compare(&self, left: &str, op: SqlOperator, right: &[String]) -> Result<String, RenderError> {
+        check_operand_count(op, right.len())?;
+        Ok(match op {
+            SqlOperator::Between => format!("{} BETWEEN {} AND {}", left, right[0], right[1]),
+            SqlOperator::In if right.is_empty() => "1 = 0".to_string(),
+            SqlOperator::In => format!("{} IN ({})", left, right.join(", ")),
+            SqlOperator::Regexp => self.regexp_match(left, &right[0]),
+            SqlOperator::ILike => self.case_insensitive_like(left, &right[0]),
+            _ => format!("{} {} {}", left, op, right[0]),
+        })
+    }
+
+    // Upsert clause appended after INSERT ... VALUES (...), None when the
+    // dialect upserts with a MERGE statement instead
+    fn upsert_clause(
+        &self,
+        _conflict_keys: &[String],
+        _update_columns: &[String],
+    ) -> Option<String> {
+        None
+    }
+
+    // MERGE INTO ... USING ... head for dialects without an upsert clause
+    fn merge_source(&self, table: &str, columns: &[String], placeholders: &[String]) -> String {
+        format!(
+            "MERGE INTO {} AS target USING (VALUES ({})) AS source ({})",
+            table,
+            placeholders.join(", "),
+            columns.join(", ")
+        )
+    }
+
+    fn merge_terminator(&self) -> &'static str {
+        ""
+    }
+
+    fn returning_style(&self) -> ReturningStyle {
+        ReturningStyle::Unsupported
+    }
+
+    fn supports_ilike(&self) -> bool {
+        false
+    }
+
+    // Whether `table AS alias` is accepted, Oracle only takes `table alias`
+    fn supports_table_alias_as(&self) -> bool {
+        true
+    }
+}
+
+// UTC text of a time point, fractional seconds are only printed when present
+fn utc_time_point_text(value: &SystemTime) -> String {
+    let datetime: chrono::DateTime<chrono::Utc> = (*value).into();
+    datetime.format("%Y-%m-%d %H:%M:%S%.f").to_string()
+}
+
+fn hex_text(value: &[u8]) -> String {
+    value.iter().map(|b| format!("{:02X}", b)).collect()
+}
+
+fn on_conflict_clause(conflict_keys: &[String], update_columns: &[String]) -> String {
+    let action = if update_columns.is_empty() {
+        "DO NOTHING".to_string()
+    } else {
+        format!(
+            "DO UPDATE SET {}",
+            update_columns
+                .iter()
+                .map(|c| format!("{} = EXCLUDED.{}", c, c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+    format!("ON CONFLICT ({}) {}", conflict_keys.join(", "), action)
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PostgreSqlDialect;
+
+impl SqlDialect for PostgreSqlDialect {
+    fn name(&self) -> &str {
+        "PostgreSQL"
+    }
+
+    fn parameter_placeholder(&self, index: u32) -> String {
+        format!("${}", index)
+    }
+
+    fn reuses_parameter_placeholders(&self) -> bool {
+        true
+    }
+
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => format!("LIMIT {} OFFSET {}", limit, offset),
+            (Some(limit), None) => format!("LIMIT {}", limit),
+            (None, Some(offset)) => format!("OFFSET {}", offset),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn iso8601_format(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        let mask = match fn_type {
+            SqlAggregateFunction::ToIso8601Date => "YYYY-MM-DD",
+            SqlAggregateFunction::ToIso8601Time => "HH24:MI:SS",
+            _ => "YYYY-MM-DD\"T\"HH24:MI:SS",
+        };
+        format!("to_char({}, '{}')", expression, mask)
+    }
+
+    fn supports_aggregate_filter(&self) -> bool {
+        true
+    }
+
+    fn blob_literal(&self, value: &[u8]) -> String {
+        format!("'\\x{}'::bytea", hex_text(value))
+    }
+
+    fn case_insensitive_like(&self, field: &str, pattern: &str) -> String {
+        format!("{} ILIKE {}", field, pattern)
+    }
+
+    fn regexp_match(&self, field: &str, pattern: &str) -> String {
+        format!("{} ~ {}", field, pattern)
+    }
+
+    fn upsert_clause(&self, conflict_keys: &[String], update_columns: &[String]) -> Option<String> {
+        Some(on_conflict_clause(conflict_keys, update_columns))
+    }
+
+    fn returning_style(&self) -> ReturningStyle {
+        ReturningStyle::Returning
+    }
+
+    fn supports_ilike(&self) -> bool {
+        true
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct OracleDialect;
+
+impl SqlDialect for OracleDialect {
+    fn name(&self) -> &str {
+        "Oracle"
+    }
+
+    fn parameter_placeholder(&self, index: u32) -> String {
+        format!(":{}", index)
+    }
+
+    fn named_parameter_placeholder(&self, name: &str) -> Option<String> {
+        Some(format!(":{}", name))
+    }
+
+    // BOOLEAN is only available in SQL since 23ai
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value {
+            "1"
+        } else {
+            "0"
+        }
+    }
+
+    fn function_name(&self, fn_type: SqlAggregateFunction) -> &'static str {
+        match fn_type {
+            SqlAggregateFunction::StringAgg => "LISTAGG",
+            SqlAggregateFunction::ArrayAgg => "JSON_ARRAYAGG",
+            _ => PostgreSqlDialect.function_name(fn_type),
+        }
+    }
+
+    fn string_aggregate(&self, expression: &str, separator: &str) -> String {
+        format!(
+            "LISTAGG({}, {}) WITHIN GROUP (ORDER BY {})",
+            expression,
+            self.string_literal(separator),
+            expression
+        )
+    }
+
+    // DATE keeps whole seconds, TIMESTAMP is needed for fractional seconds
+    fn time_point_literal(&self, value: &SystemTime) -> String {
+        let text = utc_time_point_text(value);
+        if text.contains('.') {
+            format!("TO_TIMESTAMP('{}', 'YYYY-MM-DD HH24:MI:SS.FF')", text)
+        } else {
+            format!("TO_DATE('{}', 'YYYY-MM-DD HH24:MI:SS')", text)
+        }
+    }
+
+    fn blob_literal(&self, value: &[u8]) -> String {
+        format!("HEXTORAW('{}')", hex_text(value))
+    }
+
+    fn case_insensitive_like(&self, field: &str, pattern: &str) -> String {
+        format!("UPPER({}) LIKE UPPER({})", field, pattern)
+    }
+
+    fn merge_source(&self, table: &str, columns: &[String], placeholders: &[String]) -> String {
+        format!(
+            "MERGE INTO {} target USING (SELECT {} FROM dual) source",
+            table,
+            placeholders
+                .iter()
+                .zip(columns)
+                .map(|(p, c)| format!("{} AS {}", p, c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    }
+
+    fn supports_table_alias_as(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MySqlDialect;
+
+impl SqlDialect for MySqlDialect {
+    fn name(&self) -> &str {
+        "MySQL"
+    }
+
+    fn parameter_placeholder(&self, _index: u32) -> String {
+        "?".to_string()
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("`{}`", identifier.replace('`', "``"))
+    }
+
+    // MySQL has no OFFSET without LIMIT, the documented workaround is the
+    // largest BIGINT UNSIGNED as row count
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => format!("LIMIT {}, {}", offset, limit),
+            (Some(limit), None) => format!("LIMIT {}", limit),
+            (None, Some(offset)) => format!("LIMIT {}, {}", offset, u64::MAX),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn function_name(&self, fn_type: SqlAggregateFunction) -> &'static str {
+        match fn_type {
+            SqlAggregateFunction::StringAgg => "GROUP_CONCAT",
+            SqlAggregateFunction::ArrayAgg => "JSON_ARRAYAGG",
+            SqlAggregateFunction::ToIso8601DateTime
+            | SqlAggregateFunction::ToIso8601Date
+            | SqlAggregateFunction::ToIso8601Time => "DATE_FORMAT",
+            _ => PostgreSqlDialect.function_name(fn_type),
+        }
+    }
+
+    fn iso8601_format(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        let format = match fn_type {
+            SqlAggregateFunction::ToIso8601Date => "%Y-%m-%d",
+            SqlAggregateFunction::ToIso8601Time => "%H:%i:%s",
+            _ => "%Y-%m-%dT%H:%i:%s",
+        };
+        format!("DATE_FORMAT({}, '{}')", expression, format)
+    }
+
+    fn string_aggregate(&self, expression: &str, separator: &str) -> String {
+        format!(
+            "GROUP_CONCAT({} SEPARATOR {})",
+            expression,
+            self.string_literal(separator)
+        )
+    }
+
+    // Backslash is an escape character unless NO_BACKSLASH_ESCAPES is set
+    fn string_literal(&self, value: &str) -> String {
+        format!("'{}'", value.replace('\\', "\\\\").replace('\'', "''"))
+    }
+
+    fn regexp_match(&self, field: &str, pattern: &str) -> String {
+        format!("{} REGEXP {}", field, pattern)
+    }
+
+    // MySQL resolves the conflict from the table unique keys, VALUES(col) is
+    // used instead of the 8.0.19 row alias so MariaDB accepts it as well
+    fn upsert_clause(&self, conflict_keys: &[String], update_columns: &[String]) -> Option<String> {
+        let columns = if update_columns.is_empty() {
+            conflict_keys
+        } else {
+            update_columns
+        };
+        Some(format!(
+            "ON DUPLICATE KEY UPDATE {}",
+            columns
+                .iter()
+                .map(|c| format!("{} = VALUES({})", c, c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ))
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqliteDialect;
+
+impl SqlDialect for SqliteDialect {
+    fn name(&self) -> &str {
+        "SQLite"
+    }
+
+    fn parameter_placeholder(&self, index: u32) -> String {
+        format!("?{}", index)
+    }
+
+    fn reuses_parameter_placeholders(&self) -> bool {
+        true
+    }
+
+    // SQLite requires LIMIT before OFFSET, a negative limit means no limit
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => format!("LIMIT {} OFFSET {}", limit, offset),
+            (Some(limit), None) => format!("LIMIT {}", limit),
+            (None, Some(offset)) => format!("LIMIT -1 OFFSET {}", offset),
+            (None, None) => String::new(),
+        }
+    }
+
+    fn function_name(&self, fn_type: SqlAggregateFunction) -> &'static str {
+        match fn_type {
+            SqlAggregateFunction::StringAgg => "GROUP_CONCAT",
+            SqlAggregateFunction::ArrayAgg => "json_group_array",
+            _ => PostgreSqlDialect.function_name(fn_type),
+        }
+    }
+
+    fn iso8601_format(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        let format = match fn_type {
+            SqlAggregateFunction::ToIso8601Date => "%Y-%m-%d",
+            SqlAggregateFunction::ToIso8601Time => "%H:%M:%S",
+            _ => "%Y-%m-%dT%H:%M:%S",
+        };
+        format!("strftime('{}', {})", format, expression)
+    }
+
+    // FILTER is available since SQLite 3.30
+    fn supports_aggregate_filter(&self) -> bool {
+        true
+    }
+
+    // Older SQLite releases have no TRUE / FALSE keywords
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value {
+            "1"
+        } else {
+            "0"
+        }
+    }
+
+    // Time points are stored as ISO-8601 text
+    fn time_point_literal(&self, value: &SystemTime) -> String {
+        self.string_literal(&sqlite_time_point_to_string(value))
+    }
+
+    // Needs a user supplied regexp() function, e.g. the REGEXP extension
+    fn regexp_match(&self, field: &str, pattern: &str) -> String {
+        format!("{} REGEXP {}", field, pattern)
+    }
+
+    fn upsert_clause(&self, conflict_keys: &[String], update_columns: &[String]) -> Option<String> {
+        Some(on_conflict_clause(conflict_keys, update_columns))
+    }
+
+    fn returning_style(&self) -> ReturningStyle {
+        ReturningStyle::Returning
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SqlServerDialect;
+
+impl SqlDialect for SqlServerDialect {
+    fn name(&self) -> &str {
+        "SQL Server"
+    }
+
+    fn parameter_placeholder(&self, index: u32) -> String {
+        format!("@p{}", index)
+    }
+
+    fn named_parameter_placeholder(&self, name: &str) -> Option<String> {
+        Some(format!("@{}", name))
+    }
+
+    fn quote_identifier(&self, identifier: &str) -> String {
+        format!("[{}]", identifier.replace(']', "]]"))
+    }
+
+    // A limit without offset is rendered as TOP (n), see top_clause
+    fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), Some(offset)) => {
+                format!("OFFSET {} ROWS FETCH NEXT {} ROWS ONLY", offset, limit)
+            }
+            (None, Some(offset)) => format!("OFFSET {} ROWS", offset),
+            (_, None) => String::new(),
+        }
+    }
+
+    fn top_clause(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+        match (limit, offset) {
+            (Some(limit), None) => format!("TOP ({})", limit),
+            _ => String::new(),
+        }
+    }
+
+    fn paging_requires_order_by(&self, offset: Option<u64>) -> bool {
+        offset.is_some()
+    }
+
+    // CONVERT styles: 126 is yyyy-mm-ddThh:mi:ss.mmm, 23 is yyyy-mm-dd and
+    // 108 is hh:mi:ss, the VARCHAR length truncates the milliseconds
+    fn iso8601_format(&self, fn_type: SqlAggregateFunction, expression: &str) -> String {
+        let (length, style) = match fn_type {
+            SqlAggregateFunction::ToIso8601Date => (10, 23),
+            SqlAggregateFunction::ToIso8601Time => (8, 108),
+            _ => (19, 126),
+        };
+        format!("CONVERT(VARCHAR({}), {}, {})", length, expression, style)
+    }
+
+    // JSON_ARRAYAGG is available since SQL Server 2025
+    fn function_name(&self, fn_type: SqlAggregateFunction) -> &'static str {
+        match fn_type {
+            SqlAggregateFunction::ArrayAgg => "JSON_ARRAYAGG",
+            _ => PostgreSqlDialect.function_name(fn_type),
+        }
+    }
+
+    fn boolean_literal(&self, value: bool) -> &'static str {
+        if value {
+            "1"
+        } else {
+            "0"
+        }
+    }
+
+    fn time_point_literal(&self, value: &SystemTime) -> String {
+        format!("CAST('{}' AS DATETIME2)", utc_time_point_text(value))
+    }
+
+    fn blob_literal(&self, value: &[u8]) -> String {
+        format!("0x{}", hex_text(value))
+    }
+
+    fn merge_source(&self, table: &str, columns: &[String], placeholders: &[String]) -> String {
+        format!(
+            "MERGE INTO {} WITH (HOLDLOCK) AS target USING (VALUES ({})) AS source ({})",
+            table,
+            placeholders.join(", "),
+            columns.join(", ")
+        )
+    }
+
+    // MERGE must be terminated by a semicolon on SQL Server
+    fn merge_terminator(&self) -> &'static str {
+        ";"
+    }
+
+    fn returning_style(&self) -> ReturningStyle {
+        ReturningStyle::Output
+    }
+}
+
+// Default SqlDialect::aggregate_function, for overrides that only change a
+// few functions
+pub fn default_aggregate_function<D: SqlDialect + ?Sized>(
+    dialect: &D,
+    fn_type: SqlAggregateFunction,
+    expression: &str,
+) -> String {
+    match fn_type {
+        SqlAggregateFunction::None => expression.to_string(),
+        SqlAggregateFunction::Distinct => format!("DISTINCT {}", expression),
+        SqlAggregateFunction::CountDistinct => format!(
+            "{}(DISTINCT {})",
+            dialect.function_name(fn_type),
+            expression
+        ),
+        SqlAggregateFunction::StringAgg => dialect.string_aggregate(expression, ","),
+        SqlAggregateFunction::ToIso8601DateTime
+        | SqlAggregateFunction::ToIso8601Date
+        | SqlAggregateFunction::ToIso8601Time => dialect.iso8601_format(fn_type, expression),
+        _ => format!("{}({})", dialect.function_name(fn_type), expression),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, NvSelect, SqlAggregateFunction,
+    };
+
+    struct CockroachDialect;
+
+    impl SqlDialect for CockroachDialect {
+        fn name(&self) -> &str {
+            "CockroachDB"
+        }
+
+        fn parameter_placeholder(&self, index: u32) -> String {
+            PostgreSqlDialect.parameter_placeholder(index)
+        }
+
+        fn limit_offset(&self, limit: Option<u64>, offset: Option<u64>) -> String {
+            PostgreSqlDialect.limit_offset(limit, offset)
+        }
+    }
+
+    static COCKROACH: CockroachDialect = CockroachDialect;
+
+    #[test]
+    fn test_custom_dialect() {
+        let dialect = DatabaseDialect::Custom(&COCKROACH);
+        assert_eq!(dialect.to_string(), "CockroachDB");
+        assert_eq!(dialect, DatabaseDialect::Custom(&COCKROACH));
+        assert_ne!(dialect, DatabaseDialect::PostgreSQL);
+
+        let select = NvSelect::<DefaultPostgresParamType>::new(dialect)
+            .f(
+                "name".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::ToUpper,
+                true,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block()
+            .limit(1);
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT UPPER(\"name\") FROM users AS u LIMIT 1"
+        );
+    }
+    #[test]
+    fn test_oracle_table_alias_and_boolean() {
+        let select = NvSelect::<DefaultPostgresParamType>::new(DatabaseDialect::Oracle)
+            .f(
+                "id".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block();
+
+        assert_eq!(select.generate_query(false), "SELECT u.id FROM users u");
+        assert_eq!(OracleDialect.boolean_literal(true), "1");
+        assert_eq!(PostgreSqlDialect.boolean_literal(false), "FALSE");
+    }
+    #[test]
+    fn test_function_mapping() {
+        use crate::sqlbuilder::{generate_aggregate_call, generate_aggregate_function};
+        use SqlAggregateFunction::{
+            ArrayAgg, CountDistinct, Max, Min, StringAgg, ToIso8601Date, ToIso8601DateTime,
+            ToIso8601Time, ToLower, ToUpper,
+        };
+
+        let pg = DatabaseDialect::PostgreSQL;
+        let ora = DatabaseDialect::Oracle;
+        let my = DatabaseDialect::MySQL;
+
+        assert_eq!(
+            generate_aggregate_function(&pg, ToUpper, "u.name"),
+            "UPPER(u.name)"
+        );
+        assert_eq!(
+            generate_aggregate_function(&ora, ToLower, "u.name"),
+            "LOWER(u.name)"
+        );
+        assert_eq!(
+            generate_aggregate_function(&pg, ToIso8601DateTime, "o.created"),
+            "to_char(o.created, 'YYYY-MM-DD\"T\"HH24:MI:SS')"
+        );
+        assert_eq!(
+            generate_aggregate_function(&ora, ToIso8601Date, "o.created"),
+            "TO_CHAR(o.created, 'YYYY-MM-DD')"
+        );
+        assert_eq!(
+            generate_aggregate_function(&my, ToIso8601Time, "o.created"),
+            "DATE_FORMAT(o.created, '%H:%i:%s')"
+        );
+        assert_eq!(
+            generate_aggregate_function(&pg, Min, "o.total"),
+            "MIN(o.total)"
+        );
+        assert_eq!(
+            generate_aggregate_function(&pg, Max, "o.total"),
+            "MAX(o.total)"
+        );
+        assert_eq!(
+            generate_aggregate_function(&pg, CountDistinct, "o.user_id"),
+            "COUNT(DISTINCT o.user_id)"
+        );
+        assert_eq!(
+            generate_aggregate_function(&pg, ArrayAgg, "o.id"),
+            "ARRAY_AGG(o.id)"
+        );
+        assert_eq!(
+            generate_aggregate_call(&pg, StringAgg, "u.name", Some("; "), None),
+            "STRING_AGG(u.name, '; ')"
+        );
+        assert_eq!(
+            generate_aggregate_call(&ora, StringAgg, "u.name", Some(","), None),
+            "LISTAGG(u.name, ',') WITHIN GROUP (ORDER BY u.name)"
+        );
+        assert_eq!(
+            generate_aggregate_call(&my, StringAgg, "u.name", Some("'"), None),
+            "GROUP_CONCAT(u.name SEPARATOR '''')"
+        );
+    }
+
+    #[test]
+    fn test_aggregate_filter() {
+        use crate::sqlbuilder::AggregateOptions;
+
+        let pg = NvSelect::<DefaultPostgresParamType>::new(DatabaseDialect::PostgreSQL)
+            .aggregate(
+                SqlAggregateFunction::Count,
+                "*".to_string(),
+                None,
+                Some("paid".to_string()),
+                AggregateOptions::default().filter("o.status = 'PAID'"),
+            )
+            .from()
+            .add_table_with_alias("orders", &Some("o".to_string()))
+            .end_from_table_block();
+
+        assert_eq!(
+            pg.generate_query(false),
+            "SELECT COUNT(*) FILTER (WHERE o.status = 'PAID') AS paid FROM orders AS o"
+        );
+
+        let oracle = NvSelect::<DefaultPostgresParamType>::new(DatabaseDialect::Oracle).aggregate(
+            SqlAggregateFunction::Sum,
+            "total".to_string(),
+            Some("o".to_string()),
+            Some("paid".to_string()),
+            AggregateOptions::default().filter("o.status = 'PAID'"),
+        );
+
+        assert_eq!(
+            oracle.generate_query(false),
+            "SELECT SUM(CASE WHEN o.status = 'PAID' THEN o.total END) AS paid"
+        );
+    }
+}
@@ -0,0 +1,617 @@
+// Layout of the generated SQL
+//
+// `generate_query(true)` formats with FormatOptions::default() and
+// `generate_query(false)` with FormatOptions::compact(). In pretty mode a
+// clause stays on the line of its keyword when it fits in `line_width`,
+// otherwise its items go one per line, indented below the keyword. Keyword
+// case is applied as the builder and the dialect write their keywords, names
+// and raw SQL fragments are never recased, so a column named `date` stays as
+// it is.
+
+use crate::sqlbuilder::generate_indentation;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordCase {
+    // Keywords as the builder writes them, upper case
+    Preserve,
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommaPlacement {
+    Trailing,
+    Leading,
+}
+
+// How the items of a clause are separated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ItemLayout {
+    // Comma separated list
+    List,
+    // Conditions carrying their own AND / OR prefix
+    Conditions,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FormatOptions {
+    pub(crate) pretty: bool,
+    pub(crate) indent_width: u32,
+    pub(crate) indent_char: char,
+    pub(crate) keyword_case: KeywordCase,
+    pub(crate) line_width: usize,
+    pub(crate) comma_placement: CommaPlacement,
+    pub(crate) condition_per_line: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            pretty: true,
+            indent_width: 2,
+            indent_char: ' ',
+            keyword_case: KeywordCase::Preserve,
+            line_width: 80,
+            comma_placement: CommaPlacement::Trailing,
+            condition_per_line: true,
+        }
+    }
+}
+
+impl FormatOptions {
+    // Everything on one line
+    pub fn compact() -> Self {
+        Self {
+            pretty: false,
+            ..Self::default()
+        }
+    }
+
+    pub fn from_pretty_print(pretty_print: bool) -> Self {
+        if pretty_print {
+            Self::default()
+        } else {
+            Self::compact()
+        }
+    }
+
+    pub fn indent(mut self, width: u32, indent_char: char) -> Self {
+        self.indent_width = width;
+        self.indent_char = indent_char;
+        self
+    }
+
+    pub fn keyword_case(mut self, keyword_case: KeywordCase) -> Self {
+        self.keyword_case = keyword_case;
+        self
+    }
+
+    // 0 puts every item on its own line
+    pub fn line_width(mut self, line_width: usize) -> Self {
+        self.line_width = line_width;
+        self
+    }
+
+    pub fn comma_placement(mut self, comma_placement: CommaPlacement) -> Self {
+        self.comma_placement = comma_placement;
+        self
+    }
+
+    // Breaks a WHERE with more than one condition even when it would fit
+    pub fn condition_per_line(mut self, condition_per_line: bool) -> Self {
+        self.condition_per_line = condition_per_line;
+        self
+    }
+
+    pub fn is_pretty(&self) -> bool {
+        self.pretty
+    }
+
+    pub(crate) fn indentation(&self, level: u32) -> String {
+        if self.pretty {
+            generate_indentation(level, self.indent_char, self.indent_width)
+        } else {
+            String::new()
+        }
+    }
+
+    pub(crate) fn clause_separator(&self) -> &'static str {
+        if self.pretty {
+            "\n"
+        } else {
+            " "
+        }
+    }
+
+    // `keyword` followed by its items, the keyword is indented at `level`
+    pub(crate) fn clause(
+        &self,
+        level: u32,
+        keyword: &str,
+        items: &[String],
+        layout: ItemLayout,
+    ) -> String {
+        let keyword = self.keyword(keyword);
+        let inline = format!(
+            "{}{} {}",
+            self.indentation(level),
+            keyword,
+            join_inline(items, layout)
+        );
+        let forced_break =
+            layout == ItemLayout::Conditions && self.condition_per_line && items.len() > 1;
+        if !self.pretty || (!forced_break && self.fits(&inline)) {
+            return inline;
+        }
+        format!(
+            "{}{}\n{}",
+            self.indentation(level),
+            keyword,
+            self.lines(level + 1, items, layout)
+        )
+    }
+
+    // `prefix(items)`, the list breaks inside the parentheses
+    pub(crate) fn parenthesized(&self, level: u32, prefix: &str, items: &[String]) -> String {
+        let inline = format!(
+            "{}{}({})",
+            self.indentation(level),
+            prefix,
+            join_inline(items, ItemLayout::List)
+        );
+        if !self.pretty || self.fits(&inline) {
+            return inline;
+        }
+        format!(
+            "{}{}(\n{}\n{})",
+            self.indentation(level),
+            prefix,
+            self.lines(level + 1, items, ItemLayout::List),
+            self.indentation(level)
+        )
+    }
+
+    // `sql` is a select rendered one level below `level`, the closing
+    // parenthesis lines up with the line holding the opening one
+    pub(crate) fn subquery(&self, level: u32, sql: &str) -> String {
+        if self.pretty {
+            format!("(\n{}\n{})", sql, self.indentation(level))
+        } else {
+            format!("({})", sql)
+        }
+    }
+
+    fn lines(&self, level: u32, items: &[String], layout: ItemLayout) -> String {
+        let indentation = self.indentation(level);
+        let mut query = String::new();
+        for (i, item) in items.iter().enumerate() {
+            if i > 0 {
+                if layout == ItemLayout::List && self.comma_placement == CommaPlacement::Trailing {
+                    query.push(',');
+                }
+                query.push('\n');
+            }
+            query.push_str(&indentation);
+            if i > 0
+                && layout == ItemLayout::List
+                && self.comma_placement == CommaPlacement::Leading
+            {
+                query.push_str(", ");
+            }
+            query.push_str(item);
+        }
+        query
+    }
+
+    fn fits(&self, line: &str) -> bool {
+        !line.contains('\n') && line.chars().count() <= self.line_width
+    }
+
+    // Text with operands, such as a dialect comparison. `fragment` is written
+    // with placeholders in place of the operands, which are put back once its
+    // keywords are cased
+    pub(crate) fn keywords_around(
+        &self,
+        operands: &[&str],
+        fragment: impl FnOnce(&[String]) -> String,
+    ) -> String {
+        if self.keyword_case == KeywordCase::Preserve {
+            return fragment(&operands.iter().map(|o| o.to_string()).collect::<Vec<_>>());
+        }
+        let slots = (0..operands.len())
+            .map(|i| format!("\u{0}{}\u{0}", i))
+            .collect::<Vec<_>>();
+        let mut query = self.keyword(&fragment(&slots));
+        for (slot, operand) in slots.iter().zip(operands) {
+            query = query.replace(slot.as_str(), operand);
+        }
+        query
+    }
+
+    // Keywords, or text the builder writes whole such as a LIMIT clause. Quoted
+    // text and words qualified with ':', '@', '$' or '.' are left as they are
+    pub(crate) fn keyword(&self, sql: &str) -> String {
+        let upper = match self.keyword_case {
+            KeywordCase::Preserve => return sql.to_string(),
+            KeywordCase::Upper => true,
+            KeywordCase::Lower => false,
+        };
+
+        let chars: Vec<char> = sql.chars().collect();
+        let mut query = String::with_capacity(sql.len());
+        let mut i = 0;
+        while i < chars.len() {
+            let c = chars[i];
+            let next = chars.get(i + 1).copied();
+            let end = match c {
+                '\'' | '"' | '`' => quoted_end(&chars, i, c),
+                '[' => quoted_end(&chars, i, ']'),
+                '/' if next == Some('*') => comment_end(&chars, i),
+                '-' if next == Some('-') => line_end(&chars, i),
+                c if c.is_alphabetic() || c == '_' => {
+                    let end = word_end(&chars, i);
+                    let word: String = chars[i..end].iter().collect();
+                    let qualified = (i > 0 && matches!(chars[i - 1], ':' | '@' | '$' | '.'))
+                        || chars.get(end) == Some(&'.');
+                    if !qualified && is_keyword(&word) {
+                        query.push_str(&if upper {
+                            word.to_uppercase()
+                        } else {
+                            word.to_lowercase()
+                        });
+                        i = end;
+                        continue;
+                    }
+                    end
+                }
+                _ => i + 1,
+            };
+            query.extend(&chars[i..end]);
+            i = end;
+        }
+        query
+    }
+}
+
+fn join_inline(items: &[String], layout: ItemLayout) -> String {
+    match layout {
+        ItemLayout::List => items.join(", "),
+        ItemLayout::Conditions => items.join(" "),
+    }
+}
+
+// A doubled closing quote is an escaped quote
+fn quoted_end(chars: &[char], start: usize, close: char) -> usize {
+    let mut i = start + 1;
+    while i < chars.len() {
+        if chars[i] == close {
+            if close != ']' && chars.get(i + 1) == Some(&close) {
+                i += 2;
+                continue;
+            }
+            return i + 1;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn comment_end(chars: &[char], start: usize) -> usize {
+    let mut i = start + 2;
+    while i + 1 < chars.len() {
+        if chars[i] == '*' && chars[i + 1] == '/' {
+            return i + 2;
+        }
+        i += 1;
+    }
+    chars.len()
+}
+
+fn line_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|c| *c == '\n')
+        .map_or(chars.len(), |p| start + p)
+}
+
+fn word_end(chars: &[char], start: usize) -> usize {
+    chars[start..]
+        .iter()
+        .position(|c| !(c.is_alphanumeric() || matches!(c, '_' | '$' | '#')))
+        .map_or(chars.len(), |p| start + p)
+}
+
+const KEYWORDS: &[&str] = &[
+    "ALL",
+    "AND",
+    "ARRAY_AGG",
+    "AS",
+    "ASC",
+    "AVG",
+    "BETWEEN",
+    "BY",
+    "CAST",
+    "CONFLICT",
+    "COUNT",
+    "CROSS",
+    "DATE",
+    "DESC",
+    "DUAL",
+    "DISTINCT",
+    "DO",
+    "DUPLICATE",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCLUDED",
+    "EXISTS",
+    "FALSE",
+    "FETCH",
+    "FILTER",
+    "FIRST",
+    "FROM",
+    "FULL",
+    "GROUP",
+    "GROUP_CONCAT",
+    "HAVING",
+    "HEXTORAW",
+    "ILIKE",
+    "IN",
+    "INNER",
+    "INSERT",
+    "INSERTED",
+    "INTO",
+    "IS",
+    "JOIN",
+    "KEY",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "LISTAGG",
+    "MATCHED",
+    "MAX",
+    "MERGE",
+    "MIN",
+    "NEXT",
+    "NOT",
+    "NOTHING",
+    "NULL",
+    "OFFSET",
+    "ON",
+    "ONLY",
+    "OR",
+    "ORDER",
+    "OUTER",
+    "OUTPUT",
+    "REGEXP_LIKE",
+    "RETURNING",
+    "RIGHT",
+    "ROW",
+    "ROWS",
+    "SELECT",
+    "SEPARATOR",
+    "SET",
+    "STRING_AGG",
+    "SUM",
+    "THEN",
+    "TIMESTAMP",
+    "TOP",
+    "TO_DATE",
+    "TO_TIMESTAMP",
+    "TRUE",
+    "UPDATE",
+    "USING",
+    "VALUES",
+    "WHEN",
+    "WHERE",
+    "WITHIN",
+];
+
+fn is_keyword(word: &str) -> bool {
+    KEYWORDS
+        .iter()
+        .any(|keyword| keyword.eq_ignore_ascii_case(word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, DefaultSqlServerParamType, NvInsert, NvSelect,
+        Select, SortType, SqlAggregateFunction, SqlOperator,
+    };
+    use std::sync::Arc;
+
+    type Param = DefaultPostgresParamType;
+
+    fn nested_legacy_select() -> Arc<NvSelect<Param>> {
+        NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "id".to_string(),
+                Some("o".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "total".to_string(),
+                Some("o".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .begin_subquery("o".to_string())
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "total".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("orders", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "status",
+                &SqlOperator::Equal,
+                Param::String("paid".to_string()),
+            )
+            .unwrap()
+            .end_where_block()
+            .end_subquery_inside_from()
+            .end_from_table_block()
+            .where_clause()
+            .add_condition("o.total", &SqlOperator::Greater, Param::Int(10))
+            .unwrap()
+            .and()
+            .add_subquery("o.user_id".to_string(), SqlOperator::In, String::new())
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition("active", &SqlOperator::Equal, Param::Bool(true))
+            .unwrap()
+            .end_where_block()
+            .end_subquery_inside_where_condition()
+            .end_where_block()
+    }
+
+    #[test]
+    fn test_pretty_print_nested_subqueries() {
+        let legacy = nested_legacy_select();
+        let expected = "\
+SELECT o.id, o.total
+FROM
+  (
+    SELECT id, total
+    FROM orders
+    WHERE status = $1
+  ) AS o
+WHERE
+  o.total > $2
+  AND o.user_id IN (
+    SELECT id
+    FROM users
+    WHERE active = $3
+  )";
+        assert_eq!(legacy.generate_query(true), expected);
+        assert_eq!(legacy.to_select().generate_query(true), expected);
+        assert_eq!(
+            legacy.generate_query(false),
+            "SELECT o.id, o.total FROM (SELECT id, total FROM orders WHERE status = $1) AS o \
+             WHERE o.total > $2 AND o.user_id IN (SELECT id FROM users WHERE active = $3)"
+        );
+    }
+
+    #[test]
+    fn test_format_options() {
+        let format = FormatOptions::default()
+            .indent(1, '\t')
+            .keyword_case(KeywordCase::Lower)
+            .comma_placement(CommaPlacement::Leading)
+            .line_width(0);
+        assert_eq!(
+            nested_legacy_select().generate_query_with(&format),
+            "\
+select
+\to.id
+\t, o.total
+from
+\t(
+\t\tselect
+\t\t\tid
+\t\t\t, total
+\t\tfrom
+\t\t\torders
+\t\twhere
+\t\t\tstatus = $1
+\t) as o
+where
+\to.total > $2
+\tand o.user_id in (
+\t\tselect
+\t\t\tid
+\t\tfrom
+\t\t\tusers
+\t\twhere
+\t\t\tactive = $3
+\t)"
+        );
+
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("u"), None, SqlAggregateFunction::None, false)
+            .from_table("users", Some("u"))
+            .where_clause(|w| {
+                w.add_condition("u.id", SqlOperator::Equal, Param::Int(1))
+                    .unwrap()
+                    .and()
+                    .add_condition("u.active", SqlOperator::Equal, Param::Bool(true))
+                    .unwrap()
+            });
+        assert_eq!(
+            select.generate_query_with(&FormatOptions::default().condition_per_line(false)),
+            "SELECT u.id\nFROM users AS u\nWHERE u.id = $1 AND u.active = $2"
+        );
+
+        let insert = NvInsert::new(DatabaseDialect::PostgreSQL)
+            .into_table("users")
+            .value("name", Param::String("ana".to_string()))
+            .value("email", Param::String("ana@example.com".to_string()))
+            .returning(&["id"])
+            .unwrap();
+        assert_eq!(
+            insert.generate_query_with(&FormatOptions::default().line_width(24)),
+            "INSERT INTO users (\n  name,\n  email\n)\nVALUES ($1, $2)\nRETURNING id"
+        );
+    }
+
+    #[test]
+    fn test_keyword_case_leaves_names_alone() {
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f("date", None, None, SqlAggregateFunction::None, false)
+            .f(
+                "row",
+                None,
+                Some("first"),
+                SqlAggregateFunction::Count,
+                false,
+            )
+            .from_table("events", Some("key"))
+            .where_clause(|w| {
+                w.add_condition("set", SqlOperator::Equal, Param::String("IN".to_string()))
+                    .unwrap()
+            })
+            .order_by("date", None, SortType::Descending);
+        let lower = FormatOptions::compact().keyword_case(KeywordCase::Lower);
+        assert_eq!(
+            select.generate_query_with(&lower),
+            "select date, count(row) as first from events as key where set = $1 order by date desc"
+        );
+
+        let insert = NvInsert::new(DatabaseDialect::SqlServer)
+            .into_table("DATE")
+            .value("KEY", DefaultSqlServerParamType::Int(1))
+            .returning(&["ROW"])
+            .unwrap();
+        assert_eq!(
+            insert.generate_query_with(&lower),
+            "insert into DATE (KEY) output inserted.ROW values (@p1)"
+        );
+    }
+}
@@ -1,8 +1,5 @@
-#![allow(dead_code)]
-
-use crate::sqlbuilder::{DatabaseDialect, NvSelect};
-use crate::utils::indent_space;
-use std::sync::{Arc, RwLock};
+use crate::sqlbuilder::{DatabaseDialect, FromClause, NvSelect, SubqueryParent, TableRef};
+use std::sync::Arc;
 
 #[derive(Debug, Clone)]
 pub struct FromTable {
@@ -10,6 +7,12 @@ pub struct FromTable {
     table_alias: Option<String>,
 }
 
+impl Default for FromTable {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl FromTable {
     pub fn new() -> Self {
         Self {
@@ -25,172 +28,99 @@ impl FromTable {
         }
     }
 
-    pub fn build_table_name(&self) -> String {
-        match &self.table_alias {
-            Some(alias) => format!("{} AS {}", self.table, alias),
-            None => self.table.clone(),
+    pub fn table(&self) -> &String {
+        &self.table
+    }
+
+    pub fn table_alias(&self) -> &Option<String> {
+        &self.table_alias
+    }
+
+    pub fn to_table_ref<T>(&self) -> TableRef<T> {
+        TableRef::Table {
+            name: self.table.clone(),
+            alias: self.table_alias.clone(),
+            quoted: false,
         }
     }
 }
 
+// FROM block of NvSelect, owning its select until end_from_table_block hands
+// it back with the tables appended to its FROM clause
+#[derive(Debug, Clone)]
 pub struct FromTableStatement<T> {
-    parent: RwLock<Option<Arc<NvSelect<T>>>>,
-    tables: RwLock<Vec<FromTable>>,
-    subqueries: RwLock<Vec<Arc<NvSelect<T>>>>,
-    parameter_values: Arc<RwLock<Vec<T>>>,
-    current_parameter_index: RwLock<u32>,
-    level: u32,
+    parent: Option<NvSelect<T>>,
+    from: FromClause<T>,
     dialect: DatabaseDialect,
 }
 
-impl<T> FromTableStatement<T> {
-    pub fn new(
-        parameter_values: Arc<RwLock<Vec<T>>>,
-        parent: Arc<NvSelect<T>>,
-        parameter_index: u32,
-        level: u32,
-        dialect: DatabaseDialect,
-    ) -> Arc<Self> {
+impl<T: Clone> FromTableStatement<T> {
+    pub fn new(dialect: DatabaseDialect) -> Arc<Self> {
+        Arc::new(Self {
+            parent: None,
+            from: FromClause::new(),
+            dialect,
+        })
+    }
+
+    pub(crate) fn with_parent(parent: NvSelect<T>) -> Arc<Self> {
         Arc::new(Self {
-            parent: Some( parent).into(),
-            tables: Vec::new().into(),
-            subqueries: Vec::new().into(),
-            parameter_values:  parameter_values,
-            level: level,
-            current_parameter_index: parameter_index.into(),
-            dialect: dialect,
+            dialect: parent.dialect(),
+            parent: Some(parent),
+            from: FromClause::new(),
         })
     }
 
+    fn map(self: Arc<Self>, build: impl FnOnce(FromClause<T>) -> FromClause<T>) -> Arc<Self> {
+        let mut statement = Arc::unwrap_or_clone(self);
+        statement.from = build(statement.from);
+        Arc::new(statement)
+    }
+
     pub fn add_table(self: Arc<Self>, table: FromTable) -> Arc<Self> {
-        let mut write_guard = self.tables.write().unwrap();
-        write_guard.push(table);
-        self.clone()
+        self.map(|f| f.add_table(table.to_table_ref()))
     }
 
     pub fn add_table_with_alias(
         self: Arc<Self>,
-        table_name: &String,
+        table_name: &str,
         table_alias: &Option<String>,
     ) -> Arc<Self> {
-        {
-        let mut write_guard = self.tables.write().unwrap();
-        write_guard.push(FromTable::with_alias(table_name.clone(), table_alias.clone()));
-        }
-        self.clone()
+        self.map(|f| f.add_table_with_alias(table_name, table_alias.as_deref()))
     }
 
-    pub fn get_current_parameter_index(&self) -> u32 {
-        *self.current_parameter_index.read().unwrap()
+    // Opens a subquery selected from under `table_alias`, the subquery owns
+    // this block until end_subquery_inside_from
+    pub fn begin_subquery(self: Arc<Self>, table_alias: String) -> Arc<NvSelect<T>> {
+        let dialect = self.dialect;
+        NvSelect::subquery(
+            dialect,
+            SubqueryParent::From {
+                statement: Arc::unwrap_or_clone(self),
+                table_alias,
+            },
+        )
     }
 
-    pub fn update_current_parameter_index(&mut self, param_index: u32) {
-        *self.current_parameter_index.write().unwrap() = param_index;
-    }
-
-    // pub fn get_current_parameter_index_from_parent(&self, select: &NvSelect<T>) -> u32 {
-    //     select.get_current_param_index()
-    // }
-
-    pub fn begin_subquery(self:Arc<Self>, table_alias: String) -> Arc<NvSelect<T>> {
-        let index = *self.current_parameter_index.read().unwrap();
-        let level = self.level.clone();
-        self.create_new_select_block(index, level + 1, table_alias)
+    pub(crate) fn attach_subquery(self, table_alias: &str, subquery: NvSelect<T>) -> Arc<Self> {
+        Arc::new(self).map(|f| f.add_subquery(subquery.into_inner(), table_alias))
     }
 
     pub fn is_empty(&self) -> bool {
-        self.tables.read().unwrap().is_empty()
-    }
-
-    pub fn end_from_table_block( self:Arc<Self>) -> Arc<NvSelect<T>> {
-        let parent_guard = self.parent.read().unwrap();
-        if parent_guard.is_none() {
-            panic!("EndFromTableBlock() null-reference to parent");
-        }
-
-        // Cloning the Arc inside the Option
-        Arc::clone(parent_guard.as_ref().unwrap())
-         
-    }
-
-    pub fn generate_query(&self, pretty_print: bool) -> String {
-        let mut query = String::new();
-        let mut first_element = true;
-
-        let table_guard = self.tables.read().unwrap();
-        let sq_guard = self.subqueries.read().unwrap();
-        
-        let is_subqueries_empty = sq_guard.is_empty();
-
-        let indent_level = self.level.clone() +1;
-        let indentation = indent_space(indent_level.clone()); 
-        for table in table_guard.iter() {
-            if !first_element {
-                query.push_str(if pretty_print { ",\n" } else { ", " });
-            }
-            query.push_str(if pretty_print {
-                &indentation
-            } else {
-                ""
-            });
-            query.push_str(&table.build_table_name());
-            first_element = false;
-        }
-
-        if !is_subqueries_empty {
-            for subquery in sq_guard.iter() {
-                let alias = self.get_table_alias_from_parent(subquery);
-                if !first_element {
-                    query.push_str(if pretty_print { ",\n" } else { ", " });
-                }
-                query.push_str(if pretty_print {
-                    &indentation
-                } else {
-                    ""
-                });
-                query.push_str(&format!(
-                    "(\n{}{})",
-                    self.generate_select_query(subquery, pretty_print),
-                    if alias.is_empty() {
-                        "".to_string()
-                    } else {
-                        format!(" AS {}", alias)
-                    }
-                ));
-            }
-        }
-        query
-    }
-
-    pub fn generate_select_block(&self, select: &NvSelect<T>) -> String {
-        select.generate_query(false)
-    }
-
-     fn create_new_select_block( self:Arc<Self>, index: u32, level: u32, table_alias: String)->Arc<NvSelect<T>> {
-        
-        let subquery = NvSelect::new_subquery_from(
-            self.parameter_values.clone(),
-            index,
-            level,
-            self.clone(),
-            table_alias,
-            self.dialect.clone(),
-        );
-
-        let subquery_to_return = subquery.clone();
-
-        let mut sq_guard = self.subqueries.write().unwrap();
-        sq_guard.push(subquery);
-
-        subquery_to_return
+        self.from.tables().is_empty()
     }
 
-    pub fn generate_select_query(&self, select: &NvSelect<T>, pretty_print: bool) -> String {
-        select.generate_query(pretty_print)
+    pub fn end_from_table_block(self: Arc<Self>) -> Arc<NvSelect<T>> {
+        let statement = Arc::unwrap_or_clone(self);
+        let parent = match statement.parent {
+            Some(parent) => parent,
+            None => panic!("EndFromTableBlock() null-reference to parent"),
+        };
+        let from = statement.from;
+        Arc::new(parent).map(|select| select.from(|_| from))
     }
 
-    pub fn get_table_alias_from_parent(&self, select: &NvSelect<T>) -> String {
-        select.table_alias().to_string()
+    pub fn to_from_clause(&self) -> FromClause<T> {
+        self.from.clone()
     }
-    
 }
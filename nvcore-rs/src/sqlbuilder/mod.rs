@@ -1,20 +1,48 @@
+mod ast;
 mod def;
+mod dialect;
 //  mod group_by;
 //  mod join_statement;
-//  mod order_by;
+ mod order_by;
  mod where_statement;
  mod from_statement;
  mod field;
  mod nv_select;
+ mod nv_insert;
+ mod select;
+ mod render;
+ mod format;
+ mod visitor;
+ mod parser;
+ mod prepared;
+ mod allowlist;
+ mod schema;
+ #[cfg(feature = "serde")]
+ mod saved_query;
 
+pub use ast::*;
 pub use def::*;
+pub use dialect::*;
 // pub use group_by::*;
 // pub use join_statement::*;
-// pub use order_by::*;
+pub use order_by::*;
 pub use where_statement::*;
 pub use  from_statement::*;
 pub use  field::*;
 pub use nv_select::*;
+pub use nv_insert::*;
+pub use select::*;
+pub use render::*;
+pub use format::*;
+pub use visitor::*;
+pub use parser::*;
+pub use prepared::*;
+pub use allowlist::*;
+pub use schema::*;
+#[cfg(feature = "derive")]
+pub use nvcore_derive::{nvsql, NvTable};
+#[cfg(feature = "serde")]
+pub use saved_query::*;
 
 
 // // pub struct NvSelect<T> {
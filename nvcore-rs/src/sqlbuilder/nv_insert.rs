@@ -0,0 +1,438 @@
+#![allow(dead_code)]
+
+use crate::sqlbuilder::{
+    determine_parameter_format, Allowlist, AllowlistError, DatabaseDialect, DebugSqlOptions, FormatOptions, ItemLayout,
+    ReturningStyle, ToSqlLiteral, DEBUG_SQL_HEADER, REDACTED_LITERAL,
+};
+use std::fmt;
+
+// An insert the dialect cannot express, returned by the builder call that
+// makes it so
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertError {
+    ReturningUnsupported { dialect: DatabaseDialect },
+    // The dialect upserts with MERGE, which has no RETURNING clause
+    ReturningWithMerge { dialect: DatabaseDialect },
+    // generate_upsert_clause on a dialect that upserts with MERGE
+    UpsertIsMerge { dialect: DatabaseDialect },
+    EmptyConflictKeys,
+}
+
+impl fmt::Display for InsertError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertError::ReturningUnsupported { dialect } => {
+                write!(f, "RETURNING is not supported for {}", dialect)
+            }
+            InsertError::ReturningWithMerge { dialect } => {
+                write!(f, "RETURNING is not supported by MERGE on {}", dialect)
+            }
+            InsertError::UpsertIsMerge { dialect } => {
+                write!(f, "{} upserts are generated as MERGE statements", dialect)
+            }
+            InsertError::EmptyConflictKeys => write!(f, "an upsert needs at least one conflict key"),
+        }
+    }
+}
+
+impl std::error::Error for InsertError {}
+
+#[derive(Debug, Clone)]
+pub struct NvInsert<T> {
+    table: String,
+    columns: Vec<String>,
+    parameter_values: Vec<T>,
+    conflict_keys: Vec<String>,
+    update_columns: Vec<String>,
+    upsert: bool,
+    returning: Vec<String>,
+    dialect: DatabaseDialect,
+}
+
+impl<T> NvInsert<T> {
+    pub fn new(dialect: DatabaseDialect) -> Self {
+        Self {
+            table: String::new(),
+            columns: Vec::new(),
+            parameter_values: Vec::new(),
+            conflict_keys: Vec::new(),
+            update_columns: Vec::new(),
+            upsert: false,
+            returning: Vec::new(),
+            dialect,
+        }
+    }
+
+    pub fn dialect(&self) -> DatabaseDialect {
+        self.dialect
+    }
+
+    pub fn into_table(mut self, table: &str) -> Self {
+        self.table = table.to_string();
+        self
+    }
+
+    pub fn value(mut self, column: &str, value: T) -> Self {
+        self.columns.push(column.to_string());
+        self.parameter_values.push(value);
+        self
+    }
+
+    // Insert or update on a unique key violation, `conflict_keys` is ignored by
+    // MySQL which resolves the conflict from the table unique keys unless
+    // `update_columns` is empty
+    pub fn on_conflict_update(
+        mut self,
+        conflict_keys: &[&str],
+        update_columns: &[&str],
+    ) -> Result<Self, InsertError> {
+        if conflict_keys.is_empty() {
+            return Err(InsertError::EmptyConflictKeys);
+        }
+        self.upsert = true;
+        self.conflict_keys = conflict_keys.iter().map(|c| c.to_string()).collect();
+        self.update_columns = update_columns.iter().map(|c| c.to_string()).collect();
+        self.check_returning()?;
+        Ok(self)
+    }
+
+    pub fn returning(mut self, columns: &[&str]) -> Result<Self, InsertError> {
+        self.returning = columns.iter().map(|c| c.to_string()).collect();
+        self.check_returning()?;
+        Ok(self)
+    }
+
+    fn check_returning(&self) -> Result<(), InsertError> {
+        if self.returning.is_empty() {
+            return Ok(());
+        }
+        let sql_dialect = self.dialect.sql_dialect();
+        match sql_dialect.returning_style() {
+            ReturningStyle::Unsupported => Err(InsertError::ReturningUnsupported {
+                dialect: self.dialect,
+            }),
+            ReturningStyle::Returning
+                if self.upsert
+                    && sql_dialect
+                        .upsert_clause(&self.conflict_keys, &self.update_columns)
+                        .is_none() =>
+            {
+                Err(InsertError::ReturningWithMerge {
+                    dialect: self.dialect,
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn columns(&self) -> &Vec<String> {
+        &self.columns
+    }
+
+    pub fn returning_columns(&self) -> &Vec<String> {
+        &self.returning
+    }
+
+    pub fn generate_query(&self, pretty_print: bool) -> String {
+        self.generate_query_with(&FormatOptions::from_pretty_print(pretty_print))
+    }
+
+    // Generates the query only when the table and every column are in
+    // `allowlist`
+    pub fn generate_query_checked(
+        &self,
+        allowlist: &Allowlist,
+        pretty_print: bool,
+    ) -> Result<String, AllowlistError> {
+        let columns = self
+            .columns
+            .iter()
+            .chain(&self.conflict_keys)
+            .chain(&self.update_columns)
+            .chain(&self.returning);
+        allowlist.check_columns(&self.table, columns)?;
+        Ok(self.generate_query(pretty_print))
+    }
+
+    pub fn generate_query_with(&self, format: &FormatOptions) -> String {
+        self.build_query(format, self.generate_placeholders())
+    }
+
+    // `values` are the rendered placeholders, or literals for debug SQL
+    fn build_query(&self, format: &FormatOptions, values: Vec<String>) -> String {
+        let sql_dialect = self.dialect.sql_dialect();
+        let upsert_clause = if self.upsert {
+            if sql_dialect
+                .upsert_clause(&self.conflict_keys, &self.update_columns)
+                .is_none()
+            {
+                return self.generate_merge_query(format, values);
+            }
+            let keys = self.conflict_keys.len();
+            let operands = self
+                .conflict_keys
+                .iter()
+                .chain(&self.update_columns)
+                .map(String::as_str)
+                .collect::<Vec<_>>();
+            Some(format.keywords_around(&operands, |slots| {
+                sql_dialect
+                    .upsert_clause(&slots[..keys], &slots[keys..])
+                    .unwrap_or_default()
+            }))
+        } else {
+            None
+        };
+
+        let returning_style = self.returning_style();
+        let mut clauses = vec![format.parenthesized(
+            0,
+            &format!("{} {} ", format.keyword("INSERT INTO"), self.table),
+            &self.columns,
+        )];
+
+        // OUTPUT sits between the column list and VALUES
+        if returning_style == ReturningStyle::Output {
+            clauses.push(self.generate_output_clause(format));
+        }
+
+        clauses.push(format.parenthesized(0, &format.keyword("VALUES "), &values));
+
+        if let Some(clause) = upsert_clause {
+            clauses.push(clause);
+        }
+
+        if returning_style == ReturningStyle::Returning {
+            clauses.push(format.clause(0, "RETURNING", &self.returning, ItemLayout::List));
+        }
+
+        clauses.join(format.clause_separator())
+    }
+
+    // Style of the returning clause to render, Unsupported when nothing is
+    // returned. returning() has rejected the dialects without one
+    fn returning_style(&self) -> ReturningStyle {
+        if self.returning.is_empty() {
+            return ReturningStyle::Unsupported;
+        }
+        self.dialect.sql_dialect().returning_style()
+    }
+
+    fn generate_placeholders(&self) -> Vec<String> {
+        (1..=self.parameter_values.len() as u32)
+            .map(|i| determine_parameter_format(&self.dialect, i))
+            .collect()
+    }
+
+    fn generate_output_clause(&self, format: &FormatOptions) -> String {
+        let columns = self
+            .returning
+            .iter()
+            .map(|c| format!("{}.{}", format.keyword("INSERTED"), c))
+            .collect::<Vec<_>>();
+        format.clause(0, "OUTPUT", &columns, ItemLayout::List)
+    }
+
+    // Dialects without an upsert clause rewrite the insert as
+    // MERGE INTO target USING (values) source ON (conflict keys)
+    fn generate_merge_query(&self, format: &FormatOptions, values: Vec<String>) -> String {
+        let sql_dialect = self.dialect.sql_dialect();
+        let separator = format.clause_separator();
+
+        let columns = self.columns.len();
+        let operands = std::iter::once(&self.table)
+            .chain(&self.columns)
+            .map(String::as_str)
+            .collect::<Vec<_>>();
+        let mut query = format.keywords_around(&operands, |slots| {
+            sql_dialect.merge_source(&slots[0], &slots[1..=columns], &values)
+        });
+        query.push_str(separator);
+        query.push_str(&format!(
+            "{} ({})",
+            format.keyword("ON"),
+            self.conflict_keys
+                .iter()
+                .map(|c| format!("target.{} = source.{}", c, c))
+                .collect::<Vec<_>>()
+                .join(&format.keyword(" AND "))
+        ));
+
+        if !self.update_columns.is_empty() {
+            query.push_str(separator);
+            query.push_str(&format!(
+                "{} {}",
+                format.keyword("WHEN MATCHED THEN UPDATE SET"),
+                self.update_columns
+                    .iter()
+                    .map(|c| format!("target.{} = source.{}", c, c))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ));
+        }
+
+        query.push_str(separator);
+        query.push_str(&format!(
+            "{} ({}) {} ({})",
+            format.keyword("WHEN NOT MATCHED THEN INSERT"),
+            self.columns.join(", "),
+            format.keyword("VALUES"),
+            self.columns
+                .iter()
+                .map(|c| format!("source.{}", c))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ));
+
+        // RETURNING with MERGE is rejected by the builder
+        if self.returning_style() == ReturningStyle::Output {
+            query.push_str(separator);
+            query.push_str(&self.generate_output_clause(format));
+        }
+
+        query.push_str(sql_dialect.merge_terminator());
+        query
+    }
+
+    pub fn values(&self) -> &Vec<T> {
+        &self.parameter_values
+    }
+}
+
+impl<T: ToSqlLiteral> NvInsert<T> {
+    // Query with the values inlined as literals, for logs only
+    pub fn to_debug_sql(&self) -> String {
+        self.to_debug_sql_with(&DebugSqlOptions::default())
+    }
+
+    pub fn to_debug_sql_with(&self, options: &DebugSqlOptions) -> String {
+        let values = self
+            .columns
+            .iter()
+            .zip(&self.parameter_values)
+            .map(|(column, value)| {
+                if options.is_redacted(column) {
+                    REDACTED_LITERAL.to_string()
+                } else {
+                    value.to_sql_literal(&self.dialect)
+                }
+            })
+            .collect();
+        format!("{}{}", DEBUG_SQL_HEADER, self.build_query(&FormatOptions::compact(), values))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        generate_upsert_clause, DefaultMySqlParamType, DefaultPostgresParamType,
+        DefaultSqlServerParamType,
+    };
+
+    #[test]
+    fn test_mysql_on_duplicate_key_update() {
+        let insert = NvInsert::new(DatabaseDialect::MySQL)
+            .into_table("users")
+            .value("id", DefaultMySqlParamType::BigInt(1))
+            .value("email", DefaultMySqlParamType::String("a@b.c".to_string()))
+            .on_conflict_update(&["id"], &["email"])
+            .unwrap();
+
+        assert_eq!(
+            insert.generate_query(false),
+            "INSERT INTO users (id, email) VALUES (?, ?) \
+             ON DUPLICATE KEY UPDATE email = VALUES(email)"
+        );
+        assert_eq!(insert.values().len(), 2);
+    }
+
+    #[test]
+    fn test_postgres_on_conflict_returning() {
+        let insert = NvInsert::new(DatabaseDialect::PostgreSQL)
+            .into_table("users")
+            .value("id", DefaultPostgresParamType::BigInt(1))
+            .value(
+                "email",
+                DefaultPostgresParamType::String("a@b.c".to_string()),
+            )
+            .on_conflict_update(&["id"], &["email"])
+            .unwrap()
+            .returning(&["id"])
+            .unwrap();
+
+        assert_eq!(
+            insert.generate_query(false),
+            "INSERT INTO users (id, email) VALUES ($1, $2) \
+             ON CONFLICT (id) DO UPDATE SET email = EXCLUDED.email RETURNING id"
+        );
+    }
+    #[test]
+    fn test_sql_server_output_and_merge() {
+        let insert = NvInsert::new(DatabaseDialect::SqlServer)
+            .into_table("users")
+            .value("id", DefaultSqlServerParamType::BigInt(1))
+            .value(
+                "email",
+                DefaultSqlServerParamType::String("a@b.c".to_string()),
+            )
+            .returning(&["id"])
+            .unwrap();
+
+        assert_eq!(
+            insert.generate_query(false),
+            "INSERT INTO users (id, email) OUTPUT INSERTED.id VALUES (@p1, @p2)"
+        );
+
+        let upsert = insert.on_conflict_update(&["id"], &["email"]).unwrap();
+        assert_eq!(
+            upsert.generate_query(false),
+            "MERGE INTO users WITH (HOLDLOCK) AS target \
+             USING (VALUES (@p1, @p2)) AS source (id, email) ON (target.id = source.id) \
+             WHEN MATCHED THEN UPDATE SET target.email = source.email \
+             WHEN NOT MATCHED THEN INSERT (id, email) VALUES (source.id, source.email) \
+             OUTPUT INSERTED.id;"
+        );
+    }
+
+    #[test]
+    fn test_debug_sql_inlines_and_redacts_values() {
+        let insert = NvInsert::new(DatabaseDialect::MySQL)
+            .into_table("users")
+            .value("name", DefaultMySqlParamType::String("O'Brien \\ co".to_string()))
+            .value("avatar", DefaultMySqlParamType::Blob(vec![0xCA, 0xFE]))
+            .value("password", DefaultMySqlParamType::String("secret".to_string()));
+
+        assert_eq!(
+            insert.to_debug_sql_with(&DebugSqlOptions::default().redact("PASSWORD")),
+            "/* nvcore debug SQL, values inlined for logging, do not execute */ \
+             INSERT INTO users (name, avatar, password) VALUES ('O''Brien \\\\ co', X'CAFE', '***')"
+        );
+    }
+
+    #[test]
+    fn test_rejects_inserts_the_dialect_cannot_express() {
+        let insert = || {
+            NvInsert::new(DatabaseDialect::MySQL)
+                .into_table("users")
+                .value("id", DefaultMySqlParamType::BigInt(1))
+        };
+
+        assert_eq!(
+            insert().returning(&["id"]).unwrap_err(),
+            InsertError::ReturningUnsupported {
+                dialect: DatabaseDialect::MySQL
+            }
+        );
+        assert_eq!(
+            insert().on_conflict_update(&[], &["email"]).unwrap_err(),
+            InsertError::EmptyConflictKeys
+        );
+        assert_eq!(
+            generate_upsert_clause(&DatabaseDialect::Oracle, &["id".to_string()], &[])
+                .unwrap_err()
+                .to_string(),
+            "Oracle upserts are generated as MERGE statements"
+        );
+    }
+}
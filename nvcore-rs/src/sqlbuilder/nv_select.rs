@@ -1,164 +1,68 @@
-#![allow(dead_code)]
-
+// Compatibility layer over the owned select core
+//
+// NvSelect keeps the `Arc<Self>` chaining API and owns a `Select<T>`, every
+// call edits it in place when the Arc is unique and clones it otherwise, so
+// the hot path takes no locks. Opening a block (from, where_clause, order_by,
+// a subquery) moves the select into the block, and the matching end_* hands
+// it back with the block applied, which leaves no parent cycles behind.
+
+use crate::row::{FromRow, Row, RowError};
 use crate::sqlbuilder::{
-    DatabaseDialect, FieldDef, FieldDefMode, FromTableStatement, SqlAggregateFunction,
+    AggregateOptions, Allowlist, AllowlistError, DatabaseDialect, DebugSqlOptions, Expr,
+    FormatOptions, FromTableStatement, MutVisitor, NvTable, OrderByStatement, RenderError, Select,
+    SelectItem, SelectStmt, SqlAggregateFunction, SqlOperator, ToSqlLiteral, Visitor,
     WhereStatement,
 };
 
-use crate::utils::indent_space;
-use std::sync::{Arc, RwLock};
+use std::sync::Arc;
 
+#[derive(Debug, Clone)]
 pub struct NvSelect<T> {
-    current_param_index: RwLock<u32>,
-    // join_blocks: RwLock<Arc<Vec<JoinStatement<T>>>>,
-    from_statement: RwLock<Option<Arc<FromTableStatement<T>>>>,
-    fields: RwLock<Vec<FieldDef<T>>>,
-    parameter_values: Arc<RwLock<Vec<T>>>,
-    subquery_from_parent: RwLock<Option<Arc<FromTableStatement<T>>>>,
-    where_statement: RwLock<Option<Arc<WhereStatement<T>>>>,
-    // order_by: RwLock<Option<Arc<OrderByStatement<T>>>>,
-    // group_by: RwLock<Option<Arc<GroupByStatement<T>>>>,
-    subquery_where_parent: RwLock<Option<Arc<WhereStatement<T>>>>,
-    // limit_offset: Option<Arc<LimitOffsetStatement<T>>>,
-    table_alias: String,
-    level: u32,
-    dialect: DatabaseDialect,
+    select: Select<T>,
+    parent: Option<Box<SubqueryParent<T>>>,
 }
 
-impl<T> NvSelect<T> {
-    pub fn new(dialect: DatabaseDialect) -> Arc<Self> {
-        Arc::new(Self {
-            current_param_index: 0.into(),
-            level: 0,
-            // join_blocks: Arc::new(Vec::new()).into(),
-            from_statement: None.into(),
-            fields: Vec::new().into(),
-            parameter_values: Arc::new(RwLock::new(Vec::new())),
-            subquery_from_parent: None.into(),
-            table_alias: String::new(),
-            where_statement: None.into(),
-            // order_by: None.into(),
-            // group_by: None.into(),
-            subquery_where_parent: None.into(),
-            // limit_offset: None,
-            dialect: dialect,
-        })
-    }
+// Block a subquery was opened from, taken back by end_subquery_inside_*
+#[derive(Debug, Clone)]
+pub(crate) enum SubqueryParent<T> {
+    From {
+        statement: FromTableStatement<T>,
+        table_alias: String,
+    },
+    Where {
+        statement: WhereStatement<T>,
+        field_name: String,
+        op: SqlOperator,
+    },
+}
 
-    pub fn with_param_index(current_param_index: u32, dialect: DatabaseDialect) -> Arc<Self> {
+impl<T: Clone> NvSelect<T> {
+    pub fn new(dialect: DatabaseDialect) -> Arc<Self> {
         Arc::new(Self {
-            current_param_index: current_param_index.into(),
-            level: 0,
-            // join_blocks: Arc::new(Vec::new()).into(),
-            from_statement: None.into(),
-            fields: Vec::new().into(),
-            parameter_values: Arc::new(RwLock::new( Vec::new())),
-            subquery_from_parent: None.into(),
-            table_alias: String::new().into(),
-            where_statement: None.into(),
-            // order_by: None.into(),
-            // group_by: None.into(),
-            subquery_where_parent: None.into(),
-            // limit_offset: None,
-            dialect: dialect,
+            select: Select::new(dialect),
+            parent: None,
         })
     }
 
-    pub fn new_subquery(
-        values: Arc<RwLock<Vec<T>>>,
-        current_param_index: u32,
-        level: u32,
-        dialect: DatabaseDialect,
-    ) -> Arc<Self> {
+    pub(crate) fn subquery(dialect: DatabaseDialect, parent: SubqueryParent<T>) -> Arc<Self> {
         Arc::new(Self {
-            current_param_index: current_param_index.into(),
-            level,
-            // join_blocks: Arc::new(Vec::new()).into(),
-            from_statement: None.into(),
-            fields: Vec::new().into(),
-            parameter_values: values,
-            subquery_from_parent: None.into(),
-            table_alias: String::new(),
-            where_statement: None.into(),
-            // order_by: None.into(),
-            // group_by: None.into(),
-            subquery_where_parent: None.into(),
-            // limit_offset: None,
-            dialect: dialect,
+            select: Select::new(dialect),
+            parent: Some(Box::new(parent)),
         })
     }
 
-    pub fn new_subquery_from(
-        values: Arc<RwLock<Vec<T>>>,
-        current_param_index: u32,
-        level: u32,
-        from_obj: Arc<FromTableStatement<T>>,
-        table_alias: String,
-        dialect: DatabaseDialect,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            current_param_index: current_param_index.into(),
-            level,
-            // join_blocks: Arc::new(Vec::new()).into(),
-            from_statement: None.into(),
-            fields: Vec::new().into(),
-            parameter_values: values,
-            subquery_from_parent: Some(from_obj).into(),
-            table_alias,
-            where_statement: None.into(),
-            // order_by: None.into(),
-            // group_by: None.into(),
-            subquery_where_parent: None.into(),
-            // limit_offset: None,
-            dialect: dialect,
-        })
+    pub(crate) fn map(self: Arc<Self>, build: impl FnOnce(Select<T>) -> Select<T>) -> Arc<Self> {
+        let mut nv_select = Arc::unwrap_or_clone(self);
+        nv_select.select = build(nv_select.select);
+        Arc::new(nv_select)
     }
 
-    pub fn new_subquery_where(
-        values: Arc<RwLock<Vec<T>>>,
-        where_obj: Arc<WhereStatement<T>>,
-        current_param_index: u32,
-        level: u32,
-        table_alias: String,
-        dialect: DatabaseDialect,
-    ) -> Arc<Self> {
-        Arc::new(Self {
-            current_param_index: current_param_index.into(),
-            level,
-            // join_blocks: Arc::new(Vec::new()).into(),
-            from_statement: None.into(),
-            fields: Vec::new().into(),
-            parameter_values: values,
-            subquery_from_parent: None.into(),
-            table_alias,
-            where_statement: None.into(),
-            // order_by: None.into(),
-            // group_by: None.into(),
-            subquery_where_parent: Some(where_obj).into(),
-            // limit_offset: None,
-            dialect: dialect,
-        })
+    pub(crate) fn into_inner(self) -> Select<T> {
+        self.select
     }
 
     pub fn dialect(&self) -> DatabaseDialect {
-        self.dialect
-    }
-
-    pub fn get_current_param_index(&self) -> u32 {
-        *self.current_param_index.read().unwrap()
-    }
-
-    pub fn update_current_param_index(&mut self, current_param_index: u32) {
-        let mut write_guard = self.current_param_index.write().unwrap();
-        *write_guard = current_param_index;
-    }
-
-    pub fn table_alias(&self) -> &str {
-        &self.table_alias
-    }
-
-    pub fn get_block_level(&self) -> u32 {
-        self.level
+        self.select.dialect()
     }
 
     pub fn field(
@@ -169,26 +73,13 @@ impl<T> NvSelect<T> {
         aggregate_fn: SqlAggregateFunction,
         enclose_field_name: bool,
     ) -> Arc<Self> {
-        let mut field_guard = self.fields.write().unwrap();
-
-        let d = self.dialect.clone();
-        let l = self.level.clone();
-
-        // let field_mut = Arc::get_mut(&mut *field_guard)
-        //     .expect("There should be no other references to the Arc at this point");
-
-        field_guard.push(FieldDef::new_field_def(
-            d,
+        self.f(
             field,
             table_alias,
-            enclose_field_name,
-            aggregate_fn,
             field_alias,
-            l,
-            FieldDefMode::FieldWType,
-        ));
-
-        self.clone()
+            aggregate_fn,
+            enclose_field_name,
+        )
     }
 
     pub fn f(
@@ -199,336 +90,480 @@ impl<T> NvSelect<T> {
         aggregate_fn: SqlAggregateFunction,
         enclose_field_name: bool,
     ) -> Arc<Self> {
-        let mut field_guard = self.fields.write().unwrap();
+        self.map(|select| {
+            select.f(
+                &field,
+                table_alias.as_deref(),
+                field_alias.as_deref(),
+                aggregate_fn,
+                enclose_field_name,
+            )
+        })
+    }
 
-        field_guard.push(FieldDef::new_field_def(
-            self.dialect,
-            field,
-            table_alias,
-            enclose_field_name,
-            aggregate_fn,
-            field_alias,
-            self.level,
-            FieldDefMode::FieldRaw,
-        ));
+    // Every field of the entity, unqualified and selected under its field
+    // name so rows map back with FromRow
+    pub fn select_all<E: NvTable>(self: Arc<Self>) -> Arc<Self> {
+        E::fields().iter().fold(self, |select, (column, field)| {
+            select.f(
+                column.to_string(),
+                None,
+                Some(field.to_string()),
+                SqlAggregateFunction::None,
+                false,
+            )
+        })
+    }
 
-        self.clone()
+    // Names of the select list in result rows, see Select::column_names
+    pub fn column_names(&self) -> Vec<String> {
+        self.select.column_names()
     }
 
-    pub fn end_subquery_inside_from(self: Arc<Self>) -> Arc<FromTableStatement<T>> {
-        let mut from_parent_guard = self.subquery_from_parent.write().unwrap();
+    // Maps a result row of this select, tuples take the columns in order
+    pub fn map_row<F: FromRow, R: Row>(&self, row: &R) -> Result<F, RowError> {
+        F::from_row(row, &self.column_names())
+    }
 
-        if from_parent_guard.is_none() {
-            panic!("Call this only from .From().AddSubquery().EndFromSubquery()");
-        }
+    pub fn aggregate(
+        self: Arc<Self>,
+        aggregate_fn: SqlAggregateFunction,
+        field: String,
+        table_alias: Option<String>,
+        field_alias: Option<String>,
+        options: AggregateOptions,
+    ) -> Arc<Self> {
+        self.map(|select| {
+            select.aggregate(
+                aggregate_fn,
+                &field,
+                table_alias.as_deref(),
+                field_alias.as_deref(),
+                options,
+            )
+        })
+    }
 
-        let p_index = *self.current_param_index.read().unwrap();
+    pub fn fn_static(
+        self: Arc<Self>,
+        fn_name: String,
+        param_values: Arc<Vec<String>>,
+        field_alias: Option<String>,
+    ) -> Arc<Self> {
+        self.map(|select| {
+            select.item(SelectItem {
+                expr: Expr::Function {
+                    name: fn_name,
+                    args: param_values.iter().cloned().map(Expr::Raw).collect(),
+                },
+                alias: field_alias,
+            })
+        })
+    }
 
-        // Use as_mut to get a mutable reference inside the Option
-        if let Some(ref mut from_parent_rw) = *from_parent_guard {
-            Arc::get_mut(from_parent_rw)
-                .expect("There should not be any other mutable references here")
-                .update_current_parameter_index(p_index);
-        }
+    pub fn fn_dynamic(
+        self: Arc<Self>,
+        fn_name: String,
+        parameter_list_format: String,
+        param_values: Arc<Vec<T>>,
+        static_param_values: Arc<Vec<String>>,
+        field_alias: Option<String>,
+    ) -> Arc<Self> {
+        self.map(|select| {
+            select.fn_dynamic(
+                &fn_name,
+                &parameter_list_format,
+                param_values.to_vec(),
+                static_param_values.to_vec(),
+                field_alias.as_deref(),
+            )
+        })
+    }
 
-        // Clone the Arc to return
-        Arc::clone(from_parent_guard.as_ref().unwrap())
+    pub fn from(self: Arc<Self>) -> Arc<FromTableStatement<T>> {
+        FromTableStatement::with_parent(Arc::unwrap_or_clone(self))
     }
 
-    pub fn end_subquery_inside_where_condition(self: Arc<Self>) -> Arc<WhereStatement<T>> {
-        let mut where_parent_guard = self.subquery_where_parent.write().unwrap();
+    // A second WHERE block is ANDed with the conditions of the first
+    pub fn where_clause(self: Arc<Self>) -> Arc<WhereStatement<T>> {
+        WhereStatement::with_parent(Arc::unwrap_or_clone(self))
+    }
 
-        if where_parent_guard.is_none() {
-            panic!("Call this only from .From().AddSubquery().EndFromSubquery()");
-        }
+    pub fn order_by(self: Arc<Self>) -> Arc<OrderByStatement<T>> {
+        OrderByStatement::with_parent(Arc::unwrap_or_clone(self))
+    }
 
-        let p_index = *self.current_param_index.read().unwrap();
+    pub fn end_subquery_inside_from(self: Arc<Self>) -> Arc<FromTableStatement<T>> {
+        let mut subquery = Arc::unwrap_or_clone(self);
+        match subquery.parent.take().map(|parent| *parent) {
+            Some(SubqueryParent::From {
+                statement,
+                table_alias,
+            }) => statement.attach_subquery(&table_alias, subquery),
+            _ => panic!("Call this only from .From().AddSubquery().EndFromSubquery()"),
+        }
+    }
 
-        // Use as_mut to get a mutable reference inside the Option
-        if let Some(ref mut where_parent_rw) = *where_parent_guard {
-            Arc::get_mut(where_parent_rw)
-                .expect("There should not be any other mutable references here")
-                .update_current_parameter_index(p_index);
+    pub fn end_subquery_inside_where_condition(self: Arc<Self>) -> Arc<WhereStatement<T>> {
+        let mut subquery = Arc::unwrap_or_clone(self);
+        match subquery.parent.take().map(|parent| *parent) {
+            Some(SubqueryParent::Where {
+                statement,
+                field_name,
+                op,
+            }) => statement.attach_subquery(&field_name, op, subquery),
+            _ => panic!("Call this only from .Where().AddSubquery().EndWhereSubquery()"),
         }
+    }
 
-        // Clone the Arc to return
-        Arc::clone(where_parent_guard.as_ref().unwrap())
+    pub fn limit(self: Arc<Self>, limit: u64) -> Arc<Self> {
+        self.map(|select| select.limit(limit))
     }
 
-    pub fn from(self: Arc<Self>) -> Arc<FromTableStatement<T>> {
-        {
-            let from_parent_guard = self.from_statement.write().unwrap();
+    // Fails on dialects that need the ORDER BY block first, see Select::offset
+    pub fn offset(self: Arc<Self>, offset: u64) -> Result<Arc<Self>, RenderError> {
+        let mut nv_select = Arc::unwrap_or_clone(self);
+        nv_select.select = nv_select.select.offset(offset)?;
+        Ok(Arc::new(nv_select))
+    }
 
-            if let Some(ref from_parent_rw) = *from_parent_guard {
-                return Arc::clone(from_parent_rw);
-            }
-        }
+    pub fn limit_offset(
+        self: Arc<Self>,
+        limit: u64,
+        offset: u64,
+    ) -> Result<Arc<Self>, RenderError> {
+        self.limit(limit).offset(offset)
+    }
 
-        // The locks are dropped here, avoiding deadlock
+    // Bound values in placeholder order, see Select::values
+    pub fn values(&self) -> Vec<&T> {
+        self.select.values()
+    }
 
-        
-        let current_param_index = *self.current_param_index.read().unwrap();
+    pub fn to_select(&self) -> Select<T> {
+        self.select.clone()
+    }
 
-        let from = FromTableStatement::new(
-            self.parameter_values.clone(),
-            self.clone(),
-            current_param_index,
-            self.level,
-            self.dialect.clone(),
-        );
+    pub fn to_statement(&self) -> SelectStmt<T> {
+        self.to_select().build()
+    }
 
-        let from_clone = from.clone();
+    pub fn generate_query(&self, pretty_print: bool) -> String {
+        self.select.generate_query(pretty_print)
+    }
 
-        {
-            let mut from_parent_guard = self.from_statement.write().unwrap();
-            *from_parent_guard = Some(Arc::clone(&from_clone));
-        }
+    pub fn generate_query_with(&self, format: &FormatOptions) -> String {
+        self.select.generate_query_with(format)
+    }
 
-        from_clone
+    // Returns the paging error `generate_query` panics with, see Select::validate
+    pub fn try_generate_query(&self, pretty_print: bool) -> Result<String, RenderError> {
+        self.select.try_generate_query(pretty_print)
     }
 
-    pub fn where_clause(self: Arc<Self>) -> Arc<WhereStatement<T>> {
-        {
-            let where_parent_guard = self.where_statement.write().unwrap();
+    // Generates the query only when every identifier is in `allowlist`
+    pub fn generate_query_checked(
+        &self,
+        allowlist: &Allowlist,
+        pretty_print: bool,
+    ) -> Result<String, AllowlistError> {
+        self.select.generate_query_checked(allowlist, pretty_print)
+    }
 
-            if let Some(ref where_parent_rw) = *where_parent_guard {
-                return Arc::clone(where_parent_rw);
-            }
-        }
+    pub fn accept<V: Visitor<T> + ?Sized>(&self, visitor: &mut V) {
+        self.select.statement().accept(visitor);
+    }
 
-        // The locks are dropped here, avoiding deadlock
+    // Rewrites a copy of the tree and returns it as an owned select
+    pub fn rewrite<V: MutVisitor<T> + ?Sized>(&self, visitor: &mut V) -> Select<T> {
+        self.to_select().rewrite(visitor)
+    }
 
-        // let parameter_values = self.parameter_values.read().unwrap().clone();
-        // let current_param_index = *self.current_param_index.read().unwrap();
+    // Query with the values inlined as literals, for logs only
+    pub fn to_debug_sql(&self) -> String
+    where
+        T: ToSqlLiteral,
+    {
+        self.select.to_debug_sql()
+    }
 
-        let where_obj = WhereStatement::new_with_parent(
-            self.parameter_values.clone(),
-            self.clone(),
-            self.current_param_index.read().unwrap().clone(),
-            self.level,
-            self.dialect,
-        );
-        let where_clone = where_obj.clone();
+    pub fn to_debug_sql_with(&self, options: &DebugSqlOptions) -> String
+    where
+        T: ToSqlLiteral,
+    {
+        self.select.to_debug_sql_with(options)
+    }
+}
 
-        {
-            let mut where_parent_guard = self.where_statement.write().unwrap();
-            *where_parent_guard = Some(Arc::clone(&where_clone));
-        }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        ConditionError, DefaultMySqlParamType, DefaultSqlServerParamType, SqlOperator,
+    };
+
+    #[test]
+    fn test_mysql_select_pipeline() {
+        let select = NvSelect::<DefaultMySqlParamType>::new(DatabaseDialect::MySQL)
+            .f(
+                "id".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "order".to_string(),
+                Some("u".to_string()),
+                Some("o".to_string()),
+                SqlAggregateFunction::None,
+                true,
+            )
+            .fn_dynamic(
+                "CONCAT".to_string(),
+                "sv".to_string(),
+                Arc::new(vec![DefaultMySqlParamType::String("!".to_string())]),
+                Arc::new(vec!["u.name".to_string()]),
+                Some("n".to_string()),
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block()
+            .where_clause()
+            .add_condition("u.id", &SqlOperator::Greater, DefaultMySqlParamType::Int(1))
+            .unwrap()
+            .and()
+            .add_condition_between(
+                "u.age",
+                DefaultMySqlParamType::Int(18),
+                DefaultMySqlParamType::Int(65),
+            )
+            .and()
+            .add_condition(
+                "u.name",
+                &SqlOperator::Regexp,
+                DefaultMySqlParamType::String("^a".to_string()),
+            )
+            .unwrap()
+            .end_where_block()
+            .limit_offset(10, 20)
+            .unwrap();
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT u.id, u.`order` AS o, CONCAT(u.name, ?) AS n FROM users AS u \
+             WHERE u.id > ? AND u.age BETWEEN ? AND ? AND u.name REGEXP ? LIMIT 20, 10"
+        );
+        assert_eq!(select.values().len(), 5);
+    }
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_nvsql_macro() {
+        use crate::sqlbuilder::{nvsql, DefaultPostgresParamType};
+
+        type Param = DefaultPostgresParamType;
+        let pattern = "a%".to_string();
+        let ids = vec![3, 4];
+        let page = 2;
+        let select: Arc<NvSelect<Param>> = nvsql!(DatabaseDialect::PostgreSQL,
+            SELECT u.id, u.name AS user_name, COUNT(*) AS total
+            FROM users u
+            WHERE u.age BETWEEN 18 AND #{65} AND (u.name LIKE #{pattern} OR u.id IN #{ids})
+                AND u.active = TRUE
+            ORDER BY u.id DESC
+            LIMIT 20 OFFSET #{page * 20}
+        );
 
-        where_clone
-    }
-
-    // pub fn join(&mut self) -> &mut JoinStatement<T> {
-    //     self.join_blocks.push(JoinStatement::new(
-    //         self as *mut _,
-    //         self.current_param_index,
-    //         self.level,
-    //         self.dialect,
-    //     ));
-    //     self.join_blocks.last_mut().unwrap()
-    // }
-
-    // pub fn order_by(&mut self) -> &mut OrderByStatement<T> {
-    //     if self.order_by.is_none() {
-    //         self.order_by = Some(Arc::new(OrderByStatement::new_with_parent(
-    //             self as *mut _,
-    //             self.level,
-    //         )));
-    //     }
-    //     Arc::get_mut(self.order_by.as_mut().unwrap()).unwrap()
-    // }
-
-    // pub fn group_by(&mut self) -> &mut GroupByStatement<T> {
-    //     if self.group_by.is_none() {
-    //         self.group_by = Some(Arc::new(GroupByStatement::new_with_parent(
-    //             self as *mut _,
-    //             self.current_param_index,
-    //             self.level,
-    //         )));
-    //     }
-    //     Arc::get_mut(self.group_by.as_mut().unwrap()).unwrap()
-    // }
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT u.id, u.name AS user_name, COUNT(*) AS total FROM users AS u \
+             WHERE u.age BETWEEN $1 AND $2 AND (u.name LIKE $3 OR u.id IN ($4, $5)) \
+             AND u.active = $6 ORDER BY u.id DESC LIMIT 20 OFFSET 40"
+        );
+        assert_eq!(
+            select.values(),
+            vec![
+                &Param::Int(18),
+                &Param::Int(65),
+                &Param::String("a%".to_string()),
+                &Param::Int(3),
+                &Param::Int(4),
+                &Param::Bool(true),
+            ]
+        );
 
-    pub fn fn_static(
-        self: Arc<Self>,
-        fn_name: String,
-        param_values: Arc<Vec<String>>,
-        field_alias: Option<String>,
-    ) -> Arc<Self> {
-        self.fields
-            .write()
-            .unwrap()
-            .push(FieldDef::new_static_function(
-                self.dialect,
-                fn_name,
-                param_values,
-                self.level,
-                field_alias,
-            ));
-        self
+        // Both builders render an empty list as a predicate matching no row
+        let ids: Vec<i32> = Vec::new();
+        let name = "ana".to_string();
+        let select: Arc<NvSelect<Param>> = nvsql!(DatabaseDialect::PostgreSQL,
+            SELECT id FROM users WHERE id IN #{ids} OR name = #{name.clone()}
+        );
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT id FROM users WHERE 1 = 0 OR name = $1"
+        );
+        assert_eq!(
+            select.generate_query(false),
+            Select::<Param>::new(DatabaseDialect::PostgreSQL)
+                .f("id", None, None, SqlAggregateFunction::None, false)
+                .from_table("users", None)
+                .where_clause(|w| w
+                    .add_condition_in("id", Vec::new())
+                    .or()
+                    .add_condition("name", SqlOperator::Equal, Param::String(name))
+                    .unwrap())
+                .generate_query(false)
+        );
     }
 
-    pub fn fn_dynamic(
-        self: Arc<Self>,
-        fn_name: String,
-        parameter_list_format: String,
-        param_values: Arc<Vec<T>>,
-        static_param_values: Arc<Vec<String>>,
-        field_alias: Option<String>,
-    ) -> Arc<Self> {
-        self.fields
-            .write()
+    #[test]
+    fn test_sql_server_paging() {
+        let top = NvSelect::<DefaultSqlServerParamType>::new(DatabaseDialect::SqlServer)
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .f(
+                "order".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                true,
+            )
+            .from()
+            .add_table_with_alias("orders", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "id",
+                &SqlOperator::Greater,
+                DefaultSqlServerParamType::Int(1),
+            )
             .unwrap()
-            .push(FieldDef::new_dynamic_function(
-                self.dialect,
-                fn_name,
-                parameter_list_format,
-                self.parameter_values.clone(),
-                param_values,
-                static_param_values,
-                self.current_param_index.read().unwrap().clone(),
-                self.level,
-                field_alias,
-            ));
-
-        self
-    }
-
-    // pub fn limit_offset(&mut self) -> &mut LimitOffsetStatement<T> {
-    //     if self.limit_offset.is_none() {
-    //         self.limit_offset = Some(Arc::new(LimitOffsetStatement::new(
-    //             self as *mut _,
-    //             self.parameter_values.clone(),
-    //             self.current_param_index,
-    //             self.level,
-    //             self.dialect,
-    //         )));
-    //     }
-    //     Arc::get_mut(self.limit_offset.as_mut().unwrap()).unwrap()
-    // }
+            .end_where_block()
+            .limit(5);
 
-    pub fn generate_query(&self, pretty_print: bool) -> String {
-        let mut query = String::new();
-
-        let fields_guard = self.fields.read().unwrap();
-        let from_table_guard = self.from_statement.read().unwrap();
-        let where_statement_guard = self.where_statement.read().unwrap();
-
-        // SELECT
-        if pretty_print {
-            query.push_str(&indent_space(self.level));
-            query.push_str("SELECT \n");
-        } else {
-            query.push_str("SELECT ");
-        }
+        assert_eq!(
+            top.generate_query(false),
+            "SELECT TOP (5) id, [order] FROM orders WHERE id > @p1"
+        );
 
-        let mut first_element = true;
-        for field in fields_guard.iter() {
-            if !first_element {
-                query.push_str(if pretty_print { ",\n" } else { ", " });
-            }
-            if pretty_print {
-                query.push_str(&indent_space(self.level + 1));
-            }
-            query.push_str(&field.generate_query());
-            first_element = false;
-        }
+        let page = top
+            .order_by()
+            .desc("id".to_string(), None, true)
+            .end_order_by_block()
+            .offset(10)
+            .unwrap();
+
+        assert_eq!(
+            page.generate_query(false),
+            "SELECT id, [order] FROM orders WHERE id > @p1 ORDER BY id DESC \
+             OFFSET 10 ROWS FETCH NEXT 5 ROWS ONLY"
+        );
+    }
 
-        // FROM
-        if let Some(from_table) = from_table_guard.as_ref() {
-            if !from_table.is_empty() {
-                if pretty_print {
-                    query.push_str(&format!(
-                        "\n{}FROM \n{}",
-                        indent_space(self.level),
-                        from_table.generate_query(pretty_print)
-                    ));
-                } else {
-                    query.push_str(&format!(
-                        " FROM {}",
-                        from_table.generate_query(pretty_print)
-                    ));
-                }
+    #[test]
+    fn test_sql_server_offset_without_order_by() {
+        let select = NvSelect::<DefaultSqlServerParamType>::new(DatabaseDialect::SqlServer)
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("orders", &None)
+            .end_from_table_block();
+
+        assert_eq!(
+            select.clone().offset(10).unwrap_err(),
+            RenderError::OffsetWithoutOrderBy {
+                dialect: DatabaseDialect::SqlServer
             }
-        }
+        );
+        assert_eq!(
+            select
+                .order_by()
+                .asc("id".to_string(), None, false)
+                .end_order_by_block()
+                .offset(10)
+                .unwrap()
+                .try_generate_query(false)
+                .unwrap(),
+            "SELECT id FROM orders ORDER BY id OFFSET 10 ROWS"
+        );
+    }
 
-        // // JOIN
-        // if !self.join_blocks.is_empty() {
-        //     query.push_str(if pretty_print { "\n" } else { " " });
-        //     for join_block in &self.join_blocks {
-        //         query.push_str(&join_block.generate_query(pretty_print));
-        //     }
-        // }
-
-        // WHERE
-        if let Some(where_) = where_statement_guard.as_ref() {
-            if pretty_print {
-                query.push_str(&format!(
-                    "\n{}WHERE\n{}",
-                    indent_space(self.level),
-                    where_.generate_query(pretty_print, false)
-                ));
-            } else {
-                query.push_str(&format!(
-                    " WHERE {}",
-                    where_.generate_query(pretty_print, false)
-                ));
-            }
-        }
+    #[test]
+    fn test_named_conditions_in_where_block() {
+        type Param = DefaultSqlServerParamType;
+        let select = NvSelect::<Param>::new(DatabaseDialect::SqlServer)
+            .f(
+                "id".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("orders", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_named_condition("tenant_id", &SqlOperator::Equal, "tenant")
+            .unwrap()
+            .and()
+            .add_named_condition_in("status", &["paid", "open"])
+            .and()
+            .add_named_condition_between("total", "low", "high")
+            .end_where_block();
+
+        let prepared = select.prepare();
+        assert_eq!(
+            prepared.sql(),
+            "SELECT id FROM orders WHERE tenant_id = @tenant AND status IN (@paid, @open) \
+             AND total BETWEEN @low AND @high"
+        );
+        let values = prepared
+            .binder()
+            .set_named("tenant", Param::Int(7))
+            .set_named("paid", Param::String("paid".to_string()))
+            .set_named("open", Param::String("open".to_string()))
+            .set_named("low", Param::Int(10))
+            .set_named("high", Param::Int(20))
+            .finish()
+            .unwrap();
+        assert_eq!(values.len(), 5);
+
+        let error = NvSelect::<Param>::new(DatabaseDialect::SqlServer)
+            .where_clause()
+            .add_named_condition("status", &SqlOperator::In, "statuses")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "IN takes several values, use the between or in condition on status"
+        );
+    }
 
-        // // GROUP BY
-        // if let Some(ref group_by) = self.group_by {
-        //     if pretty_print {
-        //         query.push_str(&format!(
-        //             "\n{}GROUP BY\n{}{}",
-        //             indent_space(self.level),
-        //             indent_space(self.level + 1),
-        //             group_by.generate_query(pretty_print)
-        //         ));
-        //     } else {
-        //         query.push_str(&format!(
-        //             " GROUP BY {}",
-        //             group_by.generate_query(pretty_print)
-        //         ));
-        //     }
-        // }
-
-        // // ORDER BY
-        // if let Some(ref order_by) = self.order_by {
-        //     if pretty_print {
-        //         query.push_str(&format!(
-        //             "\n{}ORDER BY\n{}{}",
-        //             indent_space(self.level),
-        //             indent_space(self.level + 1),
-        //             order_by.generate_query(pretty_print)
-        //         ));
-        //     } else {
-        //         query.push_str(&format!(
-        //             " ORDER BY {}",
-        //             order_by.generate_query(pretty_print)
-        //         ));
-        //     }
-        // }
-
-        query
-    }
-
-    pub fn values(&self) -> Arc<RwLock<Vec<T>>> {
-        self.parameter_values.clone()
+    #[test]
+    fn test_where_block_rejects_multi_value_operators() {
+        let error = NvSelect::<DefaultSqlServerParamType>::new(DatabaseDialect::SqlServer)
+            .where_clause()
+            .add_condition(
+                "total",
+                &SqlOperator::Between,
+                DefaultSqlServerParamType::Int(1),
+            )
+            .unwrap_err();
+        assert_eq!(
+            error,
+            ConditionError::MultiValue {
+                field_name: "total".to_string(),
+                op: SqlOperator::Between
+            }
+        );
     }
 }
-
-// impl<T> NvSelect<T> {
-//     pub fn generate_tuples_holder<FieldTypes>(&self) -> Vec<FieldTypes> {
-//         Vec::new()
-//     }
-// }
-
-// // Implement additional methods required for `JoinDef`, `JoinStatement`, `FromTableStatement`, and `Condition` classes
-// impl<T> JoinDef<T> {}
-
-// impl<T> JoinStatement<T> {}
-
-// impl<T> Condition<T> {
-
-// }
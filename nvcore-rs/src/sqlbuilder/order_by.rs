@@ -1,91 +1,35 @@
-#![allow(dead_code)]
-
-use crate::sqlbuilder::NvSelect;
-use crate::sqlbuilder::SortType;
-
-pub struct OrderByClause {
-    field_name: String,
-    table_alias: Option<String>,
-    level: u32,
-    sort_type: SortType,
-    define_sort_type: bool,
-}
-
-impl OrderByClause {
-    pub fn new(field_name: String, sort: SortType, define_sort_type: bool, level: u32) -> Self {
-        Self {
-            field_name,
-            table_alias: None,
-            sort_type: sort,
-            define_sort_type,
-            level,
-        }
-    }
-
-    pub fn new_with_alias(
-        field_name: String,
-        alias: Option<String>,
-        sort: SortType,
-        define_sort_type: bool,
-        level: u32,
-    ) -> Self {
-        Self {
-            field_name,
-            table_alias: alias,
-            sort_type: sort,
-            define_sort_type,
-            level,
-        }
-    }
-
-    pub fn build_fieldname(&self) -> String {
-        match &self.table_alias {
-            Some(alias) => format!("{}.{}", alias, self.field_name),
-            None => self.field_name.clone(),
-        }
-    }
-
-    pub fn generate_query(&self) -> String {
-        let mut query = self.build_fieldname();
-        if self.define_sort_type {
-            query.push_str(match self.sort_type {
-                SortType::Ascending => " ASC",
-                SortType::Descending => " DESC",
-            });
-        }
-        query
-    }
-}
+use crate::sqlbuilder::{Expr, NvSelect, OrderByItem, Select, SortType};
+use std::sync::Arc;
 
+// ORDER BY block of NvSelect, owning its select until end_order_by_block
+// hands it back with the sort items appended
+#[derive(Debug, Clone)]
 pub struct OrderByStatement<T> {
-    parent: Option<*mut NvSelect<T>>,
-    sorts: Vec<OrderByClause>,
-    level: u32,
+    parent: Option<NvSelect<T>>,
+    items: Vec<OrderByItem<T>>,
 }
 
-impl<T> OrderByStatement<T> {
-    pub fn new() -> Self {
-        Self {
+impl<T: Clone> OrderByStatement<T> {
+    pub fn new() -> Arc<Self> {
+        Arc::new(Self {
             parent: None,
-            sorts: Vec::new(),
-            level: 0,
-        }
+            items: Vec::new(),
+        })
     }
 
-    pub fn new_with_parent(parent: *mut NvSelect<T>, level: u32) -> Self {
-        Self {
+    pub(crate) fn with_parent(parent: NvSelect<T>) -> Arc<Self> {
+        Arc::new(Self {
             parent: Some(parent),
-            sorts: Vec::new(),
-            level,
-        }
+            items: Vec::new(),
+        })
     }
 
     pub fn asc(
-        self,
+        self: Arc<Self>,
         field_name: String,
         table_alias: Option<String>,
         define_sort_type: bool,
-    ) -> Self {
+    ) -> Arc<Self> {
         self.by(
             field_name,
             table_alias,
@@ -95,11 +39,11 @@ impl<T> OrderByStatement<T> {
     }
 
     pub fn desc(
-        self,
+        self: Arc<Self>,
         field_name: String,
         table_alias: Option<String>,
         define_sort_type: bool,
-    ) -> Self {
+    ) -> Arc<Self> {
         self.by(
             field_name,
             table_alias,
@@ -108,43 +52,37 @@ impl<T> OrderByStatement<T> {
         )
     }
 
+    // Without `define_sort_type` the direction is left to the database
     pub fn by(
-        mut self,
+        self: Arc<Self>,
         field_name: String,
         table_alias: Option<String>,
         sort_type: SortType,
         define_sort_type: bool,
-    ) -> Self {
-        let clause = OrderByClause::new_with_alias(
-            field_name,
-            table_alias,
-            sort_type,
-            define_sort_type,
-            self.level,
-        );
-        self.sorts.push(clause);
-        self
+    ) -> Arc<Self> {
+        let mut statement = Arc::unwrap_or_clone(self);
+        statement.items.push(OrderByItem {
+            expr: Expr::column(table_alias.as_deref(), &field_name),
+            sort: define_sort_type.then_some(sort_type),
+        });
+        Arc::new(statement)
     }
 
-    pub fn generate_query(&self, _pretty_print: bool) -> String {
-        let mut query = String::new();
-        let mut is_first_element = true;
-
-        for s in &self.sorts {
-            if !is_first_element {
-                query.push_str(", ");
-            }
-            query.push_str(&s.generate_query());
-            is_first_element = false;
-        }
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
 
-        query
+    pub fn to_order_by_items(&self) -> Vec<OrderByItem<T>> {
+        self.items.clone()
     }
 
-    pub fn end_order_by_block(&self) -> &NvSelect<T> {
-        match self.parent {
-            Some(parent) => unsafe { &*parent },
+    pub fn end_order_by_block(self: Arc<Self>) -> Arc<NvSelect<T>> {
+        let statement = Arc::unwrap_or_clone(self);
+        let parent = match statement.parent {
+            Some(parent) => parent,
             None => panic!("null-reference to parent of NvSelect<T>"),
-        }
+        };
+        let items = statement.items;
+        Arc::new(parent).map(|select| items.into_iter().fold(select, Select::order_by_item))
     }
 }
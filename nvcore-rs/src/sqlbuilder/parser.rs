@@ -0,0 +1,1200 @@
+// Parser for PostgreSQL and Oracle SELECT statements
+//
+// Turns existing SQL text into the select syntax tree so legacy queries can be
+// analysed, rewritten and rendered through the builder. Literals in WHERE and
+// JOIN ... ON predicates become bound parameters, literals in the select list
+// and ORDER BY keep their SQL text, and :name binds become named parameters.
+// Anything the tree cannot represent, such as GROUP BY, NOT, IS NULL or
+// arithmetic, is reported as a ParseError rather than silently dropped.
+
+use crate::sqlbuilder::{
+    AggregateOptions, DatabaseDialect, DefaultMySqlParamType, DefaultOracleParamType,
+    DefaultPostgresParamType, DefaultSqlServerParamType, DefaultSqliteParamType, Expr, Join,
+    OrderByItem, Predicate, Select, SelectItem, SelectStmt, SortType, SqlAggregateFunction,
+    SqlJoinType, SqlOperator, TableRef,
+};
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use std::fmt;
+use std::time::SystemTime;
+
+// Literal value found in the SQL text, converted into the parameter type of the
+// statement with From<SqlLiteral>
+#[derive(Debug, Clone, PartialEq)]
+pub enum SqlLiteral {
+    Integer(i64),
+    Decimal(f64),
+    String(String),
+    Bool(bool),
+    // DATE '...' and TIMESTAMP '...', read as UTC
+    TimePoint(SystemTime),
+}
+
+impl From<SqlLiteral> for DefaultPostgresParamType {
+    fn from(literal: SqlLiteral) -> Self {
+        match literal {
+            SqlLiteral::Integer(value) => match i32::try_from(value) {
+                Ok(value) => DefaultPostgresParamType::Int(value),
+                Err(_) => DefaultPostgresParamType::BigInt(value),
+            },
+            SqlLiteral::Decimal(value) => DefaultPostgresParamType::Double(value),
+            SqlLiteral::String(value) => DefaultPostgresParamType::String(value),
+            SqlLiteral::Bool(value) => DefaultPostgresParamType::Bool(value),
+            SqlLiteral::TimePoint(value) => DefaultPostgresParamType::TimePoint(value),
+        }
+    }
+}
+
+impl From<SqlLiteral> for DefaultOracleParamType {
+    fn from(literal: SqlLiteral) -> Self {
+        match literal {
+            SqlLiteral::Integer(value) => match i32::try_from(value) {
+                Ok(value) => DefaultOracleParamType::Int(value),
+                Err(_) => DefaultOracleParamType::LongLong(value),
+            },
+            SqlLiteral::Decimal(value) => DefaultOracleParamType::Double(value),
+            SqlLiteral::String(value) => DefaultOracleParamType::String(value),
+            SqlLiteral::Bool(value) => DefaultOracleParamType::Bool(value),
+            SqlLiteral::TimePoint(value) => DefaultOracleParamType::TimePoint(value),
+        }
+    }
+}
+
+impl From<SqlLiteral> for DefaultMySqlParamType {
+    fn from(literal: SqlLiteral) -> Self {
+        match literal {
+            SqlLiteral::Integer(value) => match i32::try_from(value) {
+                Ok(value) => DefaultMySqlParamType::Int(value),
+                Err(_) => DefaultMySqlParamType::BigInt(value),
+            },
+            SqlLiteral::Decimal(value) => DefaultMySqlParamType::Double(value),
+            SqlLiteral::String(value) => DefaultMySqlParamType::String(value),
+            SqlLiteral::Bool(value) => DefaultMySqlParamType::Bool(value),
+            SqlLiteral::TimePoint(value) => DefaultMySqlParamType::TimePoint(value),
+        }
+    }
+}
+
+impl From<SqlLiteral> for DefaultSqliteParamType {
+    fn from(literal: SqlLiteral) -> Self {
+        match literal {
+            SqlLiteral::Integer(value) => match i32::try_from(value) {
+                Ok(value) => DefaultSqliteParamType::Int(value),
+                Err(_) => DefaultSqliteParamType::BigInt(value),
+            },
+            SqlLiteral::Decimal(value) => DefaultSqliteParamType::Double(value),
+            SqlLiteral::String(value) => DefaultSqliteParamType::String(value),
+            SqlLiteral::Bool(value) => DefaultSqliteParamType::Bool(value),
+            SqlLiteral::TimePoint(value) => DefaultSqliteParamType::TimePoint(value),
+        }
+    }
+}
+
+impl From<SqlLiteral> for DefaultSqlServerParamType {
+    fn from(literal: SqlLiteral) -> Self {
+        match literal {
+            SqlLiteral::Integer(value) => match i32::try_from(value) {
+                Ok(value) => DefaultSqlServerParamType::Int(value),
+                Err(_) => DefaultSqlServerParamType::BigInt(value),
+            },
+            SqlLiteral::Decimal(value) => DefaultSqlServerParamType::Double(value),
+            SqlLiteral::String(value) => DefaultSqlServerParamType::String(value),
+            SqlLiteral::Bool(value) => DefaultSqlServerParamType::Bool(value),
+            SqlLiteral::TimePoint(value) => DefaultSqlServerParamType::TimePoint(value),
+        }
+    }
+}
+
+// `position` is the byte offset in the SQL text
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub message: String,
+    pub position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+// Parses one SELECT statement, a trailing semicolon is allowed
+pub fn parse_select<T: From<SqlLiteral>>(sql: &str) -> Result<SelectStmt<T>, ParseError> {
+    parse_with(sql, false)
+}
+
+// Numbers and DATE / TIMESTAMP literals become Expr::Literal holding their
+// text instead of parameters, so a formatter writes them back unchanged.
+// Strings and booleans are still bound
+pub fn parse_select_keeping_literals<T: From<SqlLiteral>>(
+    sql: &str,
+) -> Result<SelectStmt<T>, ParseError> {
+    parse_with(sql, true)
+}
+
+fn parse_with<T: From<SqlLiteral>>(
+    sql: &str,
+    keep_literals: bool,
+) -> Result<SelectStmt<T>, ParseError> {
+    let mut parser = Parser {
+        tokens: tokenize(sql)?,
+        pos: 0,
+        keep_literals,
+    };
+    let stmt = parser.select()?;
+    parser.eat_symbol(";");
+    if parser.peek().kind != TokenKind::End {
+        return parser.unexpected("end of statement");
+    }
+    Ok(stmt)
+}
+
+impl<T: From<SqlLiteral>> Select<T> {
+    // The dialect only selects how the parsed statement is rendered, so a
+    // query written for Oracle can be parsed and rendered for PostgreSQL
+    pub fn parse(sql: &str, dialect: DatabaseDialect) -> Result<Self, ParseError> {
+        parse_select(sql).map(|stmt| Select::from_statement(stmt, dialect))
+    }
+}
+
+// Keywords that end a select item or table reference instead of aliasing it
+const RESERVED: &[&str] = &[
+    "SELECT",
+    "FROM",
+    "WHERE",
+    "JOIN",
+    "INNER",
+    "LEFT",
+    "RIGHT",
+    "FULL",
+    "CROSS",
+    "NATURAL",
+    "OUTER",
+    "ON",
+    "ORDER",
+    "GROUP",
+    "HAVING",
+    "LIMIT",
+    "OFFSET",
+    "FETCH",
+    "UNION",
+    "INTERSECT",
+    "EXCEPT",
+    "MINUS",
+    "AND",
+    "OR",
+    "NOT",
+    "AS",
+    "IN",
+    "BETWEEN",
+    "LIKE",
+    "ILIKE",
+    "IS",
+    "WITHIN",
+    "OVER",
+    "FILTER",
+    "FOR",
+];
+
+const SYMBOLS: &[&str] = &[
+    "<>", "!=", "<=", ">=", "||", "(", ")", ",", ".", "*", "=", "<", ">", "~", ";", "+", "-", "/",
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    Ident(String),
+    QuotedIdent(String),
+    Number(String),
+    Str(String),
+    Symbol(&'static str),
+    Placeholder(String),
+    End,
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn tokenize(sql: &str) -> Result<Vec<Token>, ParseError> {
+    let bytes = sql.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        let start = i;
+
+        if c.is_ascii_whitespace() {
+            i += 1;
+        } else if sql[i..].starts_with("--") {
+            i = sql[i..].find('\n').map_or(bytes.len(), |n| i + n);
+        } else if sql[i..].starts_with("/*") {
+            i = match sql[i + 2..].find("*/") {
+                Some(n) => i + n + 4,
+                None => return Err(error_at(start, "unterminated comment")),
+            };
+        } else if c == b'\'' || c == b'"' {
+            let (value, end) = quoted(sql, i, c as char)?;
+            i = end;
+            tokens.push(Token {
+                kind: if c == b'\'' {
+                    TokenKind::Str(value)
+                } else {
+                    TokenKind::QuotedIdent(value)
+                },
+                position: start,
+            });
+        } else if c.is_ascii_digit() {
+            while i < bytes.len() && (bytes[i].is_ascii_digit() || bytes[i] == b'.') {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'e' || bytes[i] == b'E') {
+                i += 1;
+                if i < bytes.len() && (bytes[i] == b'+' || bytes[i] == b'-') {
+                    i += 1;
+                }
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+            }
+            tokens.push(Token {
+                kind: TokenKind::Number(sql[start..i].to_string()),
+                position: start,
+            });
+        } else if c == b'_' || sql[i..].chars().next().is_some_and(char::is_alphabetic) {
+            // Only a letter starts an identifier, other characters such as
+            // typographic quotes or a no-break space would give an empty
+            // token and are reported below
+            i = identifier_end(sql, i);
+            tokens.push(Token {
+                kind: TokenKind::Ident(sql[start..i].to_string()),
+                position: start,
+            });
+        } else if c == b'?' || ((c == b'$' || c == b':') && identifier_end(sql, i + 1) > i + 1) {
+            i = if c == b'?' {
+                i + 1
+            } else {
+                identifier_end(sql, i + 1)
+            };
+            tokens.push(Token {
+                kind: TokenKind::Placeholder(sql[start..i].to_string()),
+                position: start,
+            });
+        } else if let Some(symbol) = SYMBOLS.iter().find(|s| sql[i..].starts_with(**s)) {
+            i += symbol.len();
+            tokens.push(Token {
+                kind: TokenKind::Symbol(symbol),
+                position: start,
+            });
+        } else {
+            let ch = sql[i..].chars().next().unwrap_or_default();
+            return Err(error_at(start, format!("unexpected character '{}'", ch)));
+        }
+    }
+
+    tokens.push(Token {
+        kind: TokenKind::End,
+        position: sql.len(),
+    });
+    Ok(tokens)
+}
+
+fn identifier_end(sql: &str, start: usize) -> usize {
+    sql[start..]
+        .char_indices()
+        .find(|(_, c)| !(c.is_alphanumeric() || *c == '_' || *c == '$' || *c == '#'))
+        .map_or(sql.len(), |(n, _)| start + n)
+}
+
+// Reads a quoted string or identifier, a doubled quote is an escaped quote
+fn quoted(sql: &str, start: usize, quote: char) -> Result<(String, usize), ParseError> {
+    let mut value = String::new();
+    let mut chars = sql[start + 1..].char_indices().peekable();
+
+    while let Some((n, c)) = chars.next() {
+        if c == quote {
+            if chars.peek().map(|(_, next)| *next) == Some(quote) {
+                chars.next();
+                value.push(quote);
+                continue;
+            }
+            return Ok((value, start + 1 + n + 1));
+        }
+        value.push(c);
+    }
+
+    Err(error_at(start, "unterminated quoted text"))
+}
+
+fn error_at(position: usize, message: impl Into<String>) -> ParseError {
+    ParseError {
+        message: message.into(),
+        position,
+    }
+}
+
+fn parse_time_point(value: &str) -> Option<SystemTime> {
+    let value = value.trim();
+    let date_time = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| d.and_time(NaiveTime::MIN))
+        })
+        .ok()?;
+    Some(date_time.and_utc().into())
+}
+
+fn same_column<T>(left: &Expr<T>, right: &Expr<T>) -> bool {
+    match (left, right) {
+        (
+            Expr::Column {
+                table_alias: left_alias,
+                name: left_name,
+                ..
+            },
+            Expr::Column {
+                table_alias: right_alias,
+                name: right_name,
+                ..
+            },
+        ) => left_alias == right_alias && left_name == right_name,
+        _ => false,
+    }
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+    keep_literals: bool,
+}
+
+impl Parser {
+    fn peek(&self) -> &Token {
+        self.peek_at(0)
+    }
+
+    fn peek_at(&self, n: usize) -> &Token {
+        &self.tokens[(self.pos + n).min(self.tokens.len() - 1)]
+    }
+
+    fn advance(&mut self) -> Token {
+        let token = self.peek().clone();
+        if token.kind != TokenKind::End {
+            self.pos += 1;
+        }
+        token
+    }
+
+    fn is_keyword_at(&self, n: usize, keyword: &str) -> bool {
+        matches!(&self.peek_at(n).kind, TokenKind::Ident(word) if word.eq_ignore_ascii_case(keyword))
+    }
+
+    fn is_keyword(&self, keyword: &str) -> bool {
+        self.is_keyword_at(0, keyword)
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let found = self.is_keyword(keyword);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<(), ParseError> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.unexpected(keyword)
+        }
+    }
+
+    fn is_symbol_at(&self, n: usize, symbol: &str) -> bool {
+        matches!(self.peek_at(n).kind, TokenKind::Symbol(s) if s == symbol)
+    }
+
+    fn is_symbol(&self, symbol: &str) -> bool {
+        self.is_symbol_at(0, symbol)
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        let found = self.is_symbol(symbol);
+        if found {
+            self.advance();
+        }
+        found
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            self.unexpected(&format!("'{}'", symbol))
+        }
+    }
+
+    fn error<R>(&self, message: impl Into<String>) -> Result<R, ParseError> {
+        Err(error_at(self.peek().position, message))
+    }
+
+    fn unexpected<R>(&self, expected: &str) -> Result<R, ParseError> {
+        let found = match &self.peek().kind {
+            TokenKind::Ident(word) | TokenKind::Number(word) | TokenKind::Placeholder(word) => {
+                word.clone()
+            }
+            TokenKind::QuotedIdent(word) => format!("\"{}\"", word),
+            TokenKind::Str(value) => format!("'{}'", value),
+            TokenKind::Symbol(symbol) => symbol.to_string(),
+            TokenKind::End => "end of input".to_string(),
+        };
+        self.error(format!("expected {}, found {}", expected, found))
+    }
+
+    fn unsupported<R>(&self, construct: &str) -> Result<R, ParseError> {
+        self.error(format!("{} is not supported by the builder", construct))
+    }
+
+    fn select<T: From<SqlLiteral>>(&mut self) -> Result<SelectStmt<T>, ParseError> {
+        self.expect_keyword("SELECT")?;
+        let mut stmt = SelectStmt::new();
+
+        let distinct_position = self.peek().position;
+        let distinct = self.eat_keyword("DISTINCT");
+        loop {
+            stmt.items.push(self.select_item()?);
+            if !self.eat_symbol(",") {
+                break;
+            }
+        }
+        if distinct {
+            if stmt.items.len() != 1 {
+                return Err(error_at(
+                    distinct_position,
+                    "DISTINCT is only supported with a single select item",
+                ));
+            }
+            let item = &mut stmt.items[0];
+            let expr = std::mem::replace(&mut item.expr, Expr::Raw(String::new()));
+            item.expr = expr.aggregate(SqlAggregateFunction::Distinct, AggregateOptions::default());
+        }
+
+        if self.eat_keyword("FROM") {
+            loop {
+                stmt.from.push(self.table_ref()?);
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+            while let Some(join_type) = self.join_type()? {
+                let table = self.table_ref()?;
+                let on = if self.eat_keyword("ON") {
+                    Some(self.predicate()?)
+                } else {
+                    None
+                };
+                stmt.joins.push(Join {
+                    join_type,
+                    table,
+                    on,
+                });
+            }
+        }
+
+        if self.eat_keyword("WHERE") {
+            stmt.selection = Some(self.predicate()?);
+        }
+
+        if self.is_keyword("GROUP") {
+            return self.unsupported("GROUP BY");
+        }
+        if self.is_keyword("HAVING") {
+            return self.unsupported("HAVING");
+        }
+
+        if self.eat_keyword("ORDER") {
+            self.expect_keyword("BY")?;
+            loop {
+                let expr = self.expr(false)?;
+                let sort = if self.eat_keyword("ASC") {
+                    Some(SortType::Ascending)
+                } else if self.eat_keyword("DESC") {
+                    Some(SortType::Descending)
+                } else {
+                    None
+                };
+                if self.is_keyword("NULLS") {
+                    return self.unsupported("NULLS FIRST/LAST");
+                }
+                stmt.order_by.push(OrderByItem { expr, sort });
+                if !self.eat_symbol(",") {
+                    break;
+                }
+            }
+        }
+
+        self.paging(&mut stmt)?;
+        Ok(stmt)
+    }
+
+    fn select_item<T: From<SqlLiteral>>(&mut self) -> Result<SelectItem<T>, ParseError> {
+        let expr = self.expr(false)?;
+        let alias = self.alias()?;
+        Ok(SelectItem { expr, alias })
+    }
+
+    fn alias(&mut self) -> Result<Option<String>, ParseError> {
+        let explicit = self.eat_keyword("AS");
+        match &self.peek().kind {
+            TokenKind::Ident(word) if !RESERVED.iter().any(|r| word.eq_ignore_ascii_case(r)) => {
+                let alias = word.clone();
+                self.advance();
+                Ok(Some(alias))
+            }
+            TokenKind::QuotedIdent(word) => {
+                let alias = format!("\"{}\"", word.replace('"', "\"\""));
+                self.advance();
+                Ok(Some(alias))
+            }
+            _ if explicit => self.unexpected("an alias"),
+            _ => Ok(None),
+        }
+    }
+
+    fn table_ref<T: From<SqlLiteral>>(&mut self) -> Result<TableRef<T>, ParseError> {
+        if self.eat_symbol("(") {
+            let query = self.select()?;
+            self.expect_symbol(")")?;
+            let alias = self.alias()?.unwrap_or_default();
+            return Ok(TableRef::Subquery {
+                query: Box::new(query),
+                alias,
+            });
+        }
+
+        // The bare parts are kept and quoted again for the target dialect
+        let start = self.peek().position;
+        let mut parts = vec![self.identifier()?];
+        while self.eat_symbol(".") {
+            parts.push(self.identifier()?);
+        }
+        let quoted = parts[0].2;
+        if parts.iter().any(|p| p.2 != quoted) {
+            return Err(error_at(
+                start,
+                "quote every part of a qualified table name or none",
+            ));
+        }
+        if quoted && parts.iter().any(|p| p.1.contains('.')) {
+            return Err(error_at(start, "a quoted table name cannot contain '.'"));
+        }
+        let name = parts
+            .iter()
+            .map(|p| p.1.as_str())
+            .collect::<Vec<_>>()
+            .join(".");
+        let alias = self.alias()?;
+        Ok(TableRef::Table {
+            name,
+            alias,
+            quoted,
+        })
+    }
+
+    fn join_type(&mut self) -> Result<Option<SqlJoinType>, ParseError> {
+        let join_type = if self.eat_keyword("JOIN") {
+            return Ok(Some(SqlJoinType::None));
+        } else if self.eat_keyword("INNER") {
+            SqlJoinType::InnerJoin
+        } else if self.eat_keyword("LEFT") {
+            self.eat_keyword("OUTER");
+            SqlJoinType::LeftJoin
+        } else if self.eat_keyword("RIGHT") {
+            self.eat_keyword("OUTER");
+            SqlJoinType::RightJoin
+        } else if self.is_keyword("FULL") || self.is_keyword("CROSS") || self.is_keyword("NATURAL")
+        {
+            let TokenKind::Ident(word) = &self.peek().kind else {
+                unreachable!()
+            };
+            return self.unsupported(&format!("{} JOIN", word.to_ascii_uppercase()));
+        } else {
+            return Ok(None);
+        };
+        self.expect_keyword("JOIN")?;
+        Ok(Some(join_type))
+    }
+
+    // OR of AND chains, parentheses written in the SQL are kept as groups
+    fn predicate<T: From<SqlLiteral>>(&mut self) -> Result<Predicate<T>, ParseError> {
+        let mut predicate = self.conjunction()?;
+        while self.eat_keyword("OR") {
+            predicate = predicate.or(self.conjunction()?);
+        }
+        Ok(predicate)
+    }
+
+    fn conjunction<T: From<SqlLiteral>>(&mut self) -> Result<Predicate<T>, ParseError> {
+        let mut predicate = self.condition()?;
+        while self.eat_keyword("AND") {
+            predicate = predicate.and(self.condition()?);
+        }
+        Ok(predicate)
+    }
+
+    fn condition<T: From<SqlLiteral>>(&mut self) -> Result<Predicate<T>, ParseError> {
+        if self.is_symbol("(") && !self.is_keyword_at(1, "SELECT") {
+            self.advance();
+            let inner = self.predicate()?;
+            self.expect_symbol(")")?;
+            return Ok(Predicate::Group(Box::new(inner)));
+        }
+        if self.is_keyword("NOT") {
+            return self.unsupported("NOT");
+        }
+        if self.is_keyword("EXISTS") {
+            return self.unsupported("EXISTS");
+        }
+
+        // Oracle writes regular expression matches as a function
+        if self.is_keyword("REGEXP_LIKE") && self.is_symbol_at(1, "(") {
+            self.advance();
+            self.advance();
+            let left = self.expr(true)?;
+            self.expect_symbol(",")?;
+            let pattern = self.expr(true)?;
+            if self.is_symbol(",") {
+                return self.unsupported("REGEXP_LIKE match parameter");
+            }
+            self.expect_symbol(")")?;
+            return Ok(Predicate::Compare {
+                left,
+                op: SqlOperator::Regexp,
+                right: vec![pattern],
+            });
+        }
+
+        let left = self.expr(true)?;
+        let op = self.comparison_operator()?;
+        let right = match op {
+            SqlOperator::Between => {
+                let low = self.expr(true)?;
+                self.expect_keyword("AND")?;
+                vec![low, self.expr(true)?]
+            }
+            SqlOperator::In => {
+                self.expect_symbol("(")?;
+                let right = if self.is_keyword("SELECT") {
+                    vec![Expr::Subquery(Box::new(self.select()?))]
+                } else {
+                    let mut values = vec![self.expr(true)?];
+                    while self.eat_symbol(",") {
+                        values.push(self.expr(true)?);
+                    }
+                    values
+                };
+                self.expect_symbol(")")?;
+                right
+            }
+            _ => vec![self.expr(true)?],
+        };
+
+        Ok(Predicate::Compare { left, op, right })
+    }
+
+    fn comparison_operator(&mut self) -> Result<SqlOperator, ParseError> {
+        let op = match &self.peek().kind {
+            TokenKind::Symbol("=") => SqlOperator::Equal,
+            TokenKind::Symbol("<>") | TokenKind::Symbol("!=") => SqlOperator::NotEqual,
+            TokenKind::Symbol("<") => SqlOperator::Less,
+            TokenKind::Symbol("<=") => SqlOperator::LessOrEqual,
+            TokenKind::Symbol(">") => SqlOperator::Greater,
+            TokenKind::Symbol(">=") => SqlOperator::GreaterOrEqual,
+            TokenKind::Symbol("~") => SqlOperator::Regexp,
+            TokenKind::Ident(word) => match word.to_ascii_uppercase().as_str() {
+                "LIKE" => SqlOperator::Like,
+                "ILIKE" => SqlOperator::ILike,
+                "IN" => SqlOperator::In,
+                "BETWEEN" => SqlOperator::Between,
+                "NOT" => return self.unsupported("NOT"),
+                "IS" => return self.unsupported("IS [NOT] NULL"),
+                _ => return self.unexpected("a comparison operator"),
+            },
+            _ => return self.unexpected("a comparison operator"),
+        };
+        self.advance();
+        Ok(op)
+    }
+
+    // `bind_literals` turns literals into parameters, otherwise they are kept
+    // as SQL text
+    fn expr<T: From<SqlLiteral>>(&mut self, bind_literals: bool) -> Result<Expr<T>, ParseError> {
+        let expr = self.primary(bind_literals)?;
+        if let TokenKind::Symbol(op @ ("+" | "-" | "*" | "/" | "||")) = self.peek().kind {
+            return self.unsupported(&format!("arithmetic operator {}", op));
+        }
+        Ok(expr)
+    }
+
+    fn primary<T: From<SqlLiteral>>(&mut self, bind_literals: bool) -> Result<Expr<T>, ParseError> {
+        let token = self.peek().clone();
+        match token.kind {
+            TokenKind::Symbol("(") if self.is_keyword_at(1, "SELECT") => {
+                self.advance();
+                let query = self.select()?;
+                self.expect_symbol(")")?;
+                Ok(Expr::Subquery(Box::new(query)))
+            }
+            TokenKind::Symbol("*") => {
+                self.advance();
+                Ok(Expr::Wildcard { table_alias: None })
+            }
+            TokenKind::Symbol("-") if matches!(self.peek_at(1).kind, TokenKind::Number(_)) => {
+                self.advance();
+                let TokenKind::Number(text) = self.advance().kind else {
+                    unreachable!()
+                };
+                self.number(&format!("-{}", text), token.position, bind_literals)
+            }
+            TokenKind::Number(text) => {
+                self.advance();
+                self.number(&text, token.position, bind_literals)
+            }
+            TokenKind::Str(value) => {
+                self.advance();
+                let sql = format!("'{}'", value.replace('\'', "''"));
+                Ok(literal(SqlLiteral::String(value), sql, bind_literals))
+            }
+            TokenKind::Placeholder(name)
+                if name.starts_with(':') && !name[1..].chars().all(|c| c.is_ascii_digit()) =>
+            {
+                self.advance();
+                Ok(Expr::named(&name))
+            }
+            TokenKind::Placeholder(name) => {
+                self.error(format!("bind placeholder {} has no value to map", name))
+            }
+            TokenKind::Ident(word) => match word.to_ascii_uppercase().as_str() {
+                "TRUE" | "FALSE" => {
+                    self.advance();
+                    let value = word.eq_ignore_ascii_case("TRUE");
+                    Ok(literal(SqlLiteral::Bool(value), word, bind_literals))
+                }
+                "NULL" => {
+                    self.advance();
+                    Ok(Expr::Raw("NULL".to_string()))
+                }
+                "DATE" | "TIMESTAMP" if matches!(self.peek_at(1).kind, TokenKind::Str(_)) => {
+                    self.advance();
+                    let value_token = self.advance();
+                    let TokenKind::Str(value) = value_token.kind else {
+                        unreachable!()
+                    };
+                    if self.keep_literals {
+                        let sql = format!("{} '{}'", word, value.replace('\'', "''"));
+                        return Ok(Expr::Literal(sql));
+                    }
+                    let Some(time_point) = parse_time_point(&value) else {
+                        return Err(error_at(
+                            value_token.position,
+                            format!("invalid {} literal '{}'", word.to_ascii_uppercase(), value),
+                        ));
+                    };
+                    let sql = format!("{} '{}'", word.to_ascii_uppercase(), value);
+                    Ok(literal(
+                        SqlLiteral::TimePoint(time_point),
+                        sql,
+                        bind_literals,
+                    ))
+                }
+                "CASE" => self.unsupported("CASE"),
+                _ => self.reference(bind_literals),
+            },
+            TokenKind::QuotedIdent(_) => self.reference(bind_literals),
+            _ => self.unexpected("an expression"),
+        }
+    }
+
+    fn number<T: From<SqlLiteral>>(
+        &self,
+        text: &str,
+        position: usize,
+        bind_literals: bool,
+    ) -> Result<Expr<T>, ParseError> {
+        if self.keep_literals {
+            return Ok(Expr::Literal(text.to_string()));
+        }
+        let value = if text.contains(['.', 'e', 'E']) {
+            text.parse().ok().map(SqlLiteral::Decimal)
+        } else {
+            text.parse().ok().map(SqlLiteral::Integer)
+        };
+        match value {
+            Some(value) => Ok(literal(value, text.to_string(), bind_literals)),
+            None => Err(error_at(position, format!("invalid number {}", text))),
+        }
+    }
+
+    // Identifier as written in the SQL, its bare name and whether it was quoted
+    fn identifier(&mut self) -> Result<(String, String, bool), ParseError> {
+        let identifier = match &self.peek().kind {
+            TokenKind::Ident(word) if !RESERVED.iter().any(|r| word.eq_ignore_ascii_case(r)) => {
+                (word.clone(), word.clone(), false)
+            }
+            TokenKind::QuotedIdent(word) => (
+                format!("\"{}\"", word.replace('"', "\"\"")),
+                word.clone(),
+                true,
+            ),
+            _ => return self.unexpected("an identifier"),
+        };
+        self.advance();
+        Ok(identifier)
+    }
+
+    // Column reference such as u.id or u.*, or a function call
+    fn reference<T: From<SqlLiteral>>(
+        &mut self,
+        bind_literals: bool,
+    ) -> Result<Expr<T>, ParseError> {
+        let mut parts = vec![self.identifier()?];
+        while self.eat_symbol(".") {
+            if self.eat_symbol("*") {
+                let table_alias = parts
+                    .iter()
+                    .map(|p| p.0.as_str())
+                    .collect::<Vec<_>>()
+                    .join(".");
+                return Ok(Expr::Wildcard {
+                    table_alias: Some(table_alias),
+                });
+            }
+            parts.push(self.identifier()?);
+        }
+
+        if self.is_symbol("(") {
+            let name = parts
+                .iter()
+                .map(|p| p.0.as_str())
+                .collect::<Vec<_>>()
+                .join(".");
+            return self.function_call(name, bind_literals);
+        }
+
+        let (_, name, quoted) = parts.pop().unwrap_or_default();
+        let table_alias = (!parts.is_empty()).then(|| {
+            parts
+                .iter()
+                .map(|p| p.0.as_str())
+                .collect::<Vec<_>>()
+                .join(".")
+        });
+        Ok(Expr::Column {
+            table_alias,
+            name,
+            quoted,
+        })
+    }
+
+    fn function_call<T: From<SqlLiteral>>(
+        &mut self,
+        name: String,
+        bind_literals: bool,
+    ) -> Result<Expr<T>, ParseError> {
+        self.expect_symbol("(")?;
+        let function = match name.to_ascii_uppercase().as_str() {
+            "COUNT" => SqlAggregateFunction::Count,
+            "SUM" => SqlAggregateFunction::Sum,
+            "AVG" => SqlAggregateFunction::Avg,
+            "MIN" => SqlAggregateFunction::Min,
+            "MAX" => SqlAggregateFunction::Max,
+            "STRING_AGG" | "LISTAGG" => SqlAggregateFunction::StringAgg,
+            "ARRAY_AGG" => SqlAggregateFunction::ArrayAgg,
+            _ => SqlAggregateFunction::None,
+        };
+
+        let expr = if function == SqlAggregateFunction::None {
+            let mut args = Vec::new();
+            if !self.is_symbol(")") {
+                loop {
+                    args.push(self.expr(bind_literals)?);
+                    if !self.eat_symbol(",") {
+                        break;
+                    }
+                }
+            }
+            self.expect_symbol(")")?;
+            Expr::Function { name, args }
+        } else {
+            self.aggregate_call(function, bind_literals)?
+        };
+
+        if self.is_keyword("OVER") {
+            return self.unsupported("window function");
+        }
+        if self.is_keyword("FILTER") {
+            return self.unsupported("aggregate FILTER");
+        }
+        Ok(expr)
+    }
+
+    fn aggregate_call<T: From<SqlLiteral>>(
+        &mut self,
+        mut function: SqlAggregateFunction,
+        bind_literals: bool,
+    ) -> Result<Expr<T>, ParseError> {
+        if self.is_keyword("DISTINCT") {
+            if function != SqlAggregateFunction::Count {
+                return self.unsupported("DISTINCT outside COUNT");
+            }
+            self.advance();
+            function = SqlAggregateFunction::CountDistinct;
+        }
+
+        let arg = if function == SqlAggregateFunction::Count && self.eat_symbol("*") {
+            Expr::Wildcard { table_alias: None }
+        } else {
+            self.expr(bind_literals)?
+        };
+
+        let mut options = AggregateOptions::default();
+        if function == SqlAggregateFunction::StringAgg && self.eat_symbol(",") {
+            let TokenKind::Str(separator) = self.peek().kind.clone() else {
+                return self.unexpected("a separator string");
+            };
+            self.advance();
+            options = options.separator(&separator);
+        }
+        self.expect_symbol(")")?;
+
+        // The renderer orders LISTAGG by the aggregated expression, any other
+        // ordering cannot be represented
+        if function == SqlAggregateFunction::StringAgg && self.is_keyword("WITHIN") {
+            self.advance();
+            self.expect_keyword("GROUP")?;
+            self.expect_symbol("(")?;
+            self.expect_keyword("ORDER")?;
+            self.expect_keyword("BY")?;
+            let position = self.peek().position;
+            let order = self.expr::<T>(bind_literals)?;
+            if !same_column(&order, &arg) {
+                return Err(error_at(
+                    position,
+                    "WITHIN GROUP must order by the aggregated column",
+                ));
+            }
+            self.expect_symbol(")")?;
+        }
+
+        Ok(Expr::Aggregate {
+            function,
+            arg: Box::new(arg),
+            options,
+        })
+    }
+
+    // PostgreSQL LIMIT/OFFSET and Oracle OFFSET ... ROWS FETCH ... ROWS ONLY
+    fn paging<T>(&mut self, stmt: &mut SelectStmt<T>) -> Result<(), ParseError> {
+        loop {
+            if self.eat_keyword("LIMIT") {
+                stmt.limit = Some(self.row_count()?);
+            } else if self.eat_keyword("OFFSET") {
+                stmt.offset = Some(self.row_count()?);
+                if !self.eat_keyword("ROWS") {
+                    self.eat_keyword("ROW");
+                }
+            } else if self.eat_keyword("FETCH") {
+                if !self.eat_keyword("FIRST") {
+                    self.expect_keyword("NEXT")?;
+                }
+                stmt.limit = Some(self.row_count()?);
+                if !self.eat_keyword("ROWS") {
+                    self.expect_keyword("ROW")?;
+                }
+                self.expect_keyword("ONLY")?;
+            } else {
+                return Ok(());
+            }
+        }
+    }
+
+    fn row_count(&mut self) -> Result<u64, ParseError> {
+        let count = match &self.peek().kind {
+            TokenKind::Number(text) => text.parse().ok(),
+            _ => None,
+        };
+        match count {
+            Some(count) => {
+                self.advance();
+                Ok(count)
+            }
+            None => self.unexpected("a row count"),
+        }
+    }
+}
+
+fn literal<T: From<SqlLiteral>>(literal: SqlLiteral, sql: String, bind: bool) -> Expr<T> {
+    if bind {
+        Expr::Param(T::from(literal))
+    } else {
+        Expr::Raw(sql)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::Renderer;
+    use std::time::Duration;
+
+    #[test]
+    fn test_parse_postgres_select_binds_literals() {
+        let select = Select::<DefaultPostgresParamType>::parse(
+            "SELECT u.id, u.name AS user_name, UPPER(u.email) \
+             FROM users u \
+             LEFT OUTER JOIN orders AS o ON o.user_id = u.id AND o.status = 'paid' \
+             WHERE u.active = TRUE \
+               AND (u.name ILIKE 'a%' OR u.age BETWEEN 18 AND 65) \
+               AND u.id IN (SELECT user_id FROM admins WHERE level >= 2) \
+               AND u.created_at > TIMESTAMP '2024-01-01 00:00:00' \
+             ORDER BY u.name DESC \
+             LIMIT 10 OFFSET 20;",
+            DatabaseDialect::PostgreSQL,
+        )
+        .unwrap();
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT u.id, u.name AS user_name, UPPER(u.email) \
+             FROM users AS u LEFT JOIN orders AS o ON o.user_id = u.id AND o.status = $1 \
+             WHERE u.active = $2 AND (u.name ILIKE $3 OR u.age BETWEEN $4 AND $5) \
+             AND u.id IN (SELECT user_id FROM admins WHERE level >= $6) \
+             AND u.created_at > $7 \
+             ORDER BY u.name DESC LIMIT 10 OFFSET 20"
+        );
+        assert_eq!(
+            select.values(),
+            vec![
+                &DefaultPostgresParamType::String("paid".to_string()),
+                &DefaultPostgresParamType::Bool(true),
+                &DefaultPostgresParamType::String("a%".to_string()),
+                &DefaultPostgresParamType::Int(18),
+                &DefaultPostgresParamType::Int(65),
+                &DefaultPostgresParamType::Int(2),
+                &DefaultPostgresParamType::TimePoint(
+                    SystemTime::UNIX_EPOCH + Duration::from_secs(1_704_067_200)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_oracle_select_and_convert_to_postgres() {
+        let sql = "SELECT e.name employee, d.name AS department \
+                   FROM emp e JOIN dept d ON d.id = e.dept_id \
+                   WHERE REGEXP_LIKE(e.code, '^A') AND e.hired >= DATE '2020-01-31' \
+                   ORDER BY e.name \
+                   OFFSET 5 ROWS FETCH NEXT 10 ROWS ONLY";
+
+        let oracle = Select::<DefaultOracleParamType>::parse(sql, DatabaseDialect::Oracle).unwrap();
+        assert_eq!(
+            oracle.generate_query(false),
+            "SELECT e.name AS employee, d.name AS department \
+             FROM emp e JOIN dept d ON d.id = e.dept_id \
+             WHERE REGEXP_LIKE(e.code, :1) AND e.hired >= :2 \
+             ORDER BY e.name OFFSET 5 ROWS FETCH NEXT 10 ROWS ONLY"
+        );
+
+        let postgres =
+            Select::<DefaultPostgresParamType>::parse(sql, DatabaseDialect::PostgreSQL).unwrap();
+        assert_eq!(
+            postgres.generate_query(false),
+            "SELECT e.name AS employee, d.name AS department \
+             FROM emp AS e JOIN dept AS d ON d.id = e.dept_id \
+             WHERE e.code ~ $1 AND e.hired >= $2 \
+             ORDER BY e.name LIMIT 10 OFFSET 5"
+        );
+
+        let names = Select::<DefaultPostgresParamType>::parse(
+            "SELECT LISTAGG(e.name, '; ') WITHIN GROUP (ORDER BY e.name) AS names FROM emp e",
+            DatabaseDialect::PostgreSQL,
+        )
+        .unwrap();
+        assert_eq!(
+            names.generate_query(false),
+            "SELECT STRING_AGG(e.name, '; ') AS names FROM emp AS e"
+        );
+    }
+
+    #[test]
+    fn test_parse_reports_unsupported_sql() {
+        let sql = "SELECT dept, COUNT(*) FROM emp GROUP BY dept";
+        let error = parse_select::<DefaultPostgresParamType>(sql).unwrap_err();
+        assert_eq!(error.message, "GROUP BY is not supported by the builder");
+        assert_eq!(error.position, sql.find("GROUP").unwrap());
+
+        let error = parse_select::<DefaultPostgresParamType>("SELECT * FROM emp WHERE id = $1")
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "bind placeholder $1 has no value to map at position 29"
+        );
+    }
+
+    #[test]
+    fn test_parse_wildcards_and_quoted_tables() {
+        let stmt = parse_select::<DefaultPostgresParamType>(
+            "SELECT u.*, COUNT(*) AS total FROM \"Sales\".\"Users\" u",
+        )
+        .unwrap();
+        assert_eq!(
+            stmt.items[0].expr,
+            Expr::Wildcard {
+                table_alias: Some("u".to_string())
+            }
+        );
+        assert!(matches!(
+            &stmt.items[1].expr,
+            Expr::Aggregate { arg, .. } if **arg == Expr::Wildcard { table_alias: None }
+        ));
+
+        for (dialect, table) in [
+            (DatabaseDialect::PostgreSQL, "\"Sales\".\"Users\" AS u"),
+            (DatabaseDialect::MySQL, "`Sales`.`Users` AS u"),
+            (DatabaseDialect::SqlServer, "[Sales].[Users] AS u"),
+        ] {
+            assert_eq!(
+                Renderer::new(dialect).render(&stmt).sql,
+                format!("SELECT u.*, COUNT(*) AS total FROM {}", table)
+            );
+        }
+
+        let error =
+            parse_select::<DefaultPostgresParamType>("SELECT id FROM sales.\"Users\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "quote every part of a qualified table name or none at position 15"
+        );
+    }
+
+    #[test]
+    fn test_tokenize_rejects_non_letter_characters() {
+        for (sql, ch) in [
+            ("SELECT * FROM emp WHERE name = ’x’", '’'),
+            ("SELECT id\u{a0}FROM emp", '\u{a0}'),
+            ("SELECT \u{1f600} FROM emp", '\u{1f600}'),
+        ] {
+            let error = parse_select::<DefaultPostgresParamType>(sql).unwrap_err();
+            assert_eq!(error.message, format!("unexpected character '{}'", ch));
+            assert_eq!(error.position, sql.find(ch).unwrap());
+        }
+
+        let stmt = parse_select::<DefaultPostgresParamType>("SELECT café FROM emp").unwrap();
+        assert_eq!(
+            Renderer::new(DatabaseDialect::PostgreSQL).render(&stmt).sql,
+            "SELECT café FROM emp"
+        );
+    }
+}
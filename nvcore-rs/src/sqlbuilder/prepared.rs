@@ -0,0 +1,302 @@
+// Prepared query templates
+//
+// A PreparedQuery renders its builder once and keeps the SQL text, so the same
+// query shape can be executed many times with new values through a binder.
+// The fingerprint identifies the shape (dialect and SQL with placeholders,
+// never the values) and is stable across processes and builds, so it can key
+// a cache of server-side prepared statements.
+//
+// Values often come from request data, so a binder never panics: the first
+// unknown slot or parameter name is recorded and reported by `finish`.
+
+use crate::sqlbuilder::{
+    BindError, DatabaseDialect, NvSelect, ParameterSlot, Renderer, Select, SelectStmt,
+};
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PreparedSlot<T> {
+    // Value written into the builder, used unless the binder replaces it
+    Value(T),
+    Named(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct PreparedQuery<T> {
+    sql: String,
+    dialect: DatabaseDialect,
+    slots: Vec<PreparedSlot<T>>,
+    fingerprint: u64,
+}
+
+impl<T: Clone> PreparedQuery<T> {
+    pub fn new(stmt: &SelectStmt<T>, dialect: DatabaseDialect) -> Self {
+        let rendered = Renderer::new(dialect).render(stmt);
+        let slots = rendered
+            .slots
+            .into_iter()
+            .map(|slot| match slot {
+                ParameterSlot::Value(value) => PreparedSlot::Value(value.clone()),
+                ParameterSlot::Named(name) => PreparedSlot::Named(name),
+            })
+            .collect();
+        let fingerprint = query_fingerprint(&dialect, &rendered.sql);
+
+        Self {
+            sql: rendered.sql,
+            dialect,
+            slots,
+            fingerprint,
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn dialect(&self) -> DatabaseDialect {
+        self.dialect
+    }
+
+    // Slot i is bound to the i-th bind position of the SQL
+    pub fn slots(&self) -> &[PreparedSlot<T>] {
+        &self.slots
+    }
+
+    pub fn fingerprint(&self) -> u64 {
+        self.fingerprint
+    }
+
+    // Binder seeded with the values written into the builder
+    pub fn binder(&self) -> QueryBinder<'_, T> {
+        QueryBinder {
+            query: self,
+            values: self
+                .slots
+                .iter()
+                .map(|slot| match slot {
+                    PreparedSlot::Value(value) => Some(value.clone()),
+                    PreparedSlot::Named(_) => None,
+                })
+                .collect(),
+            error: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BinderError {
+    SlotOutOfRange { slot: usize, slots: usize },
+    UnknownParameter(String),
+    Unbound(BindError),
+}
+
+impl fmt::Display for BinderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BinderError::SlotOutOfRange { slot, slots } => write!(
+                f,
+                "slot {} is out of range, the query has {} slots",
+                slot, slots
+            ),
+            BinderError::UnknownParameter(name) => {
+                write!(f, "the query has no parameter named :{}", name)
+            }
+            BinderError::Unbound(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for BinderError {}
+
+impl From<BindError> for BinderError {
+    fn from(error: BindError) -> Self {
+        BinderError::Unbound(error)
+    }
+}
+
+pub struct QueryBinder<'p, T> {
+    query: &'p PreparedQuery<T>,
+    values: Vec<Option<T>>,
+    error: Option<BinderError>,
+}
+
+impl<T: Clone> QueryBinder<'_, T> {
+    pub fn set(mut self, slot: usize, value: T) -> Self {
+        match self.values.get_mut(slot) {
+            Some(bound) => *bound = Some(value),
+            None => self.fail(BinderError::SlotOutOfRange {
+                slot,
+                slots: self.query.slots.len(),
+            }),
+        }
+        self
+    }
+
+    // Binds every slot of the named parameter
+    pub fn set_named(mut self, name: &str, value: T) -> Self {
+        let name = name.trim_start_matches(':');
+        let mut found = false;
+        for (slot, bound) in self.query.slots.iter().zip(self.values.iter_mut()) {
+            if matches!(slot, PreparedSlot::Named(n) if n == name) {
+                *bound = Some(value.clone());
+                found = true;
+            }
+        }
+        if !found {
+            self.fail(BinderError::UnknownParameter(name.to_string()));
+        }
+        self
+    }
+
+    // Values in bind order, fails with the first bad set or set_named call,
+    // then on a named parameter left unbound
+    pub fn finish(self) -> Result<Vec<T>, BinderError> {
+        if let Some(error) = self.error {
+            return Err(error);
+        }
+        self.query
+            .slots
+            .iter()
+            .zip(self.values)
+            .map(|(slot, value)| match (slot, value) {
+                (_, Some(value)) => Ok(value),
+                (PreparedSlot::Named(name), None) => Err(BindError { name: name.clone() }.into()),
+                (PreparedSlot::Value(value), None) => Ok(value.clone()),
+            })
+            .collect()
+    }
+
+    fn fail(&mut self, error: BinderError) {
+        self.error.get_or_insert(error);
+    }
+}
+
+impl<T: Clone> Select<T> {
+    pub fn prepare(&self) -> PreparedQuery<T> {
+        PreparedQuery::new(self.statement(), self.dialect())
+    }
+}
+
+impl<T: Clone> NvSelect<T> {
+    pub fn prepare(&self) -> PreparedQuery<T> {
+        self.to_select().prepare()
+    }
+}
+
+// 64-bit FNV-1a over the dialect name and the SQL text
+fn query_fingerprint(dialect: &DatabaseDialect, sql: &str) -> u64 {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    dialect
+        .to_string()
+        .bytes()
+        .chain(std::iter::once(0))
+        .chain(sql.bytes())
+        .fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{DefaultPostgresParamType, SqlAggregateFunction, SqlOperator};
+
+    type Param = DefaultPostgresParamType;
+
+    fn build_orders(statuses: Vec<Param>) -> Select<Param> {
+        Select::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("o"), None, SqlAggregateFunction::None, false)
+            .from_table("orders", Some("o"))
+            .where_clause(|w| {
+                w.add_named_condition("o.tenant_id", SqlOperator::Equal, "tenant_id")
+                    .unwrap()
+                    .and()
+                    .add_condition_in("o.status", statuses)
+                    .and()
+                    .add_condition("o.total", SqlOperator::Greater, Param::Int(10))
+                    .unwrap()
+            })
+    }
+
+    #[test]
+    fn test_prepared_query_rebinds_slots() {
+        let prepared = build_orders(vec![Param::String("paid".to_string())]).prepare();
+        assert_eq!(
+            prepared.sql(),
+            "SELECT o.id FROM orders AS o WHERE o.tenant_id = $1 AND o.status IN ($2) AND o.total > $3"
+        );
+
+        let values = prepared
+            .binder()
+            .set_named(":tenant_id", Param::Int(7))
+            .set(2, Param::Int(500))
+            .finish()
+            .unwrap();
+        assert_eq!(
+            values,
+            vec![
+                Param::Int(7),
+                Param::String("paid".to_string()),
+                Param::Int(500)
+            ]
+        );
+
+        let error = prepared.binder().finish().unwrap_err();
+        assert_eq!(
+            error,
+            BinderError::Unbound(BindError {
+                name: "tenant_id".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_binder_reports_unknown_slots() {
+        let prepared = build_orders(vec![Param::String("paid".to_string())]).prepare();
+
+        let error = prepared
+            .binder()
+            .set(3, Param::Int(1))
+            .set_named("tenant", Param::Int(7))
+            .finish()
+            .unwrap_err();
+        assert_eq!(error, BinderError::SlotOutOfRange { slot: 3, slots: 3 });
+
+        let error = prepared
+            .binder()
+            .set_named("tenant", Param::Int(7))
+            .finish()
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "the query has no parameter named :tenant"
+        );
+    }
+
+    #[test]
+    fn test_fingerprint_follows_query_shape() {
+        let paid = build_orders(vec![Param::String("paid".to_string())]).prepare();
+        let open = build_orders(vec![Param::String("open".to_string())]).prepare();
+        let both = build_orders(vec![
+            Param::String("paid".to_string()),
+            Param::String("open".to_string()),
+        ])
+        .prepare();
+
+        assert_eq!(paid.fingerprint(), open.fingerprint());
+        assert_ne!(paid.fingerprint(), both.fingerprint());
+        let statement = build_orders(vec![Param::String("paid".to_string())]).build();
+        assert_ne!(
+            paid.fingerprint(),
+            PreparedQuery::new(&statement, DatabaseDialect::Oracle).fingerprint()
+        );
+        // Stable across builds, it may key a persistent statement cache
+        assert_eq!(
+            query_fingerprint(&DatabaseDialect::PostgreSQL, ""),
+            0x5c31_22f3_4cb3_08a9
+        );
+    }
+}
@@ -0,0 +1,822 @@
+// Renders a SelectStmt into SQL text for one dialect. Bound values are
+// collected while the placeholders are emitted, so the parameter list is
+// always in placeholder order. Named parameters are left as slots that are
+// filled with RenderedQuery::bind when the query is executed.
+
+use crate::sqlbuilder::{
+    build_function_call, check_operand_count, determine_parameter_format, generate_aggregate_call,
+    generate_limit_offset, generate_table_alias, generate_top_clause, join_type_to_string,
+    paging_requires_order_by, quote_identifier, walk_predicate, walk_select, DatabaseDialect, Expr,
+    FormatOptions, ItemLayout, Join, OrderByItem, Predicate, SelectItem, SelectStmt, SortType,
+    SqlOperator, TableRef, ToSqlLiteral, Visitor,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+// Source of the value of one bind position
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParameterSlot<'a, T> {
+    Value(&'a T),
+    Named(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindError {
+    pub name: String,
+}
+
+impl fmt::Display for BindError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "no value bound for parameter :{}", self.name)
+    }
+}
+
+impl std::error::Error for BindError {}
+
+// A statement the dialect cannot express, returned by Renderer::validate for
+// trees that were not built through the fluent API, such as loaded SavedQuery
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderError {
+    OffsetWithoutOrderBy {
+        dialect: DatabaseDialect,
+    },
+    // Right operands of a comparison, BETWEEN takes two and the other
+    // operators but IN one
+    OperandCount {
+        op: SqlOperator,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for RenderError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RenderError::OffsetWithoutOrderBy { dialect } => {
+                write!(f, "{} requires ORDER BY when OFFSET is used", dialect)
+            }
+            RenderError::OperandCount {
+                op,
+                expected,
+                found,
+            } => write!(
+                f,
+                "{} takes {} right {}, found {}",
+                op,
+                expected,
+                if *expected == 1 {
+                    "operand"
+                } else {
+                    "operands"
+                },
+                found
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RenderError {}
+
+// Finds the first select of the tree, subqueries included, the dialect cannot
+// render
+struct RenderCheck {
+    dialect: DatabaseDialect,
+    error: Option<RenderError>,
+}
+
+impl<T> Visitor<T> for RenderCheck {
+    fn visit_select(&mut self, select: &SelectStmt<T>) {
+        if self.error.is_some() {
+            return;
+        }
+        if select.order_by.is_empty() && paging_requires_order_by(&self.dialect, select.offset) {
+            self.error = Some(RenderError::OffsetWithoutOrderBy {
+                dialect: self.dialect,
+            });
+            return;
+        }
+        walk_select(self, select);
+    }
+
+    fn visit_predicate(&mut self, predicate: &Predicate<T>) {
+        if self.error.is_some() {
+            return;
+        }
+        if let Predicate::Compare { op, right, .. } = predicate {
+            if let Err(error) = check_operand_count(*op, right.len()) {
+                self.error = Some(error);
+                return;
+            }
+        }
+        walk_predicate(self, predicate);
+    }
+}
+
+// `parameters` holds the values written into the builder in placeholder order,
+// `slots` every bind position including the named ones. A name repeated in the
+// query takes one slot on dialects that reuse placeholders or bind by name,
+// and one slot per occurrence otherwise.
+#[derive(Debug)]
+pub struct RenderedQuery<'a, T> {
+    pub sql: String,
+    pub parameters: Vec<&'a T>,
+    pub slots: Vec<ParameterSlot<'a, T>>,
+}
+
+impl<'a, T> RenderedQuery<'a, T> {
+    // Distinct parameter names in order of first appearance
+    pub fn parameter_names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = Vec::new();
+        for slot in &self.slots {
+            if let ParameterSlot::Named(name) = slot {
+                if !names.contains(&name.as_str()) {
+                    names.push(name);
+                }
+            }
+        }
+        names
+    }
+
+    // Values of every bind position, named slots are looked up in `values`
+    pub fn bind<'v>(&'v self, values: &'v HashMap<String, T>) -> Result<Vec<&'v T>, BindError> {
+        self.slots
+            .iter()
+            .map(|slot| match slot {
+                ParameterSlot::Value(value) => Ok(*value),
+                ParameterSlot::Named(name) => values
+                    .get(name)
+                    .ok_or_else(|| BindError { name: name.clone() }),
+            })
+            .collect()
+    }
+
+    // Bind name and value of every slot for drivers that bind by name, values
+    // written into the builder are named after their position
+    pub fn bind_named<'v>(
+        &'v self,
+        values: &'v HashMap<String, T>,
+    ) -> Result<Vec<(String, &'v T)>, BindError> {
+        let bound = self.bind(values)?;
+        Ok(self
+            .slots
+            .iter()
+            .zip(bound)
+            .enumerate()
+            .map(|(i, (slot, value))| match slot {
+                ParameterSlot::Value(_) => ((i + 1).to_string(), value),
+                ParameterSlot::Named(name) => (name.clone(), value),
+            })
+            .collect())
+    }
+}
+
+pub const DEBUG_SQL_HEADER: &str =
+    "/* nvcore debug SQL, values inlined for logging, do not execute */ ";
+
+pub(crate) const REDACTED_LITERAL: &str = "'***'";
+
+// Options of the inline-literal debug rendering, values compared against a
+// redacted column are printed as '***'. A column matches by name or by
+// alias.name, ignoring case
+#[derive(Debug, Clone, Default)]
+pub struct DebugSqlOptions {
+    redacted_columns: Vec<String>,
+}
+
+impl DebugSqlOptions {
+    pub fn redact(mut self, column: &str) -> Self {
+        self.redacted_columns.push(column.to_string());
+        self
+    }
+
+    pub fn is_redacted(&self, column: &str) -> bool {
+        is_redacted(&self.redacted_columns, column)
+    }
+}
+
+fn is_redacted(redacted_columns: &[String], column: &str) -> bool {
+    let name = column.rsplit('.').next().unwrap_or(column);
+    redacted_columns
+        .iter()
+        .any(|c| c.eq_ignore_ascii_case(column) || c.eq_ignore_ascii_case(name))
+}
+
+// Bound values in placeholder order, `inline` renders them as literals instead
+struct Bindings<'a, 'f, T> {
+    values: Vec<&'a T>,
+    slots: Vec<ParameterSlot<'a, T>>,
+    inline: Option<&'f dyn Fn(&T) -> String>,
+    redacted_columns: &'f [String],
+    redacting: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Renderer {
+    dialect: DatabaseDialect,
+    format: FormatOptions,
+}
+
+impl Renderer {
+    pub fn new(dialect: DatabaseDialect) -> Self {
+        Self {
+            dialect,
+            format: FormatOptions::compact(),
+        }
+    }
+
+    pub fn pretty_print(self, pretty_print: bool) -> Self {
+        self.format(FormatOptions::from_pretty_print(pretty_print))
+    }
+
+    pub fn format(mut self, format: FormatOptions) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn dialect(&self) -> DatabaseDialect {
+        self.dialect
+    }
+
+    // Checks the whole tree up front, `render` panics on the same statements
+    pub fn validate<T>(&self, stmt: &SelectStmt<T>) -> Result<(), RenderError> {
+        let mut check = RenderCheck {
+            dialect: self.dialect,
+            error: None,
+        };
+        stmt.accept(&mut check);
+        check.error.map_or(Ok(()), Err)
+    }
+
+    pub fn try_render<'a, T>(
+        &self,
+        stmt: &'a SelectStmt<T>,
+    ) -> Result<RenderedQuery<'a, T>, RenderError> {
+        self.validate(stmt)?;
+        Ok(self.render(stmt))
+    }
+
+    pub fn render<'a, T>(&self, stmt: &'a SelectStmt<T>) -> RenderedQuery<'a, T> {
+        let mut bindings = Bindings {
+            values: Vec::new(),
+            slots: Vec::new(),
+            inline: None,
+            redacted_columns: &[],
+            redacting: false,
+        };
+        let sql = self.select(stmt, 0, &mut bindings);
+        RenderedQuery {
+            sql,
+            parameters: bindings.values,
+            slots: bindings.slots,
+        }
+    }
+
+    // Renders the values as dialect literals in place of the placeholders,
+    // the result is meant for logs only and is marked as such
+    pub fn render_debug<T: ToSqlLiteral>(
+        &self,
+        stmt: &SelectStmt<T>,
+        options: &DebugSqlOptions,
+    ) -> String {
+        format!(
+            "{}{}",
+            DEBUG_SQL_HEADER,
+            self.render_literals(stmt, &options.redacted_columns)
+        )
+    }
+
+    // Values written back as dialect literals and named parameters as :name,
+    // for tools that reformat or convert SQL text rather than execute it
+    pub fn render_inline<T: ToSqlLiteral>(&self, stmt: &SelectStmt<T>) -> String {
+        self.render_literals(stmt, &[])
+    }
+
+    fn render_literals<T: ToSqlLiteral>(
+        &self,
+        stmt: &SelectStmt<T>,
+        redacted_columns: &[String],
+    ) -> String {
+        let literal = |value: &T| value.to_sql_literal(&self.dialect);
+        let mut bindings = Bindings {
+            values: Vec::new(),
+            slots: Vec::new(),
+            inline: Some(&literal),
+            redacted_columns,
+            redacting: false,
+        };
+        self.select(stmt, 0, &mut bindings)
+    }
+
+    fn placeholder<'a, T>(&self, value: &'a T, bindings: &mut Bindings<'a, '_, T>) -> String {
+        bindings.values.push(value);
+        bindings.slots.push(ParameterSlot::Value(value));
+        match bindings.inline {
+            Some(_) if bindings.redacting => REDACTED_LITERAL.to_string(),
+            Some(literal) => self.format.keyword(&literal(value)),
+            None => determine_parameter_format(&self.dialect, bindings.slots.len() as u32),
+        }
+    }
+
+    fn named_placeholder<T>(&self, name: &str, bindings: &mut Bindings<'_, '_, T>) -> String {
+        let sql_dialect = self.dialect.sql_dialect();
+        let existing = bindings
+            .slots
+            .iter()
+            .position(|slot| matches!(slot, ParameterSlot::Named(n) if n == name));
+
+        if let Some(placeholder) = sql_dialect.named_parameter_placeholder(name) {
+            if existing.is_none() {
+                bindings.slots.push(ParameterSlot::Named(name.to_string()));
+            }
+            return placeholder;
+        }
+
+        let index = match existing {
+            Some(index) if sql_dialect.reuses_parameter_placeholders() => index + 1,
+            _ => {
+                bindings.slots.push(ParameterSlot::Named(name.to_string()));
+                bindings.slots.len()
+            }
+        };
+        if bindings.inline.is_some() {
+            return format!(":{}", name);
+        }
+        determine_parameter_format(&self.dialect, index as u32)
+    }
+
+    // `level` is the indentation of the clause keywords, items sit one deeper
+    fn select<'a, T>(
+        &self,
+        stmt: &'a SelectStmt<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        let format = &self.format;
+        if stmt.order_by.is_empty() && paging_requires_order_by(&self.dialect, stmt.offset) {
+            panic!("{} requires ORDER BY when OFFSET is used", self.dialect);
+        }
+
+        let mut clauses = Vec::new();
+
+        // SELECT
+        let mut keyword = "SELECT".to_string();
+        let top = generate_top_clause(&self.dialect, stmt.limit, stmt.offset);
+        if !top.is_empty() {
+            keyword.push(' ');
+            keyword.push_str(&top);
+        }
+        let items = stmt
+            .items
+            .iter()
+            .map(|item| self.select_item(item, level + 1, bindings))
+            .collect::<Vec<_>>();
+        clauses.push(format.clause(level, &keyword, &items, ItemLayout::List));
+
+        // FROM
+        if !stmt.from.is_empty() {
+            let tables = stmt
+                .from
+                .iter()
+                .map(|table| self.table_ref(table, level + 1, bindings))
+                .collect::<Vec<_>>();
+            clauses.push(format.clause(level, "FROM", &tables, ItemLayout::List));
+        }
+
+        // JOIN
+        for join in &stmt.joins {
+            clauses.push(self.join(join, level, bindings));
+        }
+
+        // WHERE
+        if let Some(selection) = &stmt.selection {
+            let conditions = self.conditions(selection, level + 1, bindings);
+            clauses.push(format.clause(level, "WHERE", &conditions, ItemLayout::Conditions));
+        }
+
+        // ORDER BY
+        if !stmt.order_by.is_empty() {
+            let items = stmt
+                .order_by
+                .iter()
+                .map(|o| self.order_by_item(o, level + 1, bindings))
+                .collect::<Vec<_>>();
+            clauses.push(format.clause(level, "ORDER BY", &items, ItemLayout::List));
+        }
+
+        // LIMIT / OFFSET
+        let limit_offset = generate_limit_offset(&self.dialect, stmt.limit, stmt.offset);
+        if !limit_offset.is_empty() {
+            clauses.push(format!(
+                "{}{}",
+                format.indentation(level),
+                format.keyword(&limit_offset)
+            ));
+        }
+
+        clauses.join(format.clause_separator())
+    }
+
+    fn select_item<'a, T>(
+        &self,
+        item: &'a SelectItem<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        let mut query = self.expr(&item.expr, level, bindings);
+        if let Some(alias) = &item.alias {
+            query.push_str(&self.format.keyword(" AS "));
+            query.push_str(alias);
+        }
+        query
+    }
+
+    // `level` is the indentation of the line the expression starts on
+    fn expr<'a, T>(
+        &self,
+        expr: &'a Expr<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        match expr {
+            Expr::Column {
+                table_alias,
+                name,
+                quoted,
+            } => {
+                let name = if *quoted {
+                    quote_identifier(&self.dialect, name)
+                } else {
+                    name.clone()
+                };
+                match table_alias {
+                    Some(alias) => format!("{}.{}", alias, name),
+                    None => name,
+                }
+            }
+            Expr::Param(value) => self.placeholder(value, bindings),
+            Expr::Named(name) => self.named_placeholder(name, bindings),
+            Expr::Raw(sql) | Expr::Literal(sql) => sql.clone(),
+            Expr::Wildcard { table_alias } => match table_alias {
+                Some(alias) => format!("{}.*", alias),
+                None => "*".to_string(),
+            },
+            Expr::Function { name, args } => {
+                let args = args
+                    .iter()
+                    .map(|a| self.expr(a, level, bindings))
+                    .collect::<Vec<_>>();
+                build_function_call(name, args, &None)
+            }
+            Expr::Aggregate {
+                function,
+                arg,
+                options,
+            } => {
+                let arg = self.expr(arg, level, bindings);
+                let filter = options.filter.as_deref();
+                // generate_aggregate_call reads a wildcard argument, it holds
+                // no keyword to case
+                let wildcard = arg == "*" || arg.ends_with(".*");
+                self.format
+                    .keywords_around(&[&arg, filter.unwrap_or_default()], |slots| {
+                        generate_aggregate_call(
+                            &self.dialect,
+                            *function,
+                            if wildcard { &arg } else { &slots[0] },
+                            options.separator.as_deref(),
+                            filter.map(|_| slots[1].as_str()),
+                        )
+                    })
+            }
+            Expr::Subquery(query) => self
+                .format
+                .subquery(level, &self.select(query, level + 1, bindings)),
+        }
+    }
+
+    fn table_ref<'a, T>(
+        &self,
+        table: &'a TableRef<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        match table {
+            TableRef::Table {
+                name,
+                alias,
+                quoted,
+            } => {
+                let name = if *quoted {
+                    name.split('.')
+                        .map(|part| quote_identifier(&self.dialect, part))
+                        .collect::<Vec<_>>()
+                        .join(".")
+                } else {
+                    name.clone()
+                };
+                match alias {
+                    Some(alias) => format!("{}{}", name, self.table_alias(alias)),
+                    None => name,
+                }
+            }
+            TableRef::Subquery { query, alias } => {
+                let subquery = self.select(query, level + 1, bindings);
+                let mut query = self.format.subquery(level, &subquery);
+                if !alias.is_empty() {
+                    query.push_str(&self.table_alias(alias));
+                }
+                query
+            }
+        }
+    }
+
+    fn table_alias(&self, alias: &str) -> String {
+        self.format.keywords_around(&[alias], |slots| {
+            generate_table_alias(&self.dialect, &slots[0])
+        })
+    }
+
+    // One line at the clause level, the ON condition is kept inline
+    fn join<'a, T>(
+        &self,
+        join: &'a Join<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        let mut query = format!(
+            "{}{} {}",
+            self.format.indentation(level),
+            self.format.keyword(join_type_to_string(join.join_type)),
+            self.table_ref(&join.table, level, bindings)
+        );
+        if let Some(on) = &join.on {
+            query.push_str(&self.format.keyword(" ON "));
+            query.push_str(&self.predicate(on, level, bindings));
+        }
+        query
+    }
+
+    // Top level conditions of a WHERE, the first one without its operator
+    fn conditions<'a, T>(
+        &self,
+        predicate: &'a Predicate<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> Vec<String> {
+        match predicate {
+            Predicate::And(predicates) => self.operands(predicates, "AND", level, bindings),
+            Predicate::Or(predicates) => self.operands(predicates, "OR", level, bindings),
+            _ => vec![self.predicate(predicate, level, bindings)],
+        }
+    }
+
+    fn predicate<'a, T>(
+        &self,
+        predicate: &'a Predicate<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        match predicate {
+            Predicate::Compare { left, op, right } => {
+                let left = self.expr(left, level, bindings);
+                let redacting = bindings.redacting;
+                bindings.redacting = redacting || is_redacted(bindings.redacted_columns, &left);
+                let query = match right.as_slice() {
+                    [Expr::Subquery(subquery)] => format!(
+                        "{} {} {}",
+                        left,
+                        self.format.keyword(&op.to_string()),
+                        self.format
+                            .subquery(level, &self.select(subquery, level + 1, bindings))
+                    ),
+                    _ => {
+                        let right = right
+                            .iter()
+                            .map(|r| self.expr(r, level, bindings))
+                            .collect::<Vec<_>>();
+                        let operands = std::iter::once(&left)
+                            .chain(&right)
+                            .map(String::as_str)
+                            .collect::<Vec<_>>();
+                        self.format.keywords_around(&operands, |slots| {
+                            self.dialect
+                                .sql_dialect()
+                                .compare(&slots[0], *op, &slots[1..])
+                                .unwrap_or_else(|error| panic!("{}", error))
+                        })
+                    }
+                };
+                bindings.redacting = redacting;
+                query
+            }
+            Predicate::And(predicates) => {
+                self.operands(predicates, "AND", level, bindings).join(" ")
+            }
+            Predicate::Or(predicates) => self.operands(predicates, "OR", level, bindings).join(" "),
+            Predicate::Group(inner) => {
+                format!("({})", self.predicate(inner, level, bindings))
+            }
+        }
+    }
+
+    // Operands prefixed with their operator from the second one on
+    fn operands<'a, T>(
+        &self,
+        predicates: &'a [Predicate<T>],
+        operator: &str,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> Vec<String> {
+        let mut operands = Vec::new();
+        for (i, predicate) in predicates.iter().enumerate() {
+            let mut rendered = self.predicate(predicate, level, bindings);
+            // OR binds looser than AND
+            if operator == "AND" && matches!(predicate, Predicate::Or(_)) {
+                rendered = format!("({})", rendered);
+            }
+            if i > 0 {
+                rendered = format!("{} {}", self.format.keyword(operator), rendered);
+            }
+            operands.push(rendered);
+        }
+        operands
+    }
+
+    fn order_by_item<'a, T>(
+        &self,
+        item: &'a OrderByItem<T>,
+        level: u32,
+        bindings: &mut Bindings<'a, '_, T>,
+    ) -> String {
+        let mut query = self.expr(&item.expr, level, bindings);
+        match item.sort {
+            Some(SortType::Ascending) => query.push_str(&self.format.keyword(" ASC")),
+            Some(SortType::Descending) => query.push_str(&self.format.keyword(" DESC")),
+            None => {}
+        }
+        query
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        AggregateOptions, DefaultOracleParamType, DefaultPostgresParamType, SqlAggregateFunction,
+        SqlJoinType, SqlOperator,
+    };
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn test_render_ast_for_oracle() {
+        let stmt = SelectStmt {
+            items: vec![
+                SelectItem {
+                    expr: Expr::column(Some("u"), "name"),
+                    alias: None,
+                },
+                SelectItem {
+                    expr: Expr::column(Some("o"), "id").aggregate(
+                        SqlAggregateFunction::Count,
+                        AggregateOptions::default().filter("o.total > 10"),
+                    ),
+                    alias: Some("big_orders".to_string()),
+                },
+            ],
+            from: vec![TableRef::Table {
+                name: "users".to_string(),
+                alias: Some("u".to_string()),
+
+                quoted: false,
+            }],
+            joins: vec![Join {
+                join_type: SqlJoinType::LeftJoin,
+                table: TableRef::Table {
+                    name: "orders".to_string(),
+                    alias: Some("o".to_string()),
+
+                    quoted: false,
+                },
+                on: Some(Predicate::Compare {
+                    left: Expr::column(Some("o"), "user_id"),
+                    op: SqlOperator::Equal,
+                    right: vec![Expr::column(Some("u"), "id")],
+                }),
+            }],
+            selection: Some(
+                Predicate::Compare {
+                    left: Expr::column(Some("u"), "active"),
+                    op: SqlOperator::Equal,
+                    right: vec![Expr::Param(DefaultOracleParamType::Int(1))],
+                }
+                .and(
+                    Predicate::Compare {
+                        left: Expr::column(Some("u"), "name"),
+                        op: SqlOperator::ILike,
+                        right: vec![Expr::Param(DefaultOracleParamType::String("a%".into()))],
+                    }
+                    .or(Predicate::Compare {
+                        left: Expr::column(Some("u"), "age"),
+                        op: SqlOperator::Between,
+                        right: vec![
+                            Expr::Param(DefaultOracleParamType::Int(18)),
+                            Expr::Param(DefaultOracleParamType::Int(65)),
+                        ],
+                    }),
+                ),
+            ),
+            order_by: vec![OrderByItem {
+                expr: Expr::column(Some("u"), "name"),
+                sort: Some(SortType::Ascending),
+            }],
+            limit: Some(10),
+            offset: None,
+        };
+
+        let rendered = Renderer::new(DatabaseDialect::Oracle).render(&stmt);
+        assert_eq!(
+            rendered.sql,
+            "SELECT u.name, COUNT(CASE WHEN o.total > 10 THEN o.id END) AS big_orders \
+             FROM users u LEFT JOIN orders o ON o.user_id = u.id \
+             WHERE u.active = :1 AND (UPPER(u.name) LIKE UPPER(:2) OR u.age BETWEEN :3 AND :4) \
+             ORDER BY u.name ASC FETCH FIRST 10 ROWS ONLY"
+        );
+        assert_eq!(
+            rendered.parameters,
+            vec![
+                &DefaultOracleParamType::Int(1),
+                &DefaultOracleParamType::String("a%".into()),
+                &DefaultOracleParamType::Int(18),
+                &DefaultOracleParamType::Int(65),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_debug_inlines_dialect_literals() {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+        let stmt = |created: DefaultPostgresParamType| SelectStmt {
+            items: vec![SelectItem {
+                expr: Expr::column(Some("u"), "id"),
+                alias: None,
+            }],
+            from: vec![TableRef::Table {
+                name: "users".to_string(),
+                alias: Some("u".to_string()),
+
+                quoted: false,
+            }],
+            selection: Some(
+                Predicate::Compare {
+                    left: Expr::column(Some("u"), "name"),
+                    op: SqlOperator::Equal,
+                    right: vec![Expr::Param(DefaultPostgresParamType::String(
+                        "O'Brien".to_string(),
+                    ))],
+                }
+                .and(Predicate::Compare {
+                    left: Expr::column(Some("u"), "created_at"),
+                    op: SqlOperator::Greater,
+                    right: vec![Expr::Param(created)],
+                })
+                .and(Predicate::Compare {
+                    left: Expr::column(Some("u"), "password"),
+                    op: SqlOperator::Equal,
+                    right: vec![Expr::Param(DefaultPostgresParamType::String(
+                        "secret".to_string(),
+                    ))],
+                })
+                .and(Predicate::Compare {
+                    left: Expr::column(Some("u"), "active"),
+                    op: SqlOperator::Equal,
+                    right: vec![Expr::Param(DefaultPostgresParamType::Bool(true))],
+                }),
+            ),
+            ..SelectStmt::new()
+        };
+        let options = DebugSqlOptions::default().redact("password");
+
+        assert_eq!(
+            Renderer::new(DatabaseDialect::PostgreSQL).render_debug(
+                &stmt(DefaultPostgresParamType::TimePoint(created)),
+                &options
+            ),
+            "/* nvcore debug SQL, values inlined for logging, do not execute */ \
+             SELECT u.id FROM users AS u WHERE u.name = 'O''Brien' \
+             AND u.created_at > TIMESTAMP '2023-11-14 22:13:20.500' \
+             AND u.password = '***' AND u.active = TRUE"
+        );
+
+        let whole_seconds = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        assert_eq!(
+            Renderer::new(DatabaseDialect::Oracle).render_debug(
+                &stmt(DefaultPostgresParamType::TimePoint(whole_seconds)),
+                &options
+            ),
+            "/* nvcore debug SQL, values inlined for logging, do not execute */ \
+             SELECT u.id FROM users u WHERE u.name = 'O''Brien' \
+             AND u.created_at > TO_DATE('2023-11-14 22:13:20', 'YYYY-MM-DD HH24:MI:SS') \
+             AND u.password = '***' AND u.active = 1"
+        );
+    }
+}
@@ -0,0 +1,500 @@
+// Versioned serde envelope for persisting and shipping queries
+//
+// A SavedQuery carries the select syntax tree, its bound values and the
+// dialect, so a query built in one service can be rebuilt and rendered in
+// another. `version` is bumped whenever the serialized shape of the tree
+// changes, payloads with an unknown version are rejected on deserialization.
+// A payload can still hold a statement its dialect cannot render, such as
+// OFFSET without ORDER BY on SQL Server, call `validate` or render it with
+// Select::try_generate_query instead of generate_query, which panics.
+//
+// Raw SQL fragments (Expr::Raw, aggregate FILTER predicates) are rendered
+// verbatim, and so are unquoted table and column names, qualifiers, aliases
+// and function names. A SavedQuery holding a raw fragment, or a name that is
+// neither a plain identifier nor a double quoted one, is rejected on
+// deserialization. Quoted table and column names go through the dialect
+// quoting and are accepted as they are. A payload from a trusted source is
+// read as UncheckedSavedQuery and accepted with `trust_raw_sql`. Custom
+// dialects are written by name but cannot be read back, see the
+// DatabaseDialect impls below.
+
+use crate::sqlbuilder::allowlist::is_identifier;
+use crate::sqlbuilder::{
+    walk_expr, walk_select_item, walk_table_ref, DatabaseDialect, Expr, NvSelect, RenderError,
+    Renderer, Select, SelectItem, SelectStmt, TableRef, Visitor,
+};
+use serde::de::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+
+pub const QUERY_SCHEMA_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "UncheckedSavedQuery<T>")]
+pub struct SavedQuery<T> {
+    pub version: u32,
+    pub dialect: DatabaseDialect,
+    pub statement: SelectStmt<T>,
+}
+
+// A SavedQuery as read from the payload, raw SQL fragments included
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct UncheckedSavedQuery<T> {
+    #[serde(deserialize_with = "deserialize_schema_version")]
+    pub version: u32,
+    pub dialect: DatabaseDialect,
+    pub statement: SelectStmt<T>,
+}
+
+impl<T> UncheckedSavedQuery<T> {
+    // Accepts the raw fragments, only for payloads from a trusted source
+    pub fn trust_raw_sql(self) -> SavedQuery<T> {
+        SavedQuery {
+            version: self.version,
+            dialect: self.dialect,
+            statement: self.statement,
+        }
+    }
+}
+
+impl<T> TryFrom<UncheckedSavedQuery<T>> for SavedQuery<T> {
+    type Error = RawSqlError;
+
+    fn try_from(query: UncheckedSavedQuery<T>) -> Result<Self, RawSqlError> {
+        let mut finder = RawSqlFinder { fragment: None };
+        query.statement.accept(&mut finder);
+        match finder.fragment {
+            Some(fragment) => Err(RawSqlError { fragment }),
+            None => Ok(query.trust_raw_sql()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RawSqlError {
+    pub fragment: String,
+}
+
+impl fmt::Display for RawSqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the saved query holds the raw SQL fragment {:?}, read it as \
+             UncheckedSavedQuery to trust it",
+            self.fragment
+        )
+    }
+}
+
+impl std::error::Error for RawSqlError {}
+
+// First raw fragment, FILTER predicate or name rendered verbatim that is not
+// an identifier
+struct RawSqlFinder {
+    fragment: Option<String>,
+}
+
+impl RawSqlFinder {
+    fn check_name(&mut self, name: &str) {
+        if self.fragment.is_none() && !name.split('.').all(is_name_part) {
+            self.fragment = Some(name.to_string());
+        }
+    }
+}
+
+// Plain identifier, or a double quoted one as the parser writes aliases
+fn is_name_part(part: &str) -> bool {
+    if is_identifier(part) {
+        return true;
+    }
+    part.len() > 2
+        && part.starts_with('"')
+        && part.ends_with('"')
+        && !part[1..part.len() - 1].replace("\"\"", "").contains('"')
+}
+
+impl<T> Visitor<T> for RawSqlFinder {
+    fn visit_select_item(&mut self, item: &SelectItem<T>) {
+        if let Some(alias) = &item.alias {
+            self.check_name(alias);
+        }
+        walk_select_item(self, item);
+    }
+
+    fn visit_table_ref(&mut self, table: &TableRef<T>) {
+        match table {
+            TableRef::Table {
+                name,
+                alias,
+                quoted,
+            } => {
+                if !quoted {
+                    self.check_name(name);
+                }
+                if let Some(alias) = alias {
+                    self.check_name(alias);
+                }
+            }
+            TableRef::Subquery { alias, .. } if !alias.is_empty() => self.check_name(alias),
+            TableRef::Subquery { .. } => {}
+        }
+        walk_table_ref(self, table);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<T>) {
+        if self.fragment.is_some() {
+            return;
+        }
+        match expr {
+            Expr::Raw(fragment) => self.fragment = Some(fragment.clone()),
+            Expr::Literal(literal) if literal.parse::<f64>().is_err() => {
+                self.fragment = Some(literal.clone());
+            }
+            Expr::Aggregate { options, .. } if options.filter.is_some() => {
+                self.fragment = options.filter.clone();
+            }
+            Expr::Column {
+                table_alias,
+                name,
+                quoted,
+            } => {
+                if let Some(alias) = table_alias {
+                    self.check_name(alias);
+                }
+                if !quoted {
+                    self.check_name(name);
+                }
+            }
+            Expr::Wildcard {
+                table_alias: Some(alias),
+            } => self.check_name(alias),
+            Expr::Function { name, .. } => {
+                self.check_name(name);
+                walk_expr(self, expr);
+            }
+            _ => walk_expr(self, expr),
+        }
+    }
+}
+
+impl<T> SavedQuery<T> {
+    pub fn new(statement: SelectStmt<T>, dialect: DatabaseDialect) -> Self {
+        Self {
+            version: QUERY_SCHEMA_VERSION,
+            dialect,
+            statement,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), RenderError> {
+        Renderer::new(self.dialect).validate(&self.statement)
+    }
+
+    pub fn into_select(self) -> Select<T> {
+        Select::from_statement(self.statement, self.dialect)
+    }
+}
+
+impl<T: Clone> Select<T> {
+    pub fn to_saved_query(&self) -> SavedQuery<T> {
+        SavedQuery::new(self.statement().clone(), self.dialect())
+    }
+}
+
+impl<T: Clone> NvSelect<T> {
+    pub fn to_saved_query(&self) -> SavedQuery<T> {
+        let select = self.to_select();
+        SavedQuery::new(select.statement().clone(), select.dialect())
+    }
+}
+
+fn deserialize_schema_version<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let version = u32::deserialize(deserializer)?;
+    if version == 0 || version > QUERY_SCHEMA_VERSION {
+        return Err(D::Error::custom(format!(
+            "unsupported query schema version {}, expected at most {}",
+            version, QUERY_SCHEMA_VERSION
+        )));
+    }
+    Ok(version)
+}
+
+// Dialects are stored by name, a custom dialect is written with its own name
+// but cannot be read back because its implementation is not serializable
+impl Serialize for DatabaseDialect {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            DatabaseDialect::PostgreSQL => serializer.serialize_str("postgresql"),
+            DatabaseDialect::Oracle => serializer.serialize_str("oracle"),
+            DatabaseDialect::MySQL => serializer.serialize_str("mysql"),
+            DatabaseDialect::SQLite => serializer.serialize_str("sqlite"),
+            DatabaseDialect::SqlServer => serializer.serialize_str("sqlserver"),
+            DatabaseDialect::Custom(dialect) => serializer.serialize_str(dialect.name()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for DatabaseDialect {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let name = String::deserialize(deserializer)?;
+        match name.as_str() {
+            "postgresql" => Ok(DatabaseDialect::PostgreSQL),
+            "oracle" => Ok(DatabaseDialect::Oracle),
+            "mysql" => Ok(DatabaseDialect::MySQL),
+            "sqlite" => Ok(DatabaseDialect::SQLite),
+            "sqlserver" => Ok(DatabaseDialect::SqlServer),
+            _ => Err(D::Error::custom(format!(
+                "unknown dialect {}, custom dialects cannot be deserialized",
+                name
+            ))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        AggregateOptions, DefaultPostgresParamType, DefaultSqlServerParamType, SortType,
+        SqlAggregateFunction, SqlOperator,
+    };
+    use std::time::{Duration, SystemTime};
+
+    type Param = DefaultPostgresParamType;
+
+    #[test]
+    fn test_saved_query_json_schema() {
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("u"), None, SqlAggregateFunction::None, false)
+            .from_table("users", Some("u"))
+            .where_clause(|w| {
+                w.add_condition("u.id", SqlOperator::Equal, Param::Int(7))
+                    .unwrap()
+            });
+
+        assert_eq!(
+            serde_json::to_string(&select.to_saved_query()).unwrap(),
+            r#"{"version":1,"dialect":"postgresql","statement":{"items":[{"expr":{"column":{"table_alias":"u","name":"id","quoted":false}},"alias":null}],"from":[{"table":{"name":"users","alias":"u","quoted":false}}],"joins":[],"selection":{"compare":{"left":{"column":{"table_alias":"u","name":"id","quoted":false}},"op":"equal","right":[{"param":{"int":7}}]}},"order_by":[],"limit":null,"offset":null}}"#
+        );
+
+        let error = serde_json::from_str::<SavedQuery<Param>>(
+            r#"{"version":2,"dialect":"postgresql","statement":{"items":[],"from":[],"joins":[],"selection":null,"order_by":[],"limit":null,"offset":null}}"#,
+        )
+        .unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("unsupported query schema version 2"));
+    }
+
+    #[test]
+    fn test_legacy_select_round_trips_through_json() {
+        let created = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let legacy = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "name".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "u.created_at",
+                &SqlOperator::Greater,
+                Param::TimePoint(created),
+            )
+            .unwrap()
+            .and()
+            .add_subquery("u.role".to_string(), SqlOperator::In, String::new())
+            .f(
+                "code".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("roles", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "name",
+                &SqlOperator::Like,
+                Param::String("adm%".to_string()),
+            )
+            .unwrap()
+            .end_where_block()
+            .end_subquery_inside_where_condition()
+            .end_where_block();
+
+        let json = serde_json::to_string(&legacy.to_saved_query()).unwrap();
+        let rebuilt = serde_json::from_str::<SavedQuery<Param>>(&json)
+            .unwrap()
+            .into_select();
+
+        assert_eq!(rebuilt.generate_query(false), legacy.generate_query(false));
+        assert_eq!(rebuilt.values(), legacy.values());
+    }
+
+    #[test]
+    fn test_loaded_query_is_validated_before_rendering() {
+        let page = Select::<DefaultSqlServerParamType>::new(DatabaseDialect::SqlServer)
+            .f("id", Some("o"), None, SqlAggregateFunction::None, false)
+            .from(|f| {
+                // The builder rejects this offset, a payload can still hold it
+                let mut orders = Select::new(DatabaseDialect::SqlServer)
+                    .f("id", None, None, SqlAggregateFunction::None, false)
+                    .from_table("orders", None);
+                orders.statement_mut().offset = Some(10);
+                f.add_subquery(orders, "o")
+            })
+            .order_by("id", Some("o"), SortType::Ascending);
+        let json = serde_json::to_string(&page.to_saved_query()).unwrap();
+
+        let saved: SavedQuery<DefaultSqlServerParamType> = serde_json::from_str(&json).unwrap();
+        let error = RenderError::OffsetWithoutOrderBy {
+            dialect: DatabaseDialect::SqlServer,
+        };
+        assert_eq!(saved.validate(), Err(error.clone()));
+        assert_eq!(saved.into_select().try_generate_query(false), Err(error));
+
+        // A BETWEEN with one bound and an equality with no operand at all
+        for (json, op, expected, found) in [
+            (
+                r#"{"compare":{"left":{"column":{"table_alias":null,"name":"id","quoted":false}},"op":"between","right":[{"param":{"int":1}}]}}"#,
+                SqlOperator::Between,
+                2,
+                1,
+            ),
+            (
+                r#"{"compare":{"left":{"column":{"table_alias":null,"name":"id","quoted":false}},"op":"equal","right":[]}}"#,
+                SqlOperator::Equal,
+                1,
+                0,
+            ),
+        ] {
+            let payload = format!(
+                r#"{{"version":1,"dialect":"postgresql","statement":{{"items":[],"from":[{{"table":{{"name":"orders","alias":null}}}}],"joins":[],"selection":{},"order_by":[],"limit":null,"offset":null}}}}"#,
+                json
+            );
+            let saved: SavedQuery<Param> = serde_json::from_str(&payload).unwrap();
+            let error = RenderError::OperandCount {
+                op,
+                expected,
+                found,
+            };
+            assert_eq!(saved.validate(), Err(error.clone()));
+            assert_eq!(saved.into_select().try_generate_query(false), Err(error));
+        }
+    }
+
+    #[test]
+    fn test_raw_sql_is_rejected_unless_trusted() {
+        let report = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .aggregate(
+                SqlAggregateFunction::Count,
+                "id",
+                None,
+                Some("paid"),
+                AggregateOptions::default().filter("status = 'paid'"),
+            )
+            .from_table("orders", None);
+        let json = serde_json::to_string(&report.to_saved_query()).unwrap();
+
+        let error = serde_json::from_str::<SavedQuery<Param>>(&json).unwrap_err();
+        assert!(error
+            .to_string()
+            .starts_with("the saved query holds the raw SQL fragment \"status = 'paid'\""));
+
+        let trusted = serde_json::from_str::<UncheckedSavedQuery<Param>>(&json)
+            .unwrap()
+            .trust_raw_sql();
+        assert_eq!(
+            trusted.into_select().generate_query(false),
+            report.generate_query(false)
+        );
+
+        let raw = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .fn_static("timezone", &["'UTC'", "now()"], Some("at"))
+            .to_saved_query();
+        let json = serde_json::to_string(&raw).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SavedQuery<Param>>(&json)
+                .unwrap_err()
+                .to_string(),
+            "the saved query holds the raw SQL fragment \"'UTC'\", read it as \
+             UncheckedSavedQuery to trust it"
+        );
+
+        let count = Select::<Param>::parse(
+            "SELECT COUNT(*) AS total FROM orders",
+            DatabaseDialect::PostgreSQL,
+        )
+        .unwrap();
+        let json = serde_json::to_string(&count.to_saved_query()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<SavedQuery<Param>>(&json)
+                .unwrap()
+                .into_select()
+                .generate_query(false),
+            "SELECT COUNT(*) AS total FROM orders"
+        );
+    }
+
+    #[test]
+    fn test_hostile_names_are_rejected() {
+        let load = |select: Select<Param>| {
+            let json = serde_json::to_string(&select.to_saved_query()).unwrap();
+            serde_json::from_str::<SavedQuery<Param>>(&json).map(|query| query.into_select())
+        };
+        let select = || Select::<Param>::new(DatabaseDialect::PostgreSQL);
+
+        for hostile in [
+            select()
+                .f(
+                    "1; DROP TABLE users; --",
+                    None,
+                    None,
+                    SqlAggregateFunction::None,
+                    false,
+                )
+                .from_table("users", None),
+            select()
+                .fn_static("pg_sleep(10); --", &[], None)
+                .from_table("users", None),
+            select()
+                .f(
+                    "id",
+                    None,
+                    Some("x FROM users; --"),
+                    SqlAggregateFunction::None,
+                    false,
+                )
+                .from_table("users", None),
+            select()
+                .f("id", None, None, SqlAggregateFunction::None, false)
+                .from_table("users u; --", None),
+        ] {
+            let error = load(hostile).unwrap_err();
+            assert!(error.to_string().contains("--"), "{}", error);
+        }
+
+        // Quoted names go through the dialect quoting
+        let quoted = select()
+            .f(
+                "1; DROP TABLE users; --",
+                Some("u"),
+                Some("\"Total\""),
+                SqlAggregateFunction::None,
+                true,
+            )
+            .from_table("users", Some("u"));
+        assert_eq!(
+            load(quoted).unwrap().generate_query(false),
+            "SELECT u.\"1; DROP TABLE users; --\" AS \"Total\" FROM users AS u"
+        );
+    }
+}
@@ -0,0 +1,264 @@
+// Typed table and column definitions
+//
+// Tables are declared once with nv_table! and their columns carry the Rust
+// type of their values, so a misspelled column does not compile and a
+// condition only accepts values of the column type:
+//
+//     nv_table! {
+//         pub struct Users = "users" {
+//             ID: i64 = "id",
+//             EMAIL: String = "email",
+//         }
+//     }
+//
+//     Select::new(DatabaseDialect::PostgreSQL)
+//         .column(Users::EMAIL)
+//         .from_schema(Users, Some("u"))
+//         .where_clause(|w| w.condition(Users::ID, SqlOperator::Equal, 7).unwrap())
+//
+// renders `SELECT u.email FROM users AS u WHERE u.id = $1`. Columns are
+// qualified with their table name and Select replaces a table name with the
+// alias the table has in FROM or JOIN, in whichever order they are added. A
+// table that appears twice is left alone, qualify its columns with
+// Column::of instead.
+//
+// Entity structs get the same with #[derive(NvTable)], which also maps rows
+// back into the struct.
+
+use crate::row::FromRow;
+use crate::sqlbuilder::{walk_select_mut, Expr, MutVisitor, SelectStmt, TableRef};
+use std::collections::HashMap;
+use std::fmt;
+use std::marker::PhantomData;
+
+pub trait Table {
+    const NAME: &'static str;
+
+    fn columns() -> &'static [&'static str];
+}
+
+// A struct whose fields are the columns of its table, see #[derive(NvTable)]
+pub trait NvTable: Table + FromRow {
+    // Column and field name of every mapped field, fields are selected under
+    // their own name
+    fn fields() -> &'static [(&'static str, &'static str)];
+}
+
+pub struct Column<V> {
+    table: &'static str,
+    name: &'static str,
+    alias: Option<&'static str>,
+    value: PhantomData<fn() -> V>,
+}
+
+impl<V> Column<V> {
+    pub const fn new(table: &'static str, name: &'static str) -> Self {
+        Self {
+            table,
+            name,
+            alias: None,
+            value: PhantomData,
+        }
+    }
+
+    // The column of the table instance aliased `alias`, for self joins
+    pub const fn of(self, alias: &'static str) -> Self {
+        Self {
+            alias: Some(alias),
+            ..self
+        }
+    }
+
+    pub fn table(&self) -> &'static str {
+        self.table
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    pub fn expr<T>(&self) -> Expr<T> {
+        Expr::column(Some(self.alias.unwrap_or(self.table)), self.name)
+    }
+}
+
+impl<V> Clone for Column<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for Column<V> {}
+
+impl<V> fmt::Debug for Column<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}", self.alias.unwrap_or(self.table), self.name)
+    }
+}
+
+// Declares a unit struct implementing Table with one Column constant per
+// column
+#[macro_export]
+macro_rules! nv_table {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $table:ident = $name:literal {
+            $($column:ident : $ty:ty = $column_name:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $table;
+
+        impl $table {
+            $(
+                pub const $column: $crate::sqlbuilder::Column<$ty> =
+                    $crate::sqlbuilder::Column::new($name, $column_name);
+            )*
+        }
+
+        impl $crate::sqlbuilder::Table for $table {
+            const NAME: &'static str = $name;
+
+            fn columns() -> &'static [&'static str] {
+                &[$($column_name),*]
+            }
+        }
+    };
+}
+
+// Replaces table names used as column qualifiers with the alias of the table,
+// subqueries see the tables of the enclosing SELECTs unless they shadow them
+pub(crate) fn resolve_table_aliases<T>(select: &mut SelectStmt<T>) {
+    let mut resolver = AliasResolver {
+        aliases: HashMap::new(),
+    };
+    resolver.visit_select_mut(select);
+}
+
+struct AliasResolver {
+    // Lowercase table name to its alias, None when the table is not aliased
+    // or appears more than once
+    aliases: HashMap<String, Option<String>>,
+}
+
+impl<T> MutVisitor<T> for AliasResolver {
+    fn visit_select_mut(&mut self, select: &mut SelectStmt<T>) {
+        let outer = self.aliases.clone();
+        let mut seen = HashMap::new();
+        let tables = select
+            .from
+            .iter()
+            .chain(select.joins.iter().map(|join| &join.table));
+        for table in tables {
+            let (name, alias) = match table {
+                TableRef::Table { name, alias, .. } => (name.to_lowercase(), alias.clone()),
+                TableRef::Subquery { alias, .. } => (alias.to_lowercase(), None),
+            };
+            *seen.entry(name.clone()).or_insert(0) += 1;
+            self.aliases.insert(name, alias);
+            if let Some(alias) = table_alias(table) {
+                self.aliases.insert(alias.to_lowercase(), None);
+            }
+        }
+        for (name, count) in seen {
+            if count > 1 {
+                self.aliases.insert(name, None);
+            }
+        }
+
+        walk_select_mut(self, select);
+        self.aliases = outer;
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr<T>) {
+        if let Expr::Column {
+            table_alias: Some(qualifier),
+            ..
+        } = expr
+        {
+            if let Some(Some(alias)) = self.aliases.get(&qualifier.to_lowercase()) {
+                *qualifier = alias.clone();
+            }
+        }
+        crate::sqlbuilder::walk_expr_mut(self, expr);
+    }
+}
+
+fn table_alias<T>(table: &TableRef<T>) -> Option<&str> {
+    match table {
+        TableRef::Table { alias, .. } => alias.as_deref(),
+        TableRef::Subquery { alias, .. } => Some(alias),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::sqlbuilder::{
+        Allowlist, DatabaseDialect, DefaultPostgresParamType, Select, SortType, SqlJoinType,
+        SqlOperator,
+    };
+
+    nv_table! {
+        pub struct Users = "users" {
+            ID: i64 = "id",
+            EMAIL: String = "email",
+            TEAM_ID: i64 = "team_id",
+        }
+    }
+
+    nv_table! {
+        struct Teams = "teams" {
+            ID: i64 = "id",
+            NAME: String = "name",
+            PARENT_ID: i64 = "parent_id",
+        }
+    }
+
+    type Param = DefaultPostgresParamType;
+
+    #[test]
+    fn test_typed_columns_follow_table_aliases() {
+        // Columns are added before the tables that give them their alias
+        let select = Select::<Param>::new(DatabaseDialect::PostgreSQL)
+            .column(Users::EMAIL)
+            .column_as(Teams::NAME.of("t"), "team")
+            .column_as(Teams::NAME.of("p"), "parent")
+            .from_schema(Users, Some("u"))
+            .join_schema(SqlJoinType::InnerJoin, Teams, Some("t"), |on| {
+                on.column_condition(Teams::ID.of("t"), SqlOperator::Equal, Users::TEAM_ID)
+            })
+            .join(SqlJoinType::LeftJoin, "teams", Some("p"), |on| {
+                on.column_condition(
+                    Teams::ID.of("p"),
+                    SqlOperator::Equal,
+                    Teams::PARENT_ID.of("t"),
+                )
+            })
+            .where_clause(|w| {
+                w.condition(Users::EMAIL, SqlOperator::Like, "%@example.com")
+                    .unwrap()
+                    .condition_in(Users::ID, vec![1, 2])
+            })
+            .order_by_column(Users::ID, SortType::Descending);
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT u.email, t.name AS team, p.name AS parent FROM users AS u \
+             INNER JOIN teams AS t ON t.id = u.team_id \
+             LEFT JOIN teams AS p ON p.id = t.parent_id \
+             WHERE u.email LIKE $1 AND u.id IN ($2, $3) ORDER BY u.id DESC"
+        );
+        assert_eq!(
+            select.values(),
+            vec![
+                &Param::String("%@example.com".to_string()),
+                &Param::BigInt(1),
+                &Param::BigInt(2),
+            ]
+        );
+
+        let allowlist = Allowlist::new().schema::<Users>().schema::<Teams>();
+        assert!(allowlist.check(select.statement()).is_ok());
+    }
+}
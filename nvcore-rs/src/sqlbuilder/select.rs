@@ -0,0 +1,1008 @@
+// Owned select core
+//
+// `Select<T>` builds a `SelectStmt` syntax tree: every fluent method consumes
+// the builder and returns it (`self -> Self`), subqueries are owned children,
+// and there are no locks, shared vectors or parent back-pointers. Placeholder
+// indexes are assigned by the renderer, so a subtree can be built on its own
+// and attached anywhere. The `Arc<Self>` chaining API of `NvSelect` remains as
+// a thin compatibility wrapper that owns one of these, see `NvSelect::to_select`.
+
+use std::fmt;
+use std::iter::Peekable;
+use std::vec::IntoIter;
+
+use crate::sqlbuilder::{
+    paging_requires_order_by, resolve_table_aliases, AggregateOptions, Allowlist, AllowlistError,
+    Column, DatabaseDialect, DebugSqlOptions, Expr, FormatOptions, Join, LogicOperator, MutVisitor,
+    NvTable, OrderByItem, Predicate, RenderError, RenderedQuery, Renderer, SelectItem, SelectStmt,
+    SortType, SqlAggregateFunction, SqlJoinType, SqlOperator, Table, TableRef, ToSqlLiteral,
+};
+
+#[derive(Debug, Clone)]
+pub struct FromClause<T> {
+    tables: Vec<TableRef<T>>,
+}
+
+impl<T> Default for FromClause<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> FromClause<T> {
+    pub fn new() -> Self {
+        Self { tables: Vec::new() }
+    }
+
+    pub fn add_table(mut self, table: TableRef<T>) -> Self {
+        self.tables.push(table);
+        self
+    }
+
+    pub fn add_table_with_alias(self, table_name: &str, table_alias: Option<&str>) -> Self {
+        self.add_table(TableRef::Table {
+            name: table_name.to_string(),
+            alias: table_alias.map(str::to_string),
+            quoted: false,
+        })
+    }
+
+    pub fn add_schema_table<Tb: Table>(self, _table: Tb, table_alias: Option<&str>) -> Self {
+        self.add_table_with_alias(Tb::NAME, table_alias)
+    }
+
+    pub fn add_subquery(self, select: Select<T>, table_alias: &str) -> Self {
+        self.add_table(TableRef::Subquery {
+            query: Box::new(select.build()),
+            alias: table_alias.to_string(),
+        })
+    }
+
+    pub fn tables(&self) -> &[TableRef<T>] {
+        &self.tables
+    }
+}
+
+#[derive(Debug, Clone)]
+enum ConditionToken<T> {
+    Predicate(Predicate<T>),
+    Logic(LogicOperator),
+    StartGroup,
+    EndGroup,
+}
+
+type Tokens<T> = Peekable<IntoIter<ConditionToken<T>>>;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConditionError {
+    // IN and BETWEEN given to a single value condition
+    MultiValue { field_name: String, op: SqlOperator },
+}
+
+impl fmt::Display for ConditionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConditionError::MultiValue { field_name, op } => write!(
+                f,
+                "{} takes several values, use the between or in condition on {}",
+                op, field_name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConditionError {}
+
+// BETWEEN and IN have conditions of their own
+pub(crate) fn single_value(field_name: &str, op: SqlOperator) -> Result<(), ConditionError> {
+    if op == SqlOperator::Between || op == SqlOperator::In {
+        return Err(ConditionError::MultiValue {
+            field_name: field_name.to_string(),
+            op,
+        });
+    }
+    Ok(())
+}
+
+// Conditions are written in reading order with and()/or() and groups, then
+// folded into a Predicate tree where AND binds tighter than OR
+#[derive(Debug, Clone)]
+pub struct WhereClause<T> {
+    tokens: Vec<ConditionToken<T>>,
+}
+
+impl<T> Default for WhereClause<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> WhereClause<T> {
+    pub fn new() -> Self {
+        Self { tokens: Vec::new() }
+    }
+
+    pub fn add_predicate(mut self, predicate: Predicate<T>) -> Self {
+        self.tokens.push(ConditionToken::Predicate(predicate));
+        self
+    }
+
+    fn comparator(self, field_name: &str, op: SqlOperator, values: Vec<T>) -> Self {
+        self.add_predicate(Predicate::Compare {
+            left: Expr::column_ref(field_name),
+            op,
+            right: values.into_iter().map(Expr::Param).collect(),
+        })
+    }
+
+    // BETWEEN and IN take several values, see add_condition_between and
+    // add_condition_in
+    pub fn add_condition(
+        self,
+        field_name: &str,
+        op: SqlOperator,
+        value: T,
+    ) -> Result<Self, ConditionError> {
+        single_value(field_name, op)?;
+        Ok(self.comparator(field_name, op, vec![value]))
+    }
+
+    // Comparison against a named parameter whose value is bound at execution
+    // time, see RenderedQuery::bind. IN and BETWEEN take several values and
+    // are written with add_named_condition_in and add_named_condition_between
+    pub fn add_named_condition(
+        self,
+        field_name: &str,
+        op: SqlOperator,
+        name: &str,
+    ) -> Result<Self, ConditionError> {
+        single_value(field_name, op)?;
+        Ok(self.named_comparator(field_name, op, &[name]))
+    }
+
+    pub fn add_named_condition_between(self, field_name: &str, low: &str, high: &str) -> Self {
+        self.named_comparator(field_name, SqlOperator::Between, &[low, high])
+    }
+
+    // One named parameter per list item, an empty list matches no row
+    pub fn add_named_condition_in(self, field_name: &str, names: &[&str]) -> Self {
+        self.named_comparator(field_name, SqlOperator::In, names)
+    }
+
+    fn named_comparator(self, field_name: &str, op: SqlOperator, names: &[&str]) -> Self {
+        self.add_predicate(Predicate::Compare {
+            left: Expr::column_ref(field_name),
+            op,
+            right: names.iter().map(|name| Expr::named(name)).collect(),
+        })
+    }
+
+    pub fn add_condition_between(self, field_name: &str, value1: T, value2: T) -> Self {
+        self.comparator(field_name, SqlOperator::Between, vec![value1, value2])
+    }
+
+    // An empty list renders as a predicate that matches no row
+    pub fn add_condition_in(self, field_name: &str, values: Vec<T>) -> Self {
+        self.comparator(field_name, SqlOperator::In, values)
+    }
+
+    // Column to column comparison, as used by JOIN ... ON
+    pub fn add_column_condition(self, left: &str, op: SqlOperator, right: &str) -> Self {
+        self.add_predicate(Predicate::Compare {
+            left: Expr::column_ref(left),
+            op,
+            right: vec![Expr::column_ref(right)],
+        })
+    }
+
+    pub fn add_subquery(self, field_name: &str, op: SqlOperator, select: Select<T>) -> Self {
+        self.add_predicate(Predicate::Compare {
+            left: Expr::column_ref(field_name),
+            op,
+            right: vec![Expr::Subquery(Box::new(select.build()))],
+        })
+    }
+
+    // Typed counterpart of add_condition, the value must convert into the
+    // column type
+    pub fn condition<V>(
+        self,
+        column: Column<V>,
+        op: SqlOperator,
+        value: impl Into<V>,
+    ) -> Result<Self, ConditionError>
+    where
+        T: From<V>,
+    {
+        single_value(&format!("{:?}", column), op)?;
+        Ok(self.add_predicate(Predicate::Compare {
+            left: column.expr(),
+            op,
+            right: vec![Expr::Param(T::from(value.into()))],
+        }))
+    }
+
+    pub fn condition_between<V>(
+        self,
+        column: Column<V>,
+        value1: impl Into<V>,
+        value2: impl Into<V>,
+    ) -> Self
+    where
+        T: From<V>,
+    {
+        self.add_predicate(Predicate::Compare {
+            left: column.expr(),
+            op: SqlOperator::Between,
+            right: vec![
+                Expr::Param(T::from(value1.into())),
+                Expr::Param(T::from(value2.into())),
+            ],
+        })
+    }
+
+    pub fn condition_in<V>(self, column: Column<V>, values: Vec<V>) -> Self
+    where
+        T: From<V>,
+    {
+        self.add_predicate(Predicate::Compare {
+            left: column.expr(),
+            op: SqlOperator::In,
+            right: values
+                .into_iter()
+                .map(|value| Expr::Param(T::from(value)))
+                .collect(),
+        })
+    }
+
+    // Both columns hold values of the same type
+    pub fn column_condition<V>(self, left: Column<V>, op: SqlOperator, right: Column<V>) -> Self {
+        self.add_predicate(Predicate::Compare {
+            left: left.expr(),
+            op,
+            right: vec![right.expr()],
+        })
+    }
+
+    pub fn and(mut self) -> Self {
+        self.tokens.push(ConditionToken::Logic(LogicOperator::And));
+        self
+    }
+
+    pub fn or(mut self) -> Self {
+        self.tokens.push(ConditionToken::Logic(LogicOperator::Or));
+        self
+    }
+
+    pub fn start_group(mut self) -> Self {
+        self.tokens.push(ConditionToken::StartGroup);
+        self
+    }
+
+    pub fn end_group(mut self) -> Self {
+        self.tokens.push(ConditionToken::EndGroup);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    pub fn build(self) -> Option<Predicate<T>> {
+        if self.tokens.is_empty() {
+            return None;
+        }
+
+        let mut tokens = self.tokens.into_iter().peekable();
+        let predicate = Self::parse_or(&mut tokens);
+        if tokens.next().is_some() {
+            panic!("Unbalanced end_group() in where clause");
+        }
+        Some(predicate)
+    }
+
+    fn parse_or(tokens: &mut Tokens<T>) -> Predicate<T> {
+        let mut predicate = Self::parse_and(tokens);
+        while let Some(ConditionToken::Logic(LogicOperator::Or)) = tokens.peek() {
+            tokens.next();
+            predicate = predicate.or(Self::parse_and(tokens));
+        }
+        predicate
+    }
+
+    // Two conditions without and()/or() between them are joined with AND
+    fn parse_and(tokens: &mut Tokens<T>) -> Predicate<T> {
+        let mut predicate = Self::parse_primary(tokens);
+        loop {
+            match tokens.peek() {
+                Some(ConditionToken::Logic(LogicOperator::And)) => {
+                    tokens.next();
+                }
+                Some(ConditionToken::Predicate(_)) | Some(ConditionToken::StartGroup) => {}
+                _ => break,
+            }
+            predicate = predicate.and(Self::parse_primary(tokens));
+        }
+        predicate
+    }
+
+    fn parse_primary(tokens: &mut Tokens<T>) -> Predicate<T> {
+        match tokens.next() {
+            Some(ConditionToken::Predicate(predicate)) => predicate,
+            Some(ConditionToken::StartGroup) => {
+                let inner = Self::parse_or(tokens);
+                match tokens.next() {
+                    Some(ConditionToken::EndGroup) => Predicate::Group(Box::new(inner)),
+                    _ => panic!("start_group() without matching end_group() in where clause"),
+                }
+            }
+            _ => panic!("Expected a condition in where clause"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Select<T> {
+    stmt: SelectStmt<T>,
+    dialect: DatabaseDialect,
+}
+
+impl<T> Select<T> {
+    pub fn new(dialect: DatabaseDialect) -> Self {
+        Self {
+            stmt: SelectStmt::new(),
+            dialect,
+        }
+    }
+
+    pub fn from_statement(stmt: SelectStmt<T>, dialect: DatabaseDialect) -> Self {
+        Self { stmt, dialect }
+    }
+
+    pub fn dialect(&self) -> DatabaseDialect {
+        self.dialect
+    }
+
+    pub fn f(
+        self,
+        field: &str,
+        table_alias: Option<&str>,
+        field_alias: Option<&str>,
+        aggregate_fn: SqlAggregateFunction,
+        enclose_field_name: bool,
+    ) -> Self {
+        let table_alias = table_alias.map(str::to_string);
+        let column = if field == "*" && !enclose_field_name {
+            Expr::Wildcard { table_alias }
+        } else {
+            Expr::Column {
+                table_alias,
+                name: field.to_string(),
+                quoted: enclose_field_name,
+            }
+        };
+        self.item(SelectItem {
+            expr: column.aggregate(aggregate_fn, AggregateOptions::default()),
+            alias: field_alias.map(str::to_string),
+        })
+    }
+
+    pub fn aggregate(
+        self,
+        aggregate_fn: SqlAggregateFunction,
+        field: &str,
+        table_alias: Option<&str>,
+        field_alias: Option<&str>,
+        options: AggregateOptions,
+    ) -> Self {
+        if options.filter.is_some() && !aggregate_fn.is_aggregate() {
+            panic!(
+                "FILTER applies to aggregate functions only, got {}",
+                aggregate_fn
+            );
+        }
+        self.item(SelectItem {
+            expr: Expr::column(table_alias, field).aggregate(aggregate_fn, options),
+            alias: field_alias.map(str::to_string),
+        })
+    }
+
+    pub fn fn_static(
+        self,
+        fn_name: &str,
+        param_values: &[&str],
+        field_alias: Option<&str>,
+    ) -> Self {
+        self.item(SelectItem {
+            expr: Expr::Function {
+                name: fn_name.to_string(),
+                args: param_values
+                    .iter()
+                    .map(|v| Expr::Raw(v.to_string()))
+                    .collect(),
+            },
+            alias: field_alias.map(str::to_string),
+        })
+    }
+
+    pub fn fn_dynamic(
+        self,
+        fn_name: &str,
+        parameter_list_format: &str,
+        param_values: Vec<T>,
+        static_param_values: Vec<String>,
+        field_alias: Option<&str>,
+    ) -> Self {
+        self.item(SelectItem {
+            expr: Expr::Function {
+                name: fn_name.to_string(),
+                args: Expr::function_arguments(
+                    parameter_list_format,
+                    param_values,
+                    static_param_values,
+                ),
+            },
+            alias: field_alias.map(str::to_string),
+        })
+    }
+
+    pub fn item(mut self, item: SelectItem<T>) -> Self {
+        self.stmt.items.push(item);
+        self.resolve_table_aliases()
+    }
+
+    pub fn column<V>(self, column: Column<V>) -> Self {
+        self.item(SelectItem {
+            expr: column.expr(),
+            alias: None,
+        })
+    }
+
+    pub fn column_as<V>(self, column: Column<V>, alias: &str) -> Self {
+        self.item(SelectItem {
+            expr: column.expr(),
+            alias: Some(alias.to_string()),
+        })
+    }
+
+    // Every field of the entity selected under its field name, qualified
+    // like typed columns
+    pub fn select_all<E: NvTable>(self) -> Self {
+        E::fields().iter().fold(self, |select, (column, field)| {
+            select.item(SelectItem {
+                expr: Expr::column(Some(E::NAME), column),
+                alias: Some(field.to_string()),
+            })
+        })
+    }
+
+    pub fn from(mut self, build: impl FnOnce(FromClause<T>) -> FromClause<T>) -> Self {
+        self.stmt.from.extend(build(FromClause::new()).tables);
+        self.resolve_table_aliases()
+    }
+
+    pub fn from_schema<Tb: Table>(self, table: Tb, table_alias: Option<&str>) -> Self {
+        self.from(|f| f.add_schema_table(table, table_alias))
+    }
+
+    pub fn from_table(self, table_name: &str, table_alias: Option<&str>) -> Self {
+        self.from(|f| f.add_table_with_alias(table_name, table_alias))
+    }
+
+    pub fn join(
+        self,
+        join_type: SqlJoinType,
+        table_name: &str,
+        table_alias: Option<&str>,
+        on: impl FnOnce(WhereClause<T>) -> WhereClause<T>,
+    ) -> Self {
+        self.join_table(
+            join_type,
+            TableRef::Table {
+                name: table_name.to_string(),
+                alias: table_alias.map(str::to_string),
+                quoted: false,
+            },
+            on,
+        )
+    }
+
+    pub fn join_schema<Tb: Table>(
+        self,
+        join_type: SqlJoinType,
+        _table: Tb,
+        table_alias: Option<&str>,
+        on: impl FnOnce(WhereClause<T>) -> WhereClause<T>,
+    ) -> Self {
+        self.join(join_type, Tb::NAME, table_alias, on)
+    }
+
+    pub fn join_subquery(
+        self,
+        join_type: SqlJoinType,
+        select: Select<T>,
+        table_alias: &str,
+        on: impl FnOnce(WhereClause<T>) -> WhereClause<T>,
+    ) -> Self {
+        self.join_table(
+            join_type,
+            TableRef::Subquery {
+                query: Box::new(select.build()),
+                alias: table_alias.to_string(),
+            },
+            on,
+        )
+    }
+
+    fn join_table(
+        mut self,
+        join_type: SqlJoinType,
+        table: TableRef<T>,
+        on: impl FnOnce(WhereClause<T>) -> WhereClause<T>,
+    ) -> Self {
+        self.stmt.joins.push(Join {
+            join_type,
+            table,
+            on: on(WhereClause::new()).build(),
+        });
+        self.resolve_table_aliases()
+    }
+
+    // Conditions added by repeated calls are joined with AND
+    pub fn where_clause(mut self, build: impl FnOnce(WhereClause<T>) -> WhereClause<T>) -> Self {
+        if let Some(predicate) = build(WhereClause::new()).build() {
+            self.stmt.add_selection(predicate);
+        }
+        self.resolve_table_aliases()
+    }
+
+    pub fn order_by(
+        self,
+        field_name: &str,
+        table_alias: Option<&str>,
+        sort_type: SortType,
+    ) -> Self {
+        self.order_by_item(OrderByItem {
+            expr: Expr::column(table_alias, field_name),
+            sort: Some(sort_type),
+        })
+    }
+
+    pub fn order_by_column<V>(self, column: Column<V>, sort_type: SortType) -> Self {
+        self.order_by_item(OrderByItem {
+            expr: column.expr(),
+            sort: Some(sort_type),
+        })
+    }
+
+    pub fn order_by_item(mut self, item: OrderByItem<T>) -> Self {
+        self.stmt.order_by.push(item);
+        self.resolve_table_aliases()
+    }
+
+    // Table names qualifying columns become the alias of the table, see
+    // schema.rs
+    fn resolve_table_aliases(mut self) -> Self {
+        resolve_table_aliases(&mut self.stmt);
+        self
+    }
+
+    pub fn limit(mut self, limit: u64) -> Self {
+        self.stmt.limit = Some(limit);
+        self
+    }
+
+    // Dialects that page with OFFSET ... FETCH need the ORDER BY first
+    pub fn offset(mut self, offset: u64) -> Result<Self, RenderError> {
+        if self.stmt.order_by.is_empty() && paging_requires_order_by(&self.dialect, Some(offset)) {
+            return Err(RenderError::OffsetWithoutOrderBy {
+                dialect: self.dialect,
+            });
+        }
+        self.stmt.offset = Some(offset);
+        Ok(self)
+    }
+
+    pub fn limit_offset(self, limit: u64, offset: u64) -> Result<Self, RenderError> {
+        self.limit(limit).offset(offset)
+    }
+
+    pub fn statement(&self) -> &SelectStmt<T> {
+        &self.stmt
+    }
+
+    pub fn statement_mut(&mut self) -> &mut SelectStmt<T> {
+        &mut self.stmt
+    }
+
+    pub fn rewrite<V: MutVisitor<T> + ?Sized>(mut self, visitor: &mut V) -> Self {
+        self.stmt.accept_mut(visitor);
+        self
+    }
+
+    pub fn build(self) -> SelectStmt<T> {
+        self.stmt
+    }
+
+    pub fn render(&self, pretty_print: bool) -> RenderedQuery<'_, T> {
+        Renderer::new(self.dialect)
+            .pretty_print(pretty_print)
+            .render(&self.stmt)
+    }
+
+    pub fn render_with(&self, format: &FormatOptions) -> RenderedQuery<'_, T> {
+        Renderer::new(self.dialect)
+            .format(*format)
+            .render(&self.stmt)
+    }
+
+    pub fn generate_query(&self, pretty_print: bool) -> String {
+        self.render(pretty_print).sql
+    }
+
+    pub fn generate_query_with(&self, format: &FormatOptions) -> String {
+        self.render_with(format).sql
+    }
+
+    // Fails where `render` panics, for statements that were not built here
+    // such as a loaded SavedQuery
+    pub fn validate(&self) -> Result<(), RenderError> {
+        Renderer::new(self.dialect).validate(&self.stmt)
+    }
+
+    pub fn try_render(&self, pretty_print: bool) -> Result<RenderedQuery<'_, T>, RenderError> {
+        self.validate()?;
+        Ok(self.render(pretty_print))
+    }
+
+    pub fn try_generate_query(&self, pretty_print: bool) -> Result<String, RenderError> {
+        Ok(self.try_render(pretty_print)?.sql)
+    }
+
+    // Renders only when every identifier of the query is in `allowlist`
+    pub fn render_checked(
+        &self,
+        allowlist: &Allowlist,
+        pretty_print: bool,
+    ) -> Result<RenderedQuery<'_, T>, AllowlistError> {
+        allowlist.check(&self.stmt)?;
+        Ok(self.render(pretty_print))
+    }
+
+    pub fn generate_query_checked(
+        &self,
+        allowlist: &Allowlist,
+        pretty_print: bool,
+    ) -> Result<String, AllowlistError> {
+        Ok(self.render_checked(allowlist, pretty_print)?.sql)
+    }
+
+    // Bound values in placeholder order, matching `generate_query`. Queries
+    // with named parameters are bound with `render(..).bind(..)` instead
+    pub fn values(&self) -> Vec<&T> {
+        self.render(false).parameters
+    }
+
+    // Names of the select list in result rows. Unaliased items other than
+    // columns are named by the database and left empty
+    pub fn column_names(&self) -> Vec<String> {
+        self.stmt
+            .items
+            .iter()
+            .map(|item| match (&item.alias, &item.expr) {
+                (Some(alias), _) => alias.clone(),
+                (None, Expr::Column { name, .. }) => name.clone(),
+                (None, _) => String::new(),
+            })
+            .collect()
+    }
+}
+
+impl<T: ToSqlLiteral> Select<T> {
+    // Query with the values inlined as literals, for logs only
+    pub fn to_debug_sql(&self) -> String {
+        self.to_debug_sql_with(&DebugSqlOptions::default())
+    }
+
+    pub fn to_debug_sql_with(&self, options: &DebugSqlOptions) -> String {
+        Renderer::new(self.dialect).render_debug(&self.stmt, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{DefaultPostgresParamType, NvSelect};
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    fn build_orders() -> Select<DefaultPostgresParamType> {
+        Select::new(DatabaseDialect::PostgreSQL)
+            .f("id", Some("o"), None, SqlAggregateFunction::None, false)
+            .fn_dynamic(
+                "COALESCE",
+                "sv",
+                vec![DefaultPostgresParamType::String("n/a".to_string())],
+                vec!["o.note".to_string()],
+                Some("note"),
+            )
+            .from(|f| {
+                f.add_table_with_alias("orders", Some("o")).add_subquery(
+                    Select::new(DatabaseDialect::PostgreSQL)
+                        .f("id", None, None, SqlAggregateFunction::None, false)
+                        .from_table("customers", None)
+                        .where_clause(|w| {
+                            w.add_condition(
+                                "active",
+                                SqlOperator::Equal,
+                                DefaultPostgresParamType::Bool(true),
+                            )
+                            .unwrap()
+                        }),
+                    "c",
+                )
+            })
+            .join(SqlJoinType::InnerJoin, "regions", Some("r"), |on| {
+                on.add_column_condition("r.code", SqlOperator::Equal, "o.region")
+            })
+            .where_clause(|w| {
+                w.add_condition(
+                    "o.total",
+                    SqlOperator::Greater,
+                    DefaultPostgresParamType::Int(10),
+                )
+                .unwrap()
+                .and()
+                .add_subquery(
+                    "o.region",
+                    SqlOperator::In,
+                    Select::new(DatabaseDialect::PostgreSQL)
+                        .f("code", None, None, SqlAggregateFunction::None, false)
+                        .from_table("regions", None)
+                        .where_clause(|w| {
+                            w.add_condition_in(
+                                "zone",
+                                vec![
+                                    DefaultPostgresParamType::Int(1),
+                                    DefaultPostgresParamType::Int(2),
+                                ],
+                            )
+                        }),
+                )
+            })
+            .where_clause(|w| {
+                w.add_condition(
+                    "o.state",
+                    SqlOperator::Equal,
+                    DefaultPostgresParamType::Int(1),
+                )
+                .unwrap()
+                .or()
+                .add_condition(
+                    "o.state",
+                    SqlOperator::Equal,
+                    DefaultPostgresParamType::Int(2),
+                )
+                .unwrap()
+            })
+            .order_by("id", Some("o"), SortType::Descending)
+            .limit(5)
+    }
+
+    #[test]
+    fn test_owned_select_renders_placeholders_in_order() {
+        let select = build_orders();
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT o.id, COALESCE(o.note, $1) AS note \
+             FROM orders AS o, (SELECT id FROM customers WHERE active = $2) AS c \
+             INNER JOIN regions AS r ON r.code = o.region \
+             WHERE o.total > $3 AND o.region IN (SELECT code FROM regions WHERE zone IN ($4, $5)) \
+             AND (o.state = $6 OR o.state = $7) \
+             ORDER BY o.id DESC LIMIT 5"
+        );
+        assert_eq!(
+            select.values().into_iter().cloned().collect::<Vec<_>>(),
+            vec![
+                DefaultPostgresParamType::String("n/a".to_string()),
+                DefaultPostgresParamType::Bool(true),
+                DefaultPostgresParamType::Int(10),
+                DefaultPostgresParamType::Int(1),
+                DefaultPostgresParamType::Int(2),
+                DefaultPostgresParamType::Int(1),
+                DefaultPostgresParamType::Int(2),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_where_clause_precedence_and_groups() {
+        let predicate = WhereClause::<DefaultPostgresParamType>::new()
+            .start_group()
+            .add_column_condition("a", SqlOperator::Equal, "b")
+            .or()
+            .add_column_condition("c", SqlOperator::Equal, "d")
+            .end_group()
+            .and()
+            .add_column_condition("e", SqlOperator::Equal, "f")
+            .or()
+            .add_column_condition("g", SqlOperator::Equal, "h")
+            .build()
+            .unwrap();
+
+        assert!(matches!(
+            &predicate,
+            Predicate::Or(terms) if matches!(&terms[0], Predicate::And(factors)
+                if matches!(factors[0], Predicate::Group(_)))
+        ));
+    }
+
+    #[test]
+    fn test_single_value_conditions_reject_between_and_in() {
+        let id = Column::<i64>::new("users", "id");
+        let errors = [
+            WhereClause::<DefaultPostgresParamType>::new()
+                .add_condition(
+                    "u.id",
+                    SqlOperator::Between,
+                    DefaultPostgresParamType::Int(1),
+                )
+                .unwrap_err(),
+            WhereClause::<DefaultPostgresParamType>::new()
+                .condition(id, SqlOperator::In, 1)
+                .unwrap_err(),
+            WhereClause::<DefaultPostgresParamType>::new()
+                .add_named_condition("u.id", SqlOperator::In, "ids")
+                .unwrap_err(),
+        ];
+        assert_eq!(
+            errors.map(|error| error.to_string()),
+            [
+                "BETWEEN takes several values, use the between or in condition on u.id",
+                "IN takes several values, use the between or in condition on users.id",
+                "IN takes several values, use the between or in condition on u.id",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_legacy_select_converts_and_releases_parents() {
+        let legacy = NvSelect::<DefaultPostgresParamType>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "id".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "u.id",
+                &SqlOperator::Greater,
+                DefaultPostgresParamType::Int(1),
+            )
+            .unwrap()
+            .and()
+            .add_subquery("u.role".to_string(), SqlOperator::In, String::new())
+            .f(
+                "code".to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("roles", &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(
+                "level",
+                &SqlOperator::Less,
+                DefaultPostgresParamType::Int(3),
+            )
+            .unwrap()
+            .end_where_block()
+            .end_subquery_inside_where_condition()
+            .end_where_block();
+
+        let owned = legacy.to_select();
+        assert_eq!(owned.generate_query(false), legacy.generate_query(false));
+        assert_eq!(owned.values(), legacy.values());
+
+        // Every block was closed, so the tree holds no cycles
+        let weak = Arc::downgrade(&legacy);
+        drop(legacy);
+        assert!(weak.upgrade().is_none());
+    }
+
+    #[test]
+    fn test_named_parameters_are_deduplicated_and_bound_later() {
+        let build = |dialect| {
+            Select::<DefaultPostgresParamType>::new(dialect)
+                .f("id", Some("o"), None, SqlAggregateFunction::None, false)
+                .from_table("orders", Some("o"))
+                .where_clause(|w| {
+                    w.add_named_condition("o.tenant_id", SqlOperator::Equal, ":tenant_id")
+                        .unwrap()
+                        .and()
+                        .add_condition(
+                            "o.status",
+                            SqlOperator::Equal,
+                            DefaultPostgresParamType::String("paid".to_string()),
+                        )
+                        .unwrap()
+                        .and()
+                        .add_subquery(
+                            "o.user_id",
+                            SqlOperator::In,
+                            Select::new(dialect)
+                                .f("id", None, None, SqlAggregateFunction::None, false)
+                                .from_table("users", None)
+                                .where_clause(|w| {
+                                    w.add_named_condition(
+                                        "tenant_id",
+                                        SqlOperator::Equal,
+                                        "tenant_id",
+                                    )
+                                    .unwrap()
+                                }),
+                        )
+                })
+        };
+        let tenant = DefaultPostgresParamType::Int(7);
+        let paid = DefaultPostgresParamType::String("paid".to_string());
+        let values = HashMap::from([("tenant_id".to_string(), tenant.clone())]);
+
+        let postgres = build(DatabaseDialect::PostgreSQL);
+        let rendered = postgres.render(false);
+        assert_eq!(
+            rendered.sql,
+            "SELECT o.id FROM orders AS o WHERE o.tenant_id = $1 AND o.status = $2 \
+             AND o.user_id IN (SELECT id FROM users WHERE tenant_id = $1)"
+        );
+        assert_eq!(rendered.parameter_names(), vec!["tenant_id"]);
+        assert_eq!(rendered.bind(&values).unwrap(), vec![&tenant, &paid]);
+        assert_eq!(
+            rendered.bind(&HashMap::new()).unwrap_err().to_string(),
+            "no value bound for parameter :tenant_id"
+        );
+
+        let oracle = build(DatabaseDialect::Oracle);
+        let rendered = oracle.render(false);
+        assert_eq!(
+            rendered.sql,
+            "SELECT o.id FROM orders o WHERE o.tenant_id = :tenant_id AND o.status = :2 \
+             AND o.user_id IN (SELECT id FROM users WHERE tenant_id = :tenant_id)"
+        );
+        assert_eq!(
+            rendered.bind_named(&values).unwrap(),
+            vec![("tenant_id".to_string(), &tenant), ("2".to_string(), &paid)]
+        );
+
+        let mysql = build(DatabaseDialect::MySQL);
+        let rendered = mysql.render(false);
+        assert_eq!(
+            rendered.sql,
+            "SELECT o.id FROM orders AS o WHERE o.tenant_id = ? AND o.status = ? \
+             AND o.user_id IN (SELECT id FROM users WHERE tenant_id = ?)"
+        );
+        assert_eq!(
+            rendered.bind(&values).unwrap(),
+            vec![&tenant, &paid, &tenant]
+        );
+
+        let parsed = Select::<DefaultPostgresParamType>::parse(
+            "SELECT id FROM orders WHERE tenant_id = :tenant_id AND total > 10",
+            DatabaseDialect::PostgreSQL,
+        )
+        .unwrap();
+        assert_eq!(
+            parsed.generate_query(false),
+            "SELECT id FROM orders WHERE tenant_id = $1 AND total > $2"
+        );
+    }
+}
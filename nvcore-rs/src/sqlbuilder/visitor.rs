@@ -0,0 +1,408 @@
+// Read-only and mutable traversal over the select syntax tree
+//
+// Every visit_* method defaults to the matching walk_* function, which recurses
+// into the children, including FROM, JOIN and WHERE subqueries. Override a
+// method to inspect or rewrite a node and call the walk_* function from it to
+// keep descending.
+
+use crate::sqlbuilder::{Expr, Join, OrderByItem, Predicate, SelectItem, SelectStmt, TableRef};
+
+pub trait Visitor<T> {
+    fn visit_select(&mut self, select: &SelectStmt<T>) {
+        walk_select(self, select);
+    }
+
+    fn visit_select_item(&mut self, item: &SelectItem<T>) {
+        walk_select_item(self, item);
+    }
+
+    fn visit_table_ref(&mut self, table: &TableRef<T>) {
+        walk_table_ref(self, table);
+    }
+
+    fn visit_join(&mut self, join: &Join<T>) {
+        walk_join(self, join);
+    }
+
+    fn visit_predicate(&mut self, predicate: &Predicate<T>) {
+        walk_predicate(self, predicate);
+    }
+
+    fn visit_order_by_item(&mut self, item: &OrderByItem<T>) {
+        walk_order_by_item(self, item);
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<T>) {
+        walk_expr(self, expr);
+    }
+}
+
+pub fn walk_select<T, V: Visitor<T> + ?Sized>(visitor: &mut V, select: &SelectStmt<T>) {
+    for item in &select.items {
+        visitor.visit_select_item(item);
+    }
+    for table in &select.from {
+        visitor.visit_table_ref(table);
+    }
+    for join in &select.joins {
+        visitor.visit_join(join);
+    }
+    if let Some(selection) = &select.selection {
+        visitor.visit_predicate(selection);
+    }
+    for item in &select.order_by {
+        visitor.visit_order_by_item(item);
+    }
+}
+
+pub fn walk_select_item<T, V: Visitor<T> + ?Sized>(visitor: &mut V, item: &SelectItem<T>) {
+    visitor.visit_expr(&item.expr);
+}
+
+pub fn walk_table_ref<T, V: Visitor<T> + ?Sized>(visitor: &mut V, table: &TableRef<T>) {
+    if let TableRef::Subquery { query, .. } = table {
+        visitor.visit_select(query);
+    }
+}
+
+pub fn walk_join<T, V: Visitor<T> + ?Sized>(visitor: &mut V, join: &Join<T>) {
+    visitor.visit_table_ref(&join.table);
+    if let Some(on) = &join.on {
+        visitor.visit_predicate(on);
+    }
+}
+
+pub fn walk_predicate<T, V: Visitor<T> + ?Sized>(visitor: &mut V, predicate: &Predicate<T>) {
+    match predicate {
+        Predicate::Compare { left, right, .. } => {
+            visitor.visit_expr(left);
+            for expr in right {
+                visitor.visit_expr(expr);
+            }
+        }
+        Predicate::And(predicates) | Predicate::Or(predicates) => {
+            for predicate in predicates {
+                visitor.visit_predicate(predicate);
+            }
+        }
+        Predicate::Group(inner) => visitor.visit_predicate(inner),
+    }
+}
+
+pub fn walk_order_by_item<T, V: Visitor<T> + ?Sized>(visitor: &mut V, item: &OrderByItem<T>) {
+    visitor.visit_expr(&item.expr);
+}
+
+pub fn walk_expr<T, V: Visitor<T> + ?Sized>(visitor: &mut V, expr: &Expr<T>) {
+    match expr {
+        Expr::Function { args, .. } => {
+            for arg in args {
+                visitor.visit_expr(arg);
+            }
+        }
+        Expr::Aggregate { arg, .. } => visitor.visit_expr(arg),
+        Expr::Subquery(query) => visitor.visit_select(query),
+        Expr::Column { .. }
+        | Expr::Param(_)
+        | Expr::Named(_)
+        | Expr::Raw(_)
+        | Expr::Literal(_)
+        | Expr::Wildcard { .. } => {}
+    }
+}
+
+pub trait MutVisitor<T> {
+    fn visit_select_mut(&mut self, select: &mut SelectStmt<T>) {
+        walk_select_mut(self, select);
+    }
+
+    fn visit_select_item_mut(&mut self, item: &mut SelectItem<T>) {
+        walk_select_item_mut(self, item);
+    }
+
+    fn visit_table_ref_mut(&mut self, table: &mut TableRef<T>) {
+        walk_table_ref_mut(self, table);
+    }
+
+    fn visit_join_mut(&mut self, join: &mut Join<T>) {
+        walk_join_mut(self, join);
+    }
+
+    fn visit_predicate_mut(&mut self, predicate: &mut Predicate<T>) {
+        walk_predicate_mut(self, predicate);
+    }
+
+    fn visit_order_by_item_mut(&mut self, item: &mut OrderByItem<T>) {
+        walk_order_by_item_mut(self, item);
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr<T>) {
+        walk_expr_mut(self, expr);
+    }
+}
+
+pub fn walk_select_mut<T, V: MutVisitor<T> + ?Sized>(visitor: &mut V, select: &mut SelectStmt<T>) {
+    for item in &mut select.items {
+        visitor.visit_select_item_mut(item);
+    }
+    for table in &mut select.from {
+        visitor.visit_table_ref_mut(table);
+    }
+    for join in &mut select.joins {
+        visitor.visit_join_mut(join);
+    }
+    if let Some(selection) = &mut select.selection {
+        visitor.visit_predicate_mut(selection);
+    }
+    for item in &mut select.order_by {
+        visitor.visit_order_by_item_mut(item);
+    }
+}
+
+pub fn walk_select_item_mut<T, V: MutVisitor<T> + ?Sized>(
+    visitor: &mut V,
+    item: &mut SelectItem<T>,
+) {
+    visitor.visit_expr_mut(&mut item.expr);
+}
+
+pub fn walk_table_ref_mut<T, V: MutVisitor<T> + ?Sized>(visitor: &mut V, table: &mut TableRef<T>) {
+    if let TableRef::Subquery { query, .. } = table {
+        visitor.visit_select_mut(query);
+    }
+}
+
+pub fn walk_join_mut<T, V: MutVisitor<T> + ?Sized>(visitor: &mut V, join: &mut Join<T>) {
+    visitor.visit_table_ref_mut(&mut join.table);
+    if let Some(on) = &mut join.on {
+        visitor.visit_predicate_mut(on);
+    }
+}
+
+pub fn walk_predicate_mut<T, V: MutVisitor<T> + ?Sized>(
+    visitor: &mut V,
+    predicate: &mut Predicate<T>,
+) {
+    match predicate {
+        Predicate::Compare { left, right, .. } => {
+            visitor.visit_expr_mut(left);
+            for expr in right {
+                visitor.visit_expr_mut(expr);
+            }
+        }
+        Predicate::And(predicates) | Predicate::Or(predicates) => {
+            for predicate in predicates {
+                visitor.visit_predicate_mut(predicate);
+            }
+        }
+        Predicate::Group(inner) => visitor.visit_predicate_mut(inner),
+    }
+}
+
+pub fn walk_order_by_item_mut<T, V: MutVisitor<T> + ?Sized>(
+    visitor: &mut V,
+    item: &mut OrderByItem<T>,
+) {
+    visitor.visit_expr_mut(&mut item.expr);
+}
+
+pub fn walk_expr_mut<T, V: MutVisitor<T> + ?Sized>(visitor: &mut V, expr: &mut Expr<T>) {
+    match expr {
+        Expr::Function { args, .. } => {
+            for arg in args {
+                visitor.visit_expr_mut(arg);
+            }
+        }
+        Expr::Aggregate { arg, .. } => visitor.visit_expr_mut(arg),
+        Expr::Subquery(query) => visitor.visit_select_mut(query),
+        Expr::Column { .. }
+        | Expr::Param(_)
+        | Expr::Named(_)
+        | Expr::Raw(_)
+        | Expr::Literal(_)
+        | Expr::Wildcard { .. } => {}
+    }
+}
+
+impl<T> SelectStmt<T> {
+    pub fn accept<V: Visitor<T> + ?Sized>(&self, visitor: &mut V) {
+        visitor.visit_select(self);
+    }
+
+    pub fn accept_mut<V: MutVisitor<T> + ?Sized>(&mut self, visitor: &mut V) {
+        visitor.visit_select_mut(self);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, NvSelect, Select, SqlAggregateFunction,
+        SqlJoinType, SqlOperator,
+    };
+
+    type Param = DefaultPostgresParamType;
+
+    fn build_report() -> Select<Param> {
+        Select::new(DatabaseDialect::PostgreSQL)
+            .f("name", Some("u"), None, SqlAggregateFunction::None, false)
+            .from(|f| {
+                f.add_table_with_alias("users", Some("u")).add_subquery(
+                    Select::new(DatabaseDialect::PostgreSQL)
+                        .f("user_id", None, None, SqlAggregateFunction::None, false)
+                        .from_table("orders", None),
+                    "o",
+                )
+            })
+            .join(SqlJoinType::LeftJoin, "teams", Some("t"), |on| {
+                on.add_column_condition("t.id", SqlOperator::Equal, "u.team_id")
+            })
+            .where_clause(|w| {
+                w.add_condition("u.id", SqlOperator::Equal, Param::Int(1))
+                    .unwrap()
+                    .or()
+                    .add_subquery(
+                        "u.id",
+                        SqlOperator::In,
+                        Select::new(DatabaseDialect::PostgreSQL)
+                            .f("user_id", None, None, SqlAggregateFunction::None, false)
+                            .from_table("admins", None),
+                    )
+            })
+    }
+
+    #[derive(Default)]
+    struct ColumnCollector {
+        columns: Vec<String>,
+    }
+
+    impl<T> Visitor<T> for ColumnCollector {
+        fn visit_expr(&mut self, expr: &Expr<T>) {
+            if let Expr::Column {
+                table_alias, name, ..
+            } = expr
+            {
+                self.columns.push(match table_alias {
+                    Some(alias) => format!("{}.{}", alias, name),
+                    None => name.clone(),
+                });
+            }
+            walk_expr(self, expr);
+        }
+    }
+
+    struct RenameAlias<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl<T> MutVisitor<T> for RenameAlias<'_> {
+        fn visit_table_ref_mut(&mut self, table: &mut TableRef<T>) {
+            if let TableRef::Table {
+                alias: Some(alias), ..
+            } = table
+            {
+                if alias == self.from {
+                    *alias = self.to.to_string();
+                }
+            }
+            walk_table_ref_mut(self, table);
+        }
+
+        fn visit_expr_mut(&mut self, expr: &mut Expr<T>) {
+            if let Expr::Column {
+                table_alias: Some(alias),
+                ..
+            } = expr
+            {
+                if alias == self.from {
+                    *alias = self.to.to_string();
+                }
+            }
+            walk_expr_mut(self, expr);
+        }
+    }
+
+    // Restricts every SELECT, subqueries included, to one tenant
+    struct TenantFilter {
+        tenant_id: i32,
+    }
+
+    impl MutVisitor<Param> for TenantFilter {
+        fn visit_select_mut(&mut self, select: &mut SelectStmt<Param>) {
+            walk_select_mut(self, select);
+            select.add_selection(Predicate::Compare {
+                left: Expr::column(None, "tenant_id"),
+                op: SqlOperator::Equal,
+                right: vec![Expr::Param(Param::Int(self.tenant_id))],
+            });
+        }
+    }
+
+    #[test]
+    fn test_visitor_collects_columns_from_subqueries() {
+        let mut collector = ColumnCollector::default();
+        build_report().statement().accept(&mut collector);
+
+        assert_eq!(
+            collector.columns,
+            vec![
+                "u.name",
+                "user_id",
+                "t.id",
+                "u.team_id",
+                "u.id",
+                "u.id",
+                "user_id"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_mut_visitor_renames_alias_and_adds_tenant_predicate() {
+        let mut select = build_report();
+        select.statement_mut().accept_mut(&mut RenameAlias {
+            from: "u",
+            to: "usr",
+        });
+        select
+            .statement_mut()
+            .accept_mut(&mut TenantFilter { tenant_id: 7 });
+
+        assert_eq!(
+            select.generate_query(false),
+            "SELECT usr.name \
+             FROM users AS usr, (SELECT user_id FROM orders WHERE tenant_id = $1) AS o \
+             LEFT JOIN teams AS t ON t.id = usr.team_id \
+             WHERE (usr.id = $2 OR usr.id IN (SELECT user_id FROM admins WHERE tenant_id = $3)) \
+             AND tenant_id = $4"
+        );
+        assert_eq!(select.values().len(), 4);
+    }
+
+    #[test]
+    fn test_legacy_select_accepts_visitors() {
+        let legacy = NvSelect::<Param>::new(DatabaseDialect::PostgreSQL)
+            .f(
+                "name".to_string(),
+                Some("u".to_string()),
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias("users", &Some("u".to_string()))
+            .end_from_table_block();
+
+        let mut collector = ColumnCollector::default();
+        legacy.accept(&mut collector);
+        assert_eq!(collector.columns, vec!["u.name"]);
+
+        let rewritten = legacy.rewrite(&mut TenantFilter { tenant_id: 1 });
+        assert_eq!(
+            rewritten.generate_query(false),
+            "SELECT u.name FROM users AS u WHERE tenant_id = $1"
+        );
+    }
+}
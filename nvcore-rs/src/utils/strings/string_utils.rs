@@ -1,6 +1,3 @@
 pub fn indent_space(level: u32) -> String {
-    std::iter::repeat("  ")
-        .take(level as usize)
-        .collect::<String>()
-        .clone()
-}
\ No newline at end of file
+    "  ".repeat(level as usize)
+}
@@ -0,0 +1,412 @@
+// Fluent validator
+//
+// A Validator<T> is a list of rules declared once and run against any number
+// of values:
+//
+//     let validator = Validator::<User>::new()
+//         .rule_for("email", |u| &u.email).not_empty().email().max_len(255)
+//         .rule_for_option("age", |u| &u.age).range(0..=120)
+//         .build();
+//
+// Messages come from the English catalog unless the validator is run with
+// `validate_in` or `validate_into`, see messages.rs.
+//
+// Validators compose: `include` adds the rules of another validator of the
+// same type and `set_validator` runs a validator on a nested value, with its
+// errors reported below the path of the rule.
+
+use crate::validator::{PropertyRule, Rule, RuleBuilder, ValidationResult, Values, ValuesFn};
+
+pub struct Validator<T> {
+    rules: Vec<Box<dyn Rule<T> + Send + Sync>>,
+}
+
+impl<T> Default for Validator<T> {
+    fn default() -> Self {
+        Self { rules: Vec::new() }
+    }
+}
+
+impl<T: 'static> Validator<T> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn rule_for<P, F>(self, name: &str, property: F) -> RuleBuilder<T, P>
+    where
+        P: ?Sized + 'static,
+        F: Fn(&T) -> &P + Send + Sync + 'static,
+    {
+        let values: ValuesFn<T, P> = Box::new(move |value| Values::One(property(value)));
+        RuleBuilder::new(self, PropertyRule::new(name, values))
+    }
+
+    // Checks run only when the value is Some, see RuleBuilder::required
+    pub fn rule_for_option<P, F>(self, name: &str, property: F) -> RuleBuilder<T, P>
+    where
+        P: 'static,
+        F: Fn(&T) -> &Option<P> + Send + Sync + 'static,
+    {
+        let values: ValuesFn<T, P> = Box::new(move |value| match property(value) {
+            Some(property) => Values::One(property),
+            None => Values::Missing,
+        });
+        RuleBuilder::new(self, PropertyRule::new(name, values))
+    }
+
+    // Checks run on every element, reported as `name[index]`
+    pub fn rule_for_each<P, F>(self, name: &str, items: F) -> RuleBuilder<T, P>
+    where
+        P: 'static,
+        F: Fn(&T) -> &[P] + Send + Sync + 'static,
+    {
+        let values: ValuesFn<T, P> =
+            Box::new(move |value| Values::Many(items(value).iter().collect()));
+        RuleBuilder::new(self, PropertyRule::new(name, values))
+    }
+
+    pub fn include<R: Rule<T> + Send + Sync + 'static>(mut self, rule: R) -> Self {
+        self.rules.push(Box::new(rule));
+        self
+    }
+
+    pub fn validate(&self, value: &T) -> ValidationResult {
+        self.validate_into(value, ValidationResult::new())
+    }
+
+    // Messages in `locale` from the built-in English and Indonesian catalogs
+    pub fn validate_in(&self, value: &T, locale: &str) -> ValidationResult {
+        self.validate_into(value, ValidationResult::localized(locale))
+    }
+
+    // Runs the rules into `result`, whose locale and catalog decide the
+    // messages
+    pub fn validate_into(&self, value: &T, mut result: ValidationResult) -> ValidationResult {
+        Rule::validate(self, value, "", &mut result);
+        result
+    }
+}
+
+impl<T> Rule<T> for Validator<T> {
+    fn validate(&self, value: &T, path: &str, result: &mut ValidationResult) {
+        for rule in &self.rules {
+            rule.validate(value, path, result);
+        }
+    }
+}
+
+// Types that know their own validator, usually implemented with
+// #[derive(NvValidate)] when the `derive` feature is enabled
+pub trait NvValidate: Sized + 'static {
+    fn validator() -> Validator<Self>;
+
+    // Runs `run` with the validator of validate and validate_in. It is built
+    // on every call unless the implementation keeps one, as the derive does
+    fn run_validator<R>(run: impl FnOnce(&Validator<Self>) -> R) -> R {
+        run(&Self::validator())
+    }
+
+    fn validate(&self) -> ValidationResult {
+        Self::run_validator(|validator| validator.validate(self))
+    }
+
+    fn validate_in(&self, locale: &str) -> ValidationResult {
+        Self::run_validator(|validator| validator.validate_in(self, locale))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::validator::Messages;
+
+    struct Address {
+        city: String,
+        postal_code: String,
+    }
+
+    struct User {
+        email: String,
+        name: String,
+        age: Option<u32>,
+        is_company: bool,
+        tax_id: String,
+        tags: Vec<String>,
+        addresses: Vec<Address>,
+    }
+
+    fn address_validator() -> Validator<Address> {
+        Validator::<Address>::new()
+            .rule_for("city", |a| &a.city)
+            .not_empty()
+            .rule_for("postal_code", |a| &a.postal_code)
+            .matches(r"^\d{5}$")
+            .build()
+    }
+
+    fn user_validator() -> Validator<User> {
+        let names = Validator::<User>::new()
+            .rule_for("name", |u| &u.name)
+            .length(2, 5)
+            .with_message("{field} needs {min} to {max} characters")
+            .build();
+
+        Validator::<User>::new()
+            .rule_for("email", |u| &u.email)
+            .not_empty()
+            .email()
+            .max_len(255)
+            .rule_for_option("age", |u| &u.age)
+            .required()
+            .range(18..=120)
+            .rule_for("tax_id", |u| &u.tax_id)
+            .not_empty()
+            .when(|u| u.is_company)
+            .rule_for_each("tags", |u| &u.tags)
+            .stop_on_first_failure()
+            .not_empty()
+            .max_len(3)
+            .rule_for_each("addresses", |u| &u.addresses)
+            .set_validator(address_validator())
+            .build()
+            .include(names)
+    }
+
+    #[test]
+    fn test_validator_reports_paths_and_codes() {
+        let validator = user_validator();
+        fn shared<V: Send + Sync>(_: &V) {}
+        shared(&validator);
+        let mut user = User {
+            email: "ana@example.com".to_string(),
+            name: "Ana".to_string(),
+            age: Some(30),
+            is_company: false,
+            tax_id: String::new(),
+            tags: vec!["vip".to_string()],
+            addresses: vec![Address {
+                city: "Bandung".to_string(),
+                postal_code: "40111".to_string(),
+            }],
+        };
+        assert!(validator.validate(&user).is_valid());
+
+        user.email = "ana@".to_string();
+        user.name = "Anastasia".to_string();
+        user.age = None;
+        user.is_company = true;
+        user.tags.push(String::new());
+        user.addresses.push(Address {
+            city: String::new(),
+            postal_code: "4011".to_string(),
+        });
+
+        let result = validator.validate(&user);
+        let codes: Vec<(&str, &str)> = result
+            .errors()
+            .iter()
+            .map(|e| (e.path.as_str(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("email", "email"),
+                ("age", "required"),
+                ("tax_id", "not_empty"),
+                ("tags[1]", "not_empty"),
+                ("addresses[1].city", "not_empty"),
+                ("addresses[1].postal_code", "matches"),
+                ("name", "length"),
+            ]
+        );
+        assert_eq!(
+            result.errors_for("name").next().unwrap().message,
+            "name needs 2 to 5 characters"
+        );
+        assert_eq!(result.errors()[1].to_string(), "age: age is required");
+        assert!(result.has_error("tags[1]", "not_empty"));
+        assert!(result.into_result().is_err());
+
+        user.age = Some(12);
+        let result = validator.validate(&user);
+        let age = result.errors_for("age").next().unwrap();
+        assert_eq!(age.message, "age must be between 18 and 120");
+        assert_eq!(age.param("min"), Some("18"));
+    }
+
+    #[test]
+    fn test_localized_messages() {
+        let validator = Validator::<User>::new()
+            .rule_for("email", |u| &u.email)
+            .email()
+            .with_locale_message("id", "Alamat {field} salah")
+            .rule_for("name", |u| &u.name)
+            .max_len(3)
+            .rule_for_option("age", |u| &u.age)
+            .range(18..=120)
+            .with_message("{field} out of {min}..{max}")
+            .build();
+        let user = User {
+            email: "ana@".to_string(),
+            name: "Anastasia".to_string(),
+            age: Some(12),
+            is_company: false,
+            tax_id: String::new(),
+            tags: Vec::new(),
+            addresses: Vec::new(),
+        };
+        let messages = |result: ValidationResult| -> Vec<String> {
+            result.errors().iter().map(|e| e.message.clone()).collect()
+        };
+
+        assert_eq!(
+            messages(validator.validate(&user)),
+            vec![
+                "email must be a valid email address",
+                "name must have a length of at most 3",
+                "age out of 18..120",
+            ]
+        );
+        assert_eq!(
+            messages(validator.validate_in(&user, "id-ID")),
+            vec![
+                "Alamat email salah",
+                "name harus memiliki panjang maksimal 3",
+                "age out of 18..120",
+            ]
+        );
+
+        // Codes missing from a locale fall back to the English templates
+        let catalog = Messages::builtin().with("jv", "email", "{field} ora bener");
+        let result = ValidationResult::localized("jv").with_catalog(std::sync::Arc::new(catalog));
+        let result = validator.validate_into(&user, result);
+        assert_eq!(result.locale(), "jv");
+        assert_eq!(
+            messages(result),
+            vec![
+                "email ora bener",
+                "name must have a length of at most 3",
+                "age out of 18..120",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bound_messages_name_the_bound() {
+        let validator = Validator::<User>::new()
+            .rule_for_option("age", |u| &u.age)
+            .greater_than(17)
+            .less_than(12)
+            .build();
+        let user = User {
+            email: String::new(),
+            name: String::new(),
+            age: Some(12),
+            is_company: false,
+            tax_id: String::new(),
+            tags: Vec::new(),
+            addresses: Vec::new(),
+        };
+
+        let result = validator.validate(&user);
+        let messages: Vec<&str> = result.errors().iter().map(|e| e.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec!["age must be greater than 17", "age must be less than 12"]
+        );
+        assert_eq!(result.errors()[0].param("expected"), Some("17"));
+    }
+
+    #[cfg(feature = "derive")]
+    #[test]
+    fn test_derive_nv_validate() {
+        use crate::validator::NvValidate;
+
+        fn is_slug(value: &str) -> bool {
+            value.chars().all(|c| c.is_ascii_lowercase() || c == '-')
+        }
+
+        #[derive(NvValidate)]
+        struct Address {
+            #[nv(not_empty)]
+            city: String,
+            #[nv(regex = r"^\d{5}$", message = "{field} must have 5 digits")]
+            postal_code: String,
+        }
+
+        #[derive(NvValidate)]
+        struct Account {
+            #[nv(length(min = 1, max = 10), custom = is_slug)]
+            slug: String,
+            #[nv(required, email)]
+            email: Option<String>,
+            #[nv(range(18..=120))]
+            age: u8,
+            #[nv(length(max = 2), each(not_empty))]
+            tags: Vec<String>,
+            #[nv(nested, rename = "home")]
+            address: Address,
+            #[nv(nested)]
+            offices: Vec<Address>,
+            #[nv(nested)]
+            billing: Option<Address>,
+        }
+
+        let address = |city: &str, postal_code: &str| Address {
+            city: city.to_string(),
+            postal_code: postal_code.to_string(),
+        };
+        let mut account = Account {
+            slug: "ana".to_string(),
+            email: Some("ana@example.com".to_string()),
+            age: 30,
+            tags: vec!["vip".to_string()],
+            address: address("Bandung", "40111"),
+            offices: vec![address("Jakarta", "10110")],
+            billing: None,
+        };
+        assert!(account.validate().is_valid());
+        let address_of = |validator: &Validator<Account>| validator as *const _ as usize;
+        assert_eq!(
+            Account::run_validator(address_of),
+            Account::run_validator(address_of)
+        );
+
+        account.slug = "Ana Maria".to_string();
+        account.email = None;
+        account.age = 12;
+        account.tags = vec![String::new(), "a".to_string(), "b".to_string()];
+        account.address = address("", "40111");
+        account.offices.push(address("Bogor", "161"));
+        account.billing = Some(address("", "1"));
+
+        let result = account.validate();
+        let codes: Vec<(&str, &str)> = result
+            .errors()
+            .iter()
+            .map(|e| (e.path.as_str(), e.code.as_str()))
+            .collect();
+        assert_eq!(
+            codes,
+            vec![
+                ("slug", "is_slug"),
+                ("email", "required"),
+                ("age", "range"),
+                ("tags", "max_len"),
+                ("tags[0]", "not_empty"),
+                ("home.city", "not_empty"),
+                ("offices[1].postal_code", "matches"),
+                ("billing.city", "not_empty"),
+                ("billing.postal_code", "matches"),
+            ]
+        );
+        assert_eq!(
+            result
+                .errors_for("billing.postal_code")
+                .next()
+                .unwrap()
+                .message,
+            "billing.postal_code must have 5 digits"
+        );
+    }
+}
@@ -0,0 +1,169 @@
+// Message templates by error code and locale
+//
+// Templates name their placeholders in braces. `{field}` is the path of the
+// value and is there for every code, the others are the parameters of the
+// check, never the value that failed:
+//
+//   min_len, min                  {min}
+//   max_len, max                  {max}
+//   length, range                 {min} {max}
+//   greater_than, less_than       {expected}, the bound
+//   matches                       {pattern}
+//
+// The other built-in codes have `{field}` only. Locales are matched exactly
+// first, then by their language ("id" for "id-ID") and last by the fallback
+// locale of the catalog.
+
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+pub trait MessageCatalog {
+    fn template(&self, locale: &str, code: &str) -> Option<&str>;
+}
+
+// Code used when a catalog has no template for the code of an error
+pub const INVALID_CODE: &str = "invalid";
+
+const ENGLISH: &[(&str, &str)] = &[
+    ("invalid", "{field} is not valid"),
+    ("required", "{field} is required"),
+    ("not_empty", "{field} must not be empty"),
+    ("min_len", "{field} must have a length of at least {min}"),
+    ("max_len", "{field} must have a length of at most {max}"),
+    (
+        "length",
+        "{field} must have a length between {min} and {max}",
+    ),
+    ("email", "{field} must be a valid email address"),
+    ("matches", "{field} is not in the correct format"),
+    ("min", "{field} must be at least {min}"),
+    ("max", "{field} must be at most {max}"),
+    ("greater_than", "{field} must be greater than {expected}"),
+    ("less_than", "{field} must be less than {expected}"),
+    ("range", "{field} must be between {min} and {max}"),
+    ("unique", "{field} is already taken"),
+    ("exists", "{field} does not exist"),
+];
+
+const INDONESIAN: &[(&str, &str)] = &[
+    ("invalid", "{field} tidak valid"),
+    ("required", "{field} wajib diisi"),
+    ("not_empty", "{field} tidak boleh kosong"),
+    ("min_len", "{field} harus memiliki panjang minimal {min}"),
+    ("max_len", "{field} harus memiliki panjang maksimal {max}"),
+    (
+        "length",
+        "{field} harus memiliki panjang antara {min} dan {max}",
+    ),
+    ("email", "{field} harus berupa alamat email yang valid"),
+    ("matches", "{field} tidak sesuai format"),
+    ("min", "{field} minimal {min}"),
+    ("max", "{field} maksimal {max}"),
+    ("greater_than", "{field} harus lebih besar dari {expected}"),
+    ("less_than", "{field} harus lebih kecil dari {expected}"),
+    ("range", "{field} harus di antara {min} dan {max}"),
+    ("unique", "{field} sudah digunakan"),
+    ("exists", "{field} tidak ditemukan"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Messages {
+    fallback: String,
+    templates: HashMap<String, HashMap<String, String>>,
+}
+
+impl Messages {
+    // An empty catalog falling back to `fallback_locale`
+    pub fn new(fallback_locale: &str) -> Self {
+        Self {
+            fallback: fallback_locale.to_string(),
+            templates: HashMap::new(),
+        }
+    }
+
+    pub fn english() -> Self {
+        Self::new("en").with_all("en", ENGLISH)
+    }
+
+    pub fn indonesian() -> Self {
+        Self::new("id").with_all("id", INDONESIAN)
+    }
+
+    // English and Indonesian, falling back to English
+    pub fn builtin() -> Self {
+        Self::english().with_all("id", INDONESIAN)
+    }
+
+    // The built-in catalog shared by every result that is not given one
+    pub fn shared() -> Arc<Messages> {
+        static SHARED: OnceLock<Arc<Messages>> = OnceLock::new();
+        SHARED.get_or_init(|| Arc::new(Messages::builtin())).clone()
+    }
+
+    pub fn with(mut self, locale: &str, code: &str, template: &str) -> Self {
+        self.templates
+            .entry(locale.to_string())
+            .or_default()
+            .insert(code.to_string(), template.to_string());
+        self
+    }
+
+    pub fn with_all(self, locale: &str, templates: &[(&str, &str)]) -> Self {
+        templates.iter().fold(self, |messages, (code, template)| {
+            messages.with(locale, code, template)
+        })
+    }
+
+    pub fn fallback_locale(&self) -> &str {
+        &self.fallback
+    }
+}
+
+impl MessageCatalog for Messages {
+    fn template(&self, locale: &str, code: &str) -> Option<&str> {
+        locale_candidates(locale)
+            .into_iter()
+            .chain([self.fallback.as_str()])
+            .find_map(|locale| self.templates.get(locale)?.get(code))
+            .map(String::as_str)
+    }
+}
+
+// "id-ID" is tried as "id-ID" and then "id"
+pub(crate) fn locale_candidates(locale: &str) -> Vec<&str> {
+    let mut candidates = vec![locale];
+    if let Some((language, _)) = locale.split_once(['-', '_']) {
+        candidates.push(language);
+    }
+    candidates
+}
+
+pub(crate) fn render(template: &str, field: &str, params: &[(String, String)]) -> String {
+    let mut text = template.replace("{field}", field);
+    for (name, value) in params {
+        text = text.replace(&format!("{{{}}}", name), value);
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builtin_catalogs_cover_the_same_codes() {
+        let messages = Messages::builtin();
+        for (code, _) in ENGLISH {
+            assert!(INDONESIAN.iter().any(|(c, _)| c == code), "{}", code);
+        }
+        assert_eq!(
+            messages.template("id-ID", "required"),
+            Some("{field} wajib diisi")
+        );
+        assert_eq!(
+            messages.template("fr", "required"),
+            Some("{field} is required")
+        );
+        assert_eq!(messages.template("id", "unknown"), None);
+    }
+}
@@ -0,0 +1,13 @@
+mod fluent;
+mod messages;
+mod query;
+mod result;
+mod rule;
+
+pub use fluent::*;
+pub use messages::*;
+pub use query::*;
+#[cfg(feature = "derive")]
+pub use nvcore_derive::NvValidate;
+pub use result::*;
+pub use rule::*;
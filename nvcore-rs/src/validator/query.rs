@@ -0,0 +1,460 @@
+// Rules that look values up in the database
+//
+// An AsyncValidator runs a Validator first and then its query rules, each of
+// which builds a select from the value and asks a QueryExecutor whether it
+// returns any row. `unique` fails when it does, `exists` when it does not:
+//
+//     let validator = AsyncValidator::new(user_validator)
+//         .unique("email", |u: &User| email_query(&u.email))
+//         .exists("category_id", |u: &User| category_query(u.category_id));
+//     let result = validator.validate(&user, &executor).await?;
+//
+// The lookups of independent rules run concurrently on the task awaiting
+// validate. A rule is skipped when its value already failed a check, and
+// with stop_on_first_failure no lookup runs after any failure, lookups still
+// in flight are dropped.
+
+use crate::executor::{ExecuteError, Executor, IterStream, Query};
+use crate::row::{FromRow, Row, RowError, ValueRow};
+use crate::sqlbuilder::NvSelect;
+use crate::validator::{ConditionFn, ValidationResult, Validator};
+use futures_core::Stream;
+use std::convert::Infallible;
+use std::future::{poll_fn, Future};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::Poll;
+
+// Runs the lookups of query rules, implemented over the driver of the
+// application
+pub trait QueryExecutor<Q> {
+    type Error;
+
+    // Whether the select returns at least one row, the future is Send so
+    // validation can run on a multi-threaded runtime
+    fn exists(
+        &self,
+        select: &NvSelect<Q>,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send;
+}
+
+// Any Executor answers lookups by fetching the first row of the select
+impl<Q: Clone, X: Executor<Q>> QueryExecutor<Q> for X {
+    type Error = ExecuteError<X::Error>;
+
+    fn exists(
+        &self,
+        select: &NvSelect<Q>,
+    ) -> impl Future<Output = Result<bool, Self::Error>> + Send {
+        let row = self.fetch_optional::<Found>(Query::from(select));
+        async move { Ok(row.await?.is_some()) }
+    }
+}
+
+// A row whatever its columns
+struct Found;
+
+impl FromRow for Found {
+    fn from_row<R: Row>(_row: &R, _columns: &[String]) -> Result<Self, RowError> {
+        Ok(Found)
+    }
+}
+
+type QueryFn<T, Q> = Box<dyn Fn(&T) -> Arc<NvSelect<Q>> + Send + Sync>;
+type AnswerFn<Q> = Box<dyn Fn(&str, &[Q]) -> bool + Send + Sync>;
+
+struct QueryRule<T, Q> {
+    name: String,
+    code: &'static str,
+    // Whether the value is valid when the select returns rows
+    valid_when_found: bool,
+    query: QueryFn<T, Q>,
+    conditions: Vec<ConditionFn<T>>,
+    messages: Vec<(Option<String>, String)>,
+}
+
+pub struct AsyncValidator<T, Q> {
+    validator: Validator<T>,
+    rules: Vec<QueryRule<T, Q>>,
+    stop_on_first_failure: bool,
+}
+
+impl<T: 'static, Q: 'static> AsyncValidator<T, Q> {
+    pub fn new(validator: Validator<T>) -> Self {
+        Self {
+            validator,
+            rules: Vec::new(),
+            stop_on_first_failure: false,
+        }
+    }
+
+    // Fails with `unique` when the select returns a row
+    pub fn unique<F>(self, name: &str, query: F) -> Self
+    where
+        F: Fn(&T) -> Arc<NvSelect<Q>> + Send + Sync + 'static,
+    {
+        self.rule(name, "unique", false, Box::new(query))
+    }
+
+    // Fails with `exists` when the select returns no row
+    pub fn exists<F>(self, name: &str, query: F) -> Self
+    where
+        F: Fn(&T) -> Arc<NvSelect<Q>> + Send + Sync + 'static,
+    {
+        self.rule(name, "exists", true, Box::new(query))
+    }
+
+    // Guards the query rule declared last
+    pub fn when<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.last_rule("when").conditions.push(Box::new(condition));
+        self
+    }
+
+    pub fn with_message(self, message: &str) -> Self {
+        self.override_message(None, message)
+    }
+
+    pub fn with_locale_message(self, locale: &str, message: &str) -> Self {
+        self.override_message(Some(locale.to_string()), message)
+    }
+
+    pub fn stop_on_first_failure(mut self) -> Self {
+        self.stop_on_first_failure = true;
+        self
+    }
+
+    pub async fn validate<X>(&self, value: &T, executor: &X) -> Result<ValidationResult, X::Error>
+    where
+        X: QueryExecutor<Q>,
+    {
+        self.validate_into(value, ValidationResult::new(), executor)
+            .await
+    }
+
+    // Runs into `result`, whose locale and catalog decide the messages
+    pub async fn validate_into<X>(
+        &self,
+        value: &T,
+        result: ValidationResult,
+        executor: &X,
+    ) -> Result<ValidationResult, X::Error>
+    where
+        X: QueryExecutor<Q>,
+    {
+        let mut result = self.validator.validate_into(value, result);
+        if self.stop_on_first_failure && !result.is_valid() {
+            return Ok(result);
+        }
+
+        let selects: Vec<_> = self
+            .rules
+            .iter()
+            .filter(|rule| rule.conditions.iter().all(|condition| condition(value)))
+            .filter(|rule| result.errors_for(&rule.name).next().is_none())
+            .map(|rule| (rule, (rule.query)(value)))
+            .collect();
+        let mut lookups: Vec<_> = selects
+            .iter()
+            .map(|(rule, select)| (*rule, Box::pin(executor.exists(select))))
+            .collect();
+
+        // Polls every lookup until all are done, the first error or the first
+        // failure when stopping early
+        let mut failed = Vec::new();
+        poll_fn(|cx| {
+            let mut i = 0;
+            while i < lookups.len() {
+                let found = match lookups[i].1.as_mut().poll(cx) {
+                    Poll::Ready(found) => found,
+                    Poll::Pending => {
+                        i += 1;
+                        continue;
+                    }
+                };
+                let (rule, _) = lookups.swap_remove(i);
+                match found {
+                    Ok(found) if found == rule.valid_when_found => {}
+                    Ok(_) => {
+                        failed.push(rule);
+                        if self.stop_on_first_failure {
+                            return Poll::Ready(Ok(()));
+                        }
+                    }
+                    Err(error) => return Poll::Ready(Err(error)),
+                }
+            }
+            if lookups.is_empty() {
+                Poll::Ready(Ok(()))
+            } else {
+                Poll::Pending
+            }
+        })
+        .await?;
+
+        // Reported in declaration order whatever order the lookups ended in
+        for rule in self.rules.iter() {
+            if failed.iter().any(|f| std::ptr::eq(*f, rule)) {
+                result.fail(&rule.name, rule.code, &[], &rule.messages);
+            }
+        }
+        Ok(result)
+    }
+
+    fn rule(
+        mut self,
+        name: &str,
+        code: &'static str,
+        valid_when_found: bool,
+        query: QueryFn<T, Q>,
+    ) -> Self {
+        self.rules.push(QueryRule {
+            name: name.to_string(),
+            code,
+            valid_when_found,
+            query,
+            conditions: Vec::new(),
+            messages: Vec::new(),
+        });
+        self
+    }
+
+    fn last_rule(&mut self, method: &str) -> &mut QueryRule<T, Q> {
+        self.rules
+            .last_mut()
+            .unwrap_or_else(|| panic!("{}() must follow unique() or exists()", method))
+    }
+
+    fn override_message(mut self, locale: Option<String>, message: &str) -> Self {
+        let messages = &mut self.last_rule("with_message").messages;
+        messages.retain(|(l, _)| *l != locale);
+        messages.push((locale, message.to_string()));
+        self
+    }
+}
+
+// In-memory executor for tests, `answer` decides whether a select returns
+// a row from its SQL and values, the row has no columns. Every fetch yields
+// once before answering so concurrent lookups overlap, the most that were in
+// flight at the same time is kept in max_in_flight.
+pub struct MockQueryExecutor<Q> {
+    answer: AnswerFn<Q>,
+    queries: Mutex<Vec<(String, Vec<Q>)>>,
+    in_flight: AtomicUsize,
+    max_in_flight: AtomicUsize,
+}
+
+impl<Q: Clone> MockQueryExecutor<Q> {
+    pub fn new<F>(answer: F) -> Self
+    where
+        F: Fn(&str, &[Q]) -> bool + Send + Sync + 'static,
+    {
+        Self {
+            answer: Box::new(answer),
+            queries: Mutex::new(Vec::new()),
+            in_flight: AtomicUsize::new(0),
+            max_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    // SQL and values of every lookup, in the order they were started
+    pub fn queries(&self) -> Vec<(String, Vec<Q>)> {
+        self.queries.lock().unwrap().clone()
+    }
+
+    pub fn max_in_flight(&self) -> usize {
+        self.max_in_flight.load(Ordering::SeqCst)
+    }
+
+    fn record(&self, query: &Query<Q>) {
+        self.queries
+            .lock()
+            .unwrap()
+            .push((query.sql.clone(), query.values.clone()));
+    }
+
+    fn lookup(&self, query: Query<Q>) -> impl Future<Output = bool> + Send + '_
+    where
+        Q: Send + Sync,
+    {
+        self.record(&query);
+        let mut started = false;
+        poll_fn(move |cx| {
+            if !started {
+                started = true;
+                let in_flight = self.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                self.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                cx.waker().wake_by_ref();
+                return Poll::Pending;
+            }
+            self.in_flight.fetch_sub(1, Ordering::SeqCst);
+            Poll::Ready((self.answer)(&query.sql, &query.values))
+        })
+    }
+}
+
+fn mock_rows<F: FromRow>(found: bool) -> Result<Vec<F>, ExecuteError<Infallible>> {
+    if found {
+        Ok(vec![F::from_row(&ValueRow::new(), &[])?])
+    } else {
+        Ok(Vec::new())
+    }
+}
+
+impl<Q: Clone + Send + Sync> Executor<Q> for MockQueryExecutor<Q> {
+    type Error = Infallible;
+
+    fn fetch_all<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<Q>>,
+    ) -> impl Future<Output = Result<Vec<F>, ExecuteError<Infallible>>> + Send {
+        let found = self.lookup(query.into());
+        async move { mock_rows(found.await) }
+    }
+
+    fn fetch_optional<F: FromRow + Send>(
+        &self,
+        query: impl Into<Query<Q>>,
+    ) -> impl Future<Output = Result<Option<F>, ExecuteError<Infallible>>> + Send {
+        let found = self.lookup(query.into());
+        async move { mock_rows(found.await).map(|rows| rows.into_iter().next()) }
+    }
+
+    fn execute(
+        &self,
+        query: impl Into<Query<Q>>,
+    ) -> impl Future<Output = Result<u64, ExecuteError<Infallible>>> + Send {
+        let found = self.lookup(query.into());
+        async move { Ok(found.await as u64) }
+    }
+
+    // Answered without yielding
+    fn stream<F: FromRow>(
+        &self,
+        query: impl Into<Query<Q>>,
+    ) -> impl Stream<Item = Result<F, ExecuteError<Infallible>>> {
+        let query = query.into();
+        self.record(&query);
+        let rows = match mock_rows((self.answer)(&query.sql, &query.values)) {
+            Ok(rows) => rows.into_iter().map(Ok).collect(),
+            Err(error) => vec![Err(error)],
+        };
+        IterStream::new(rows)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sqlbuilder::{
+        DatabaseDialect, DefaultPostgresParamType, SqlAggregateFunction, SqlOperator,
+    };
+    use std::task::{Context, Waker};
+
+    type Param = DefaultPostgresParamType;
+
+    struct Product {
+        sku: String,
+        category_id: i32,
+    }
+
+    fn lookup(table: &str, column: &str, value: Param) -> Arc<NvSelect<Param>> {
+        NvSelect::new(DatabaseDialect::PostgreSQL)
+            .f(
+                column.to_string(),
+                None,
+                None,
+                SqlAggregateFunction::None,
+                false,
+            )
+            .from()
+            .add_table_with_alias(table, &None)
+            .end_from_table_block()
+            .where_clause()
+            .add_condition(column, &SqlOperator::Equal, value)
+            .unwrap()
+            .end_where_block()
+            .limit(1)
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn test_query_rules() {
+        let validator = AsyncValidator::new(
+            Validator::<Product>::new()
+                .rule_for("sku", |p| &p.sku)
+                .min_len(3)
+                .build(),
+        )
+        .unique("sku", |p| {
+            lookup("products", "sku", Param::String(p.sku.clone()))
+        })
+        .with_locale_message("id", "{field} sudah terdaftar")
+        .exists("category_id", |p| {
+            lookup("categories", "id", Param::Int(p.category_id))
+        })
+        .when(|p| p.category_id != 0);
+
+        let executor = MockQueryExecutor::new(|sql, values: &[Param]| {
+            sql.contains("products") && values == [Param::String("AB-1".to_string())]
+        });
+        let product = Product {
+            sku: "AB-1".to_string(),
+            category_id: 7,
+        };
+        // Spawnable on a multi-threaded runtime
+        fn send<F: Future + Send>(future: F) -> F {
+            future
+        }
+        let result = block_on(send(validator.validate_into(
+            &product,
+            ValidationResult::localized("id"),
+            &executor,
+        )))
+        .unwrap();
+        let errors: Vec<(&str, &str, &str)> = result
+            .errors()
+            .iter()
+            .map(|e| (e.path.as_str(), e.code.as_str(), e.message.as_str()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                ("sku", "unique", "sku sudah terdaftar"),
+                ("category_id", "exists", "category_id tidak ditemukan"),
+            ]
+        );
+        assert_eq!(executor.max_in_flight(), 2);
+        assert_eq!(
+            executor.queries()[0].0,
+            "SELECT sku FROM products WHERE sku = $1 LIMIT 1"
+        );
+
+        // The sku fails its own check, so it is not looked up
+        let executor = MockQueryExecutor::new(|_, _: &[Param]| true);
+        let product = Product {
+            sku: "A".to_string(),
+            category_id: 7,
+        };
+        let result = block_on(validator.validate(&product, &executor)).unwrap();
+        assert!(result.has_error("sku", "min_len"));
+        assert_eq!(executor.queries().len(), 1);
+
+        let validator = validator.stop_on_first_failure();
+        let executor = MockQueryExecutor::new(|_, _: &[Param]| true);
+        let result = block_on(validator.validate(&product, &executor)).unwrap();
+        assert_eq!(result.errors().len(), 1);
+        assert!(executor.queries().is_empty());
+    }
+}
@@ -0,0 +1,205 @@
+// Outcome of a validation run
+//
+// Every failed check is reported with the path of the value it looked at,
+// such as `address.city` or `items[2].sku`, a stable code for programs and a
+// message for people. `params` holds the values the message refers to.
+// Messages are written when a check fails, in the locale and from the
+// catalog the result was created with.
+
+use crate::validator::{locale_candidates, render, MessageCatalog, Messages, INVALID_CODE};
+use std::fmt;
+use std::sync::Arc;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationError {
+    pub path: String,
+    pub code: String,
+    pub message: String,
+    pub params: Vec<(String, String)>,
+}
+
+impl ValidationError {
+    pub fn new(path: &str, code: &str, message: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            code: code.to_string(),
+            message: message.to_string(),
+            params: Vec::new(),
+        }
+    }
+
+    pub fn with_param(mut self, name: &str, value: &str) -> Self {
+        self.params.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", self.path, self.message)
+        }
+    }
+}
+
+// Message templates of one check by locale, None applies to every locale
+pub(crate) type MessageOverrides = [(Option<String>, String)];
+
+#[derive(Clone, Default)]
+pub struct ValidationResult {
+    errors: Vec<ValidationError>,
+    // Empty is the fallback locale of the catalog
+    locale: String,
+    // None is Messages::shared()
+    catalog: Option<Arc<dyn MessageCatalog + Send + Sync>>,
+}
+
+impl ValidationResult {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Messages in `locale` from the built-in catalogs
+    pub fn localized(locale: &str) -> Self {
+        Self {
+            locale: locale.to_string(),
+            ..Self::default()
+        }
+    }
+
+    pub fn with_catalog(mut self, catalog: Arc<dyn MessageCatalog + Send + Sync>) -> Self {
+        self.catalog = Some(catalog);
+        self
+    }
+
+    pub fn locale(&self) -> &str {
+        &self.locale
+    }
+
+    pub fn is_valid(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn errors(&self) -> &[ValidationError] {
+        &self.errors
+    }
+
+    pub fn errors_for<'a>(&'a self, path: &'a str) -> impl Iterator<Item = &'a ValidationError> {
+        self.errors.iter().filter(move |e| e.path == path)
+    }
+
+    pub fn has_error(&self, path: &str, code: &str) -> bool {
+        self.errors_for(path).any(|e| e.code == code)
+    }
+
+    pub fn add(&mut self, error: ValidationError) {
+        self.errors.push(error);
+    }
+
+    pub fn merge(&mut self, other: ValidationResult) {
+        self.errors.extend(other.errors);
+    }
+
+    // Adds the failure of check `code`, the message is the override for the
+    // locale of this result if the check has one, otherwise the template of
+    // the catalog
+    pub(crate) fn fail(
+        &mut self,
+        path: &str,
+        code: &str,
+        params: &[(String, String)],
+        overrides: &MessageOverrides,
+    ) {
+        let message = self.message(path, code, params, overrides);
+        self.add(ValidationError {
+            path: path.to_string(),
+            code: code.to_string(),
+            message,
+            params: params.to_vec(),
+        });
+    }
+
+    fn message(
+        &self,
+        path: &str,
+        code: &str,
+        params: &[(String, String)],
+        overrides: &MessageOverrides,
+    ) -> String {
+        let field = if path.is_empty() { "value" } else { path };
+        let overridden = locale_candidates(&self.locale)
+            .into_iter()
+            .map(Some)
+            .chain([None])
+            .find_map(|locale| {
+                overrides
+                    .iter()
+                    .find(|(l, _)| l.as_deref() == locale)
+                    .map(|(_, template)| template.as_str())
+            });
+        if let Some(template) = overridden {
+            return render(template, field, params);
+        }
+
+        let shared;
+        let catalog: &dyn MessageCatalog = match &self.catalog {
+            Some(catalog) => catalog.as_ref(),
+            None => {
+                shared = Messages::shared();
+                shared.as_ref()
+            }
+        };
+        let template = catalog
+            .template(&self.locale, code)
+            .or_else(|| catalog.template(&self.locale, INVALID_CODE))
+            .unwrap_or("{field} is not valid");
+        render(template, field, params)
+    }
+
+    pub fn into_result(self) -> Result<(), ValidationResult> {
+        if self.is_valid() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Debug for ValidationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ValidationResult")
+            .field("errors", &self.errors)
+            .field("locale", &self.locale)
+            .finish()
+    }
+}
+
+// Results are equal when they hold the same errors
+impl PartialEq for ValidationResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.errors == other.errors
+    }
+}
+
+impl fmt::Display for ValidationResult {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, error) in self.errors.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(f, "{}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ValidationResult {}
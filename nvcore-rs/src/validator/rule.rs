@@ -0,0 +1,399 @@
+// Property rules and the fluent builder that declares them
+//
+// A PropertyRule reads one value out of T (a field, an optional field or
+// every element of a collection) and runs its checks on it. Checks run in
+// the order they were declared, `when` and `unless` guard the whole rule.
+
+use crate::validator::{ValidationResult, Validator};
+use regex::Regex;
+use std::fmt::Display;
+use std::ops::RangeInclusive;
+
+// A rule checks part of T and reports into `result`, `path` is the path of T
+// itself and is empty at the root
+pub trait Rule<T> {
+    fn validate(&self, value: &T, path: &str, result: &mut ValidationResult);
+}
+
+// Length as seen by the length rules, characters for text
+pub trait HasLength {
+    fn length(&self) -> usize;
+}
+
+impl HasLength for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl HasLength for String {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl<E> HasLength for [E] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<E> HasLength for Vec<E> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+pub(crate) enum Values<'a, P: ?Sized> {
+    Missing,
+    One(&'a P),
+    Many(Vec<&'a P>),
+}
+
+// Validators are built once and shared, so every closure is Send + Sync
+pub(crate) type ValuesFn<T, P> = Box<dyn for<'a> Fn(&'a T) -> Values<'a, P> + Send + Sync>;
+type NestedFn<P> = Box<dyn Fn(&P, &str, &mut ValidationResult) + Send + Sync>;
+pub(crate) type ConditionFn<T> = Box<dyn Fn(&T) -> bool + Send + Sync>;
+
+enum Check<P: ?Sized> {
+    Test {
+        code: String,
+        params: Vec<(String, String)>,
+        messages: Vec<(Option<String>, String)>,
+        test: Box<dyn Fn(&P) -> bool + Send + Sync>,
+    },
+    Nested(NestedFn<P>),
+}
+
+pub struct PropertyRule<T, P: ?Sized> {
+    name: String,
+    values: ValuesFn<T, P>,
+    checks: Vec<Check<P>>,
+    conditions: Vec<ConditionFn<T>>,
+    required: bool,
+    stop_on_first_failure: bool,
+}
+
+impl<T, P: ?Sized> PropertyRule<T, P> {
+    pub(crate) fn new(name: &str, values: ValuesFn<T, P>) -> Self {
+        Self {
+            name: name.to_string(),
+            values,
+            checks: Vec::new(),
+            conditions: Vec::new(),
+            required: false,
+            stop_on_first_failure: false,
+        }
+    }
+
+    fn check(&self, value: &P, path: &str, result: &mut ValidationResult) {
+        for check in &self.checks {
+            let failed = match check {
+                Check::Test {
+                    code,
+                    params,
+                    messages,
+                    test,
+                } => {
+                    if test(value) {
+                        false
+                    } else {
+                        result.fail(path, code, params, messages);
+                        true
+                    }
+                }
+                Check::Nested(validate) => {
+                    let before = result.errors().len();
+                    validate(value, path, result);
+                    result.errors().len() > before
+                }
+            };
+            if failed && self.stop_on_first_failure {
+                return;
+            }
+        }
+    }
+}
+
+impl<T, P: ?Sized> Rule<T> for PropertyRule<T, P> {
+    fn validate(&self, value: &T, path: &str, result: &mut ValidationResult) {
+        if !self.conditions.iter().all(|condition| condition(value)) {
+            return;
+        }
+
+        let path = join_path(path, &self.name);
+        match (self.values)(value) {
+            Values::Missing => {
+                if self.required {
+                    result.fail(&path, "required", &[], &[]);
+                }
+            }
+            Values::One(property) => self.check(property, &path, result),
+            Values::Many(items) => {
+                for (i, item) in items.into_iter().enumerate() {
+                    self.check(item, &format!("{}[{}]", path, i), result);
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn join_path(parent: &str, name: &str) -> String {
+    match (parent.is_empty(), name.is_empty()) {
+        (true, _) => name.to_string(),
+        (_, true) => parent.to_string(),
+        _ => format!("{}.{}", parent, name),
+    }
+}
+
+fn is_email(value: &str) -> bool {
+    let Some((local, domain)) = value.split_once('@') else {
+        return false;
+    };
+    let labels: Vec<&str> = domain.split('.').collect();
+    !local.is_empty()
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && !value.chars().any(|c| c.is_whitespace() || c.is_control())
+        && labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_alphanumeric() || c == '-')
+        })
+}
+
+// Declares the checks of one rule. `rule_for` starts the next rule on the
+// same validator and `build` hands the validator back
+pub struct RuleBuilder<T, P: ?Sized> {
+    validator: Validator<T>,
+    rule: PropertyRule<T, P>,
+}
+
+impl<T: 'static, P: ?Sized + 'static> RuleBuilder<T, P> {
+    pub(crate) fn new(validator: Validator<T>, rule: PropertyRule<T, P>) -> Self {
+        Self { validator, rule }
+    }
+
+    pub fn build(self) -> Validator<T> {
+        self.validator.include(self.rule)
+    }
+
+    pub fn rule_for<Q, F>(self, name: &str, property: F) -> RuleBuilder<T, Q>
+    where
+        Q: ?Sized + 'static,
+        F: Fn(&T) -> &Q + Send + Sync + 'static,
+    {
+        self.build().rule_for(name, property)
+    }
+
+    pub fn rule_for_option<Q, F>(self, name: &str, property: F) -> RuleBuilder<T, Q>
+    where
+        Q: 'static,
+        F: Fn(&T) -> &Option<Q> + Send + Sync + 'static,
+    {
+        self.build().rule_for_option(name, property)
+    }
+
+    pub fn rule_for_each<Q, F>(self, name: &str, items: F) -> RuleBuilder<T, Q>
+    where
+        Q: 'static,
+        F: Fn(&T) -> &[Q] + Send + Sync + 'static,
+    {
+        self.build().rule_for_each(name, items)
+    }
+
+    pub fn must<F>(self, code: &str, test: F) -> Self
+    where
+        F: Fn(&P) -> bool + Send + Sync + 'static,
+    {
+        self.test(code, Vec::new(), test)
+    }
+
+    // Replaces the message of the check declared last in every locale,
+    // `{field}` and the check parameters such as `{max}` are filled in
+    pub fn with_message(self, message: &str) -> Self {
+        self.override_message(None, message)
+    }
+
+    // Replaces the message of the check declared last in `locale` only, it
+    // wins over with_message
+    pub fn with_locale_message(self, locale: &str, message: &str) -> Self {
+        self.override_message(Some(locale.to_string()), message)
+    }
+
+    fn override_message(mut self, locale: Option<String>, message: &str) -> Self {
+        let Some(Check::Test { messages, .. }) = self.rule.checks.last_mut() else {
+            panic!(
+                "with_message() must follow a check of rule {}",
+                self.rule.name
+            );
+        };
+        messages.retain(|(l, _)| *l != locale);
+        messages.push((locale, message.to_string()));
+        self
+    }
+
+    pub fn when<F>(mut self, condition: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.rule.conditions.push(Box::new(condition));
+        self
+    }
+
+    pub fn unless<F>(self, condition: F) -> Self
+    where
+        F: Fn(&T) -> bool + Send + Sync + 'static,
+    {
+        self.when(move |value| !condition(value))
+    }
+
+    // An optional value that is None fails with `required` instead of
+    // skipping the checks
+    pub fn required(mut self) -> Self {
+        self.rule.required = true;
+        self
+    }
+
+    pub fn stop_on_first_failure(mut self) -> Self {
+        self.rule.stop_on_first_failure = true;
+        self
+    }
+
+    fn test<F>(mut self, code: &str, params: Vec<(String, String)>, test: F) -> Self
+    where
+        F: Fn(&P) -> bool + Send + Sync + 'static,
+    {
+        self.rule.checks.push(Check::Test {
+            code: code.to_string(),
+            params,
+            messages: Vec::new(),
+            test: Box::new(test),
+        });
+        self
+    }
+}
+
+impl<T: 'static, P: 'static> RuleBuilder<T, P> {
+    // Validates the value with its own validator, errors are reported under
+    // the path of this rule
+    pub fn set_validator(mut self, validator: Validator<P>) -> Self {
+        self.rule
+            .checks
+            .push(Check::Nested(Box::new(move |value, path, result| {
+                Rule::validate(&validator, value, path, result)
+            })));
+        self
+    }
+}
+
+impl<T: 'static, P: ?Sized + HasLength + 'static> RuleBuilder<T, P> {
+    pub fn not_empty(self) -> Self {
+        self.test("not_empty", Vec::new(), |value| value.length() > 0)
+    }
+
+    pub fn min_len(self, min: usize) -> Self {
+        self.test("min_len", params(&[("min", &min)]), move |value| {
+            value.length() >= min
+        })
+    }
+
+    pub fn max_len(self, max: usize) -> Self {
+        self.test("max_len", params(&[("max", &max)]), move |value| {
+            value.length() <= max
+        })
+    }
+
+    pub fn length(self, min: usize, max: usize) -> Self {
+        if min > max {
+            panic!("length({}, {}) has min greater than max", min, max);
+        }
+        self.test(
+            "length",
+            params(&[("min", &min), ("max", &max)]),
+            move |value| (min..=max).contains(&value.length()),
+        )
+    }
+}
+
+impl<T: 'static, P: ?Sized + AsRef<str> + 'static> RuleBuilder<T, P> {
+    pub fn email(self) -> Self {
+        self.test("email", Vec::new(), |value| is_email(value.as_ref()))
+    }
+
+    pub fn matches(self, pattern: &str) -> Self {
+        let regex = Regex::new(pattern)
+            .unwrap_or_else(|e| panic!("invalid pattern {} in matches(): {}", pattern, e));
+        self.test("matches", params(&[("pattern", &pattern)]), move |value| {
+            regex.is_match(value.as_ref())
+        })
+    }
+}
+
+impl<T: 'static, P: PartialOrd + Display + Send + Sync + 'static> RuleBuilder<T, P> {
+    pub fn min(self, min: P) -> Self {
+        self.test("min", params(&[("min", &min)]), move |value| *value >= min)
+    }
+
+    pub fn max(self, max: P) -> Self {
+        self.test("max", params(&[("max", &max)]), move |value| *value <= max)
+    }
+
+    pub fn greater_than(self, bound: P) -> Self {
+        self.test("greater_than", params(&[("expected", &bound)]), move |value| {
+            *value > bound
+        })
+    }
+
+    pub fn less_than(self, bound: P) -> Self {
+        self.test("less_than", params(&[("expected", &bound)]), move |value| {
+            *value < bound
+        })
+    }
+
+    pub fn range(self, range: RangeInclusive<P>) -> Self {
+        let params = params(&[("min", range.start()), ("max", range.end())]);
+        self.test("range", params, move |value| range.contains(value))
+    }
+}
+
+fn params(values: &[(&str, &dyn Display)]) -> Vec<(String, String)> {
+    values
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_shapes() {
+        for valid in [
+            "a@b.co",
+            "first.last+tag@mail.example.org",
+            "x@xn--80ak6aa92e.com",
+        ] {
+            assert!(is_email(valid), "{}", valid);
+        }
+        for invalid in [
+            "",
+            "plain",
+            "@b.co",
+            "a@",
+            "a@b",
+            "a@@b.co",
+            "a b@c.co",
+            ".a@b.co",
+            "a..b@c.co",
+            "a@-b.co",
+            "a@b..co",
+        ] {
+            assert!(!is_email(invalid), "{}", invalid);
+        }
+    }
+}
@@ -44,51 +44,54 @@ fn main() {
           false,
       )
         .from()
-        .add_table_with_alias(&"table".to_string(), &Some("a".to_string()))
+        .add_table_with_alias("table", &Some("a".to_string()))
         .end_from_table_block()
         .where_clause()
         .add_condition(
-            &"a.field1".to_string(),
+            "a.field1",
             &SqlOperator::Equal,
             DefaultPostgresParamType::Int(1),
         )
+        .unwrap()
         .or()
         .add_condition(
-            &"a.field2".to_string(),
+            "a.field2",
             &SqlOperator::Equal,
             DefaultPostgresParamType::String("Hello world".to_string()),
         )
+        .unwrap()
         .or()
         .add_condition(
-            &"a.field3".to_string(),
+            "a.field3",
             &SqlOperator::Equal,
             DefaultPostgresParamType::TimePoint(SystemTime::now()),
         )
+        .unwrap()
         .or()
         .add_condition(
-            &"a.field4".to_string(),
+            "a.field4",
             &SqlOperator::Equal,
             DefaultPostgresParamType::BigInt(192738124),
         )
+        .unwrap()
         .or()
         .add_condition(
-            &"a.field5".to_string(),
+            "a.field5",
             &SqlOperator::Equal,
             DefaultPostgresParamType::Float(0.5),
         )
+        .unwrap()
         .end_where_block();
 
     println!("\nSQL QUERY:\n\n {}\n", select.generate_query(true));
 
-    println!("PARAMETER VALUES: {}", "");
+    println!("PARAMETER VALUES: ");
 
-    let pv = select.values();
-    {
-        let values = pv.read().unwrap();
-        for v in values.iter() {
-            println!("{}", v);
-        }
+    for v in select.values() {
+        println!("{}", v);
     }
 
+    println!("\nDEBUG SQL:\n\n {}\n", select.to_debug_sql());
+
     // println!("Query: {}", select.generate_query());
 }
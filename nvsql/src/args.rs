@@ -0,0 +1,165 @@
+// Command line of nvsql
+
+use nvcore::sqlbuilder::{CommaPlacement, DatabaseDialect, FormatOptions, KeywordCase};
+
+pub const USAGE: &str = "\
+Usage: nvsql [OPTIONS] [FILE...]
+
+Reads SELECT statements from the files, or from stdin when no file or `-` is
+given, and writes them formatted to stdout. Statements are separated by `;`.
+Comments above a statement are kept as they are, a comment inside a statement
+is reported as an error.
+
+Options:
+  --dialect <postgres|oracle>  Dialect of the input, also the output dialect
+                               unless --to is given [default: postgres]
+  --to <DIALECT>               Convert to postgres, oracle, mysql, sqlite or
+                               sqlserver
+  --check                      Print the inputs that are not formatted and
+                               exit with 1 instead of writing the output
+  --compact                    One line per statement
+  --indent <N>                 Spaces per indentation level [default: 2]
+  --tabs                       Indent with one tab per level
+  --keyword-case <CASE>        upper, lower or preserve [default: preserve]
+  --line-width <N>             Width a clause may take before its items are
+                               put one per line, 0 always breaks [default: 80]
+  --leading-commas             Put commas at the start of list lines
+  --inline-conditions          Keep WHERE conditions on one line when they fit
+  -h, --help                   Print this help
+";
+
+pub enum Command {
+    Help,
+    Run(Args),
+}
+
+// The parser reads every supported input dialect, --dialect only sets the
+// default output dialect
+pub struct Args {
+    pub target: DatabaseDialect,
+    pub check: bool,
+    pub format: FormatOptions,
+    // Empty reads stdin
+    pub inputs: Vec<String>,
+}
+
+pub fn parse_args<I: IntoIterator<Item = String>>(args: I) -> Result<Command, String> {
+    let mut dialect = DatabaseDialect::PostgreSQL;
+    let mut target = None;
+    let mut check = false;
+    let mut format = FormatOptions::default();
+    let mut inputs = Vec::new();
+
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        // --option=value is read as --option value
+        let (name, mut inline_value) = match arg.split_once('=') {
+            Some((name, value)) if name.starts_with("--") => {
+                (name.to_string(), Some(value.to_string()))
+            }
+            _ => (arg.clone(), None),
+        };
+        let mut value = |name: &str| {
+            inline_value
+                .take()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("{} expects a value", name))
+        };
+
+        match name.as_str() {
+            "-h" | "--help" => return Ok(Command::Help),
+            "--dialect" => {
+                dialect = match parse_dialect(&value(&name)?)? {
+                    dialect @ (DatabaseDialect::PostgreSQL | DatabaseDialect::Oracle) => dialect,
+                    other => return Err(format!("{} input is not supported", other)),
+                }
+            }
+            "--to" => target = Some(parse_dialect(&value(&name)?)?),
+            "--check" => check = true,
+            "--compact" => format = FormatOptions::compact(),
+            "--indent" => format = format.indent(parse_number(&name, &value(&name)?)?, ' '),
+            "--tabs" => format = format.indent(1, '\t'),
+            "--keyword-case" => {
+                format = format.keyword_case(match value(&name)?.as_str() {
+                    "upper" => KeywordCase::Upper,
+                    "lower" => KeywordCase::Lower,
+                    "preserve" => KeywordCase::Preserve,
+                    other => return Err(format!("unknown keyword case {}", other)),
+                })
+            }
+            "--line-width" => {
+                format = format.line_width(parse_number(&name, &value(&name)?)? as usize)
+            }
+            "--leading-commas" => format = format.comma_placement(CommaPlacement::Leading),
+            "--inline-conditions" => format = format.condition_per_line(false),
+            "-" => inputs.push(arg),
+            option if option.starts_with('-') => return Err(format!("unknown option {}", option)),
+            _ => inputs.push(arg),
+        }
+
+        if inline_value.is_some() {
+            return Err(format!("{} does not take a value", name));
+        }
+    }
+
+    Ok(Command::Run(Args {
+        target: target.unwrap_or(dialect),
+        check,
+        format,
+        inputs,
+    }))
+}
+
+fn parse_dialect(name: &str) -> Result<DatabaseDialect, String> {
+    match name.to_ascii_lowercase().as_str() {
+        "postgres" | "postgresql" | "pg" => Ok(DatabaseDialect::PostgreSQL),
+        "oracle" => Ok(DatabaseDialect::Oracle),
+        "mysql" => Ok(DatabaseDialect::MySQL),
+        "sqlite" => Ok(DatabaseDialect::SQLite),
+        "sqlserver" | "mssql" => Ok(DatabaseDialect::SqlServer),
+        _ => Err(format!("unknown dialect {}", name)),
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{} expects a number, got {}", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        match parse_args(args.iter().map(|a| a.to_string()))? {
+            Command::Run(args) => Ok(args),
+            Command::Help => Err("help".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = parse(&["--dialect=oracle", "--to", "mysql", "--check", "a.sql", "-"]).unwrap();
+        assert_eq!(args.target, DatabaseDialect::MySQL);
+        assert!(args.check);
+        assert_eq!(args.inputs, vec!["a.sql", "-"]);
+
+        let args = parse(&["--dialect", "oracle"]).unwrap();
+        assert_eq!(args.target, DatabaseDialect::Oracle);
+
+        assert_eq!(
+            parse(&["--dialect", "mysql"]).err().unwrap(),
+            "MySQL input is not supported"
+        );
+        assert_eq!(
+            parse(&["--check=yes"]).err().unwrap(),
+            "--check does not take a value"
+        );
+        assert_eq!(parse(&["--to"]).err().unwrap(), "--to expects a value");
+        assert!(matches!(
+            parse_args(vec!["-h".to_string()]),
+            Ok(Command::Help)
+        ));
+    }
+}
@@ -0,0 +1,315 @@
+// nvsql, formats SELECT statements and converts them between dialects
+//
+// Statements are parsed into the select tree with
+// parse_select_keeping_literals and written back with the builder renderer.
+// Numbers and DATE / TIMESTAMP literals are written as they are in the input,
+// strings and booleans in the syntax of the output dialect. Exit status is 0 on success, 1 when --check finds an input
+// that is not formatted and 2 on usage, read or parse errors.
+
+mod args;
+
+use args::{parse_args, Args, Command, USAGE};
+use nvcore::sqlbuilder::{
+    paging_requires_order_by, parse_select_keeping_literals, DefaultPostgresParamType, Renderer,
+};
+use std::fmt;
+use std::io::Read;
+use std::ops::Range;
+
+fn main() {
+    std::process::exit(run());
+}
+
+fn run() -> i32 {
+    match parse_args(std::env::args().skip(1)) {
+        Ok(Command::Help) => {
+            print!("{}", USAGE);
+            0
+        }
+        Ok(Command::Run(args)) => run_with(&args),
+        Err(message) => {
+            eprintln!("nvsql: {}\n\n{}", message, USAGE);
+            2
+        }
+    }
+}
+
+fn run_with(args: &Args) -> i32 {
+    let inputs = if args.inputs.is_empty() {
+        vec!["-".to_string()]
+    } else {
+        args.inputs.clone()
+    };
+
+    let mut status = 0;
+    for input in &inputs {
+        let name = if input == "-" { "<stdin>" } else { input };
+        let source = match read_input(input) {
+            Ok(source) => source,
+            Err(error) => {
+                eprintln!("nvsql: {}: {}", name, error);
+                status = 2;
+                continue;
+            }
+        };
+
+        match format_source(&source, args) {
+            Ok(formatted) if args.check => {
+                if formatted.trim_end() != source.trim_end() {
+                    println!("{}", name);
+                    status = status.max(1);
+                }
+            }
+            Ok(formatted) => print!("{}", formatted),
+            Err(error) => {
+                eprintln!("nvsql: {}:{}", name, error);
+                status = 2;
+            }
+        }
+    }
+    status
+}
+
+fn read_input(input: &str) -> std::io::Result<String> {
+    if input == "-" {
+        let mut source = String::new();
+        std::io::stdin().read_to_string(&mut source)?;
+        Ok(source)
+    } else {
+        std::fs::read_to_string(input)
+    }
+}
+
+// Error with its 1-based line and column in the input
+#[derive(Debug, PartialEq)]
+struct SourceError {
+    line: usize,
+    column: usize,
+    message: String,
+}
+
+impl SourceError {
+    fn at(source: &str, offset: usize, message: String) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |p| p + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+            message,
+        }
+    }
+}
+
+impl fmt::Display for SourceError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+fn format_source(source: &str, args: &Args) -> Result<String, SourceError> {
+    let mut output = String::new();
+    for (i, statement) in split_statements(source).into_iter().enumerate() {
+        if i > 0 && args.format.is_pretty() {
+            output.push('\n');
+        }
+        // Comments above a statement are written back as they are, the tree
+        // has no place for comments inside one
+        let mut body = 0;
+        for comment in &statement.comments {
+            let text = &statement.text[comment.clone()];
+            if text.starts_with("/*") && (text.len() < 4 || !text.ends_with("*/")) {
+                return Err(SourceError::at(
+                    source,
+                    statement.offset + comment.start,
+                    "unterminated comment".to_string(),
+                ));
+            }
+            if !statement.text[body..comment.start].trim().is_empty() {
+                return Err(SourceError::at(
+                    source,
+                    statement.offset + comment.start,
+                    "comments inside a statement are not kept, move it above the statement"
+                        .to_string(),
+                ));
+            }
+            output.push_str(text);
+            output.push('\n');
+            body = comment.end;
+        }
+        let text = &statement.text[body..];
+        if text.trim().is_empty() {
+            continue;
+        }
+
+        let offset = statement.offset + body;
+        let stmt = parse_select_keeping_literals::<DefaultPostgresParamType>(text)
+            .map_err(|e| SourceError::at(source, offset + e.position, e.message))?;
+        if stmt.order_by.is_empty() && paging_requires_order_by(&args.target, stmt.offset) {
+            return Err(SourceError::at(
+                source,
+                offset,
+                format!("{} requires ORDER BY when OFFSET is used", args.target),
+            ));
+        }
+
+        output.push_str(
+            &Renderer::new(args.target)
+                .format(args.format)
+                .render_inline(&stmt),
+        );
+        if statement.terminated {
+            output.push(';');
+        }
+        output.push('\n');
+    }
+    Ok(output)
+}
+
+struct Statement<'a> {
+    text: &'a str,
+    offset: usize,
+    terminated: bool,
+    // Byte ranges of the comments in `text`, a line comment ends before its
+    // newline
+    comments: Vec<Range<usize>>,
+}
+
+// Splits on semicolons outside quotes and comments, blank statements are
+// dropped
+fn split_statements(source: &str) -> Vec<Statement<'_>> {
+    let mut statements = Vec::new();
+    let mut start = 0;
+    let mut comments = Vec::new();
+    let mut chars = source.char_indices().peekable();
+
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\'' | '"' => {
+                for (_, q) in chars.by_ref() {
+                    if q == c {
+                        break;
+                    }
+                }
+            }
+            '-' if chars.peek().map(|(_, n)| *n) == Some('-') => {
+                let end = source[i..].find('\n').map_or(source.len(), |n| i + n);
+                while chars.next_if(|(n, _)| *n < end).is_some() {}
+                comments.push(i - start..end - start);
+            }
+            '/' if chars.peek().map(|(_, n)| *n) == Some('*') => {
+                chars.next();
+                let mut previous = ' ';
+                let mut end = source.len();
+                for (n, next) in chars.by_ref() {
+                    if previous == '*' && next == '/' {
+                        end = n + 1;
+                        break;
+                    }
+                    previous = next;
+                }
+                comments.push(i - start..end - start);
+            }
+            ';' => {
+                statements.push(Statement {
+                    text: &source[start..i],
+                    offset: start,
+                    terminated: true,
+                    comments: std::mem::take(&mut comments),
+                });
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    statements.push(Statement {
+        text: &source[start..],
+        offset: start,
+        terminated: false,
+        comments,
+    });
+
+    statements
+        .into_iter()
+        .filter(|s| !s.text.trim().is_empty())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nvcore::sqlbuilder::{DatabaseDialect, FormatOptions};
+
+    fn args(target: DatabaseDialect) -> Args {
+        Args {
+            target,
+            check: false,
+            format: FormatOptions::default(),
+            inputs: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_format_and_convert() {
+        let source = "select u.id, u.name from users u where u.name = 'a;b' and u.created_at > DATE '2024-01-31'\n\
+                      order by u.id fetch first 10 rows only;\n\nSELECT 1 FROM dual";
+        let oracle = args(DatabaseDialect::Oracle);
+        assert_eq!(
+            format_source(source, &oracle).unwrap(),
+            "SELECT u.id, u.name\n\
+             FROM users u\n\
+             WHERE\n  u.name = 'a;b'\n  AND u.created_at > DATE '2024-01-31'\n\
+             ORDER BY u.id\n\
+             FETCH FIRST 10 ROWS ONLY;\n\
+             \n\
+             SELECT 1\n\
+             FROM dual\n"
+        );
+
+        let postgres = args(DatabaseDialect::PostgreSQL);
+        let converted = format_source(source, &postgres).unwrap();
+        assert!(converted.contains("AND u.created_at > DATE '2024-01-31'\n"));
+        assert!(converted.contains("ORDER BY u.id\nLIMIT 10;\n"));
+        assert_eq!(format_source(&converted, &postgres).unwrap(), converted);
+
+        assert_eq!(
+            format_source("SELECT a FROM t;\nSELECT b\nFROM t GROUP BY b", &postgres)
+                .unwrap_err()
+                .to_string(),
+            "3:8: GROUP BY is not supported by the builder"
+        );
+    }
+
+    #[test]
+    fn test_numbers_and_typed_literals_are_kept() {
+        let postgres = args(DatabaseDialect::PostgreSQL);
+        let source = "SELECT 1e3, -0.10\n\
+                      FROM t\n\
+                      WHERE\n  \
+                      a = 12345678901234567.89\n  \
+                      AND b = 99999999999999999999\n  \
+                      AND c > DATE '2024-01-31'\n  \
+                      AND d < TIMESTAMP '2024-01-31 10:00:00+02'\n  \
+                      AND e = 0.10;\n";
+        assert_eq!(format_source(source, &postgres).unwrap(), source);
+
+        let mysql = args(DatabaseDialect::MySQL);
+        assert_eq!(
+            format_source("SELECT id FROM t WHERE a = 1e3 AND b = 'x'", &mysql).unwrap(),
+            "SELECT id\nFROM t\nWHERE\n  a = 1e3\n  AND b = 'x'\n"
+        );
+    }
+
+    #[test]
+    fn test_comments_above_statements_are_kept() {
+        let postgres = args(DatabaseDialect::PostgreSQL);
+        let source = "-- active users\n/* paged */\nSELECT id\nFROM users;\n\n-- trailing\n";
+        assert_eq!(format_source(source, &postgres).unwrap(), source);
+
+        assert_eq!(
+            format_source("SELECT id -- key\nFROM users", &postgres)
+                .unwrap_err()
+                .to_string(),
+            "1:11: comments inside a statement are not kept, move it above the statement"
+        );
+    }
+}